@@ -0,0 +1,121 @@
+//! Benchmarks comparing parsing OVER to parsing the equivalent JSON.
+//!
+//! Run with `cargo bench`.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use over::obj::Obj;
+use std::{env, fs, str::FromStr};
+
+// Equivalent OVER and JSON documents.
+struct Doc {
+    name: &'static str,
+    over: String,
+    json: String,
+}
+
+fn ints(n: usize) -> Doc {
+    let values: Vec<String> = (0..n).map(|i| (i * 7919 % 100_000).to_string()).collect();
+
+    Doc {
+        name: "ints",
+        over: format!("values: [{}]", values.join(" ")),
+        json: format!("{{\"values\": [{}]}}", values.join(", ")),
+    }
+}
+
+fn strs(n: usize) -> Doc {
+    let values: Vec<String> = (0..n)
+        .map(|i| format!("\"string number {} without any escapes\"", i))
+        .collect();
+
+    Doc {
+        name: "strs",
+        over: format!("values: [{}]", values.join(" ")),
+        json: format!("{{\"values\": [{}]}}", values.join(", ")),
+    }
+}
+
+fn objs(n: usize) -> Doc {
+    let mut over = String::from("values: [\n");
+    let mut json = String::from("{\"values\": [\n");
+
+    for i in 0..n {
+        over.push_str(&format!(
+            "    {{ id: {} name: \"item {}\" active: {} ratio: 0.{} tags: (\"a\" \"b\") }}\n",
+            i,
+            i,
+            i % 2 == 0,
+            i % 100
+        ));
+        json.push_str(&format!(
+            "    {}{{\"id\": {}, \"name\": \"item {}\", \"active\": {}, \"ratio\": 0.{}, \
+             \"tags\": [\"a\", \"b\"]}}\n",
+            if i == 0 { "" } else { "," },
+            i,
+            i,
+            i % 2 == 0,
+            i % 100
+        ));
+    }
+
+    over.push(']');
+    json.push_str("]}");
+
+    Doc {
+        name: "objs",
+        over,
+        json,
+    }
+}
+
+fn bench_docs(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse");
+
+    for doc in &[ints(100_000), strs(20_000), objs(5_000)] {
+        group.throughput(Throughput::Bytes(doc.over.len() as u64));
+        group.bench_with_input(BenchmarkId::new("over", doc.name), &doc.over, |b, over| {
+            b.iter(|| Obj::from_str(over).unwrap())
+        });
+
+        group.throughput(Throughput::Bytes(doc.json.len() as u64));
+        group.bench_with_input(BenchmarkId::new("json", doc.name), &doc.json, |b, json| {
+            b.iter(|| serde_json::from_str::<serde_json::Value>(json).unwrap())
+        });
+    }
+
+    group.finish();
+}
+
+// Parses a large generated file pulled in with an `<Arr>` include.
+fn bench_arr_include(c: &mut Criterion) {
+    let dir = env::temp_dir().join(format!("over_bench_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+
+    let values: Vec<String> = (0..200_000).map(|i| i.to_string()).collect();
+    fs::write(dir.join("values.over"), values.join("\n")).unwrap();
+    fs::write(dir.join("main.over"), "values: <Arr \"values.over\">").unwrap();
+    fs::write(
+        dir.join("values.json"),
+        format!("{{\"values\": [{}]}}", values.join(",\n")),
+    )
+    .unwrap();
+
+    let main = dir.join("main.over");
+    let main = main.to_str().unwrap();
+    let json = dir.join("values.json");
+
+    let mut group = c.benchmark_group("arr_include");
+    group.bench_function("over", |b| b.iter(|| Obj::from_file(main).unwrap()));
+    group.bench_function("json", |b| {
+        b.iter(|| {
+            let contents = fs::read_to_string(&json).unwrap();
+            serde_json::from_str::<serde_json::Value>(&contents).unwrap()
+        })
+    });
+    group.finish();
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+criterion_group!(benches, bench_docs, bench_arr_include);
+criterion_main!(benches);
@@ -0,0 +1,498 @@
+//! Derive macros for `over::FromOver` and `over::IntoOver`.
+//!
+//! Use them through the `derive` feature of `over`. See `over::convert` for how types are read and
+//! written and for the attributes they accept.
+
+extern crate proc_macro;
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{
+    parse_macro_input, parse_quote, Attribute, Data, DataEnum, DeriveInput, Error, ExprPath, Field,
+    Fields, Generics, Ident, LitStr, Result, Token, Type,
+};
+
+/// Derives `FromOver`.
+#[proc_macro_derive(FromOver, attributes(over))]
+pub fn derive_from_over(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    from_over(&input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// Derives `IntoOver`.
+#[proc_macro_derive(IntoOver, attributes(over))]
+pub fn derive_into_over(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    into_over(&input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+#[derive(Default)]
+struct Attrs {
+    rename: Option<LitStr>,
+    default: Option<DefaultAttr>,
+    flatten: bool,
+    frac: Option<TokenStream2>,
+}
+
+enum DefaultAttr {
+    Trait,
+    Path(ExprPath),
+}
+
+impl Attrs {
+    fn parse(attrs: &[Attribute]) -> Result<Self> {
+        let mut result = Attrs::default();
+
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident("over")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    result.rename = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("default") {
+                    result.default = Some(if meta.input.peek(Token![=]) {
+                        let path: LitStr = meta.value()?.parse()?;
+                        DefaultAttr::Path(path.parse()?)
+                    } else {
+                        DefaultAttr::Trait
+                    });
+                } else if meta.path.is_ident("flatten") {
+                    result.flatten = true;
+                } else if meta.path.is_ident("frac") {
+                    let policy: LitStr = meta.value()?.parse()?;
+                    result.frac = Some(match policy.value().as_str() {
+                        "nearest" => quote!(::over::convert::FracPolicy::Nearest),
+                        "exact" => quote!(::over::convert::FracPolicy::Exact),
+                        _ => {
+                            return Err(Error::new(
+                                policy.span(),
+                                "expected \"nearest\" or \"exact\"",
+                            ))
+                        }
+                    });
+                } else {
+                    return Err(meta.error("unknown over attribute"));
+                }
+                Ok(())
+            })?;
+        }
+
+        Ok(result)
+    }
+
+    // Parses attributes which may only rename, such as those of enum variants.
+    fn parse_rename(attrs: &[Attribute]) -> Result<Option<LitStr>> {
+        let result = Self::parse(attrs)?;
+        if result.default.is_some() || result.flatten || result.frac.is_some() {
+            return Err(Error::new_spanned(
+                &attrs[0],
+                "only `rename` can be used here",
+            ));
+        }
+
+        Ok(result.rename)
+    }
+}
+
+// The name of a field or variant in .over files.
+fn over_name(ident: &Ident, rename: Option<LitStr>) -> LitStr {
+    rename.unwrap_or_else(|| {
+        let name = ident.to_string();
+        LitStr::new(name.trim_start_matches("r#"), ident.span())
+    })
+}
+
+fn add_bounds(generics: &Generics, bound: TokenStream2) -> Generics {
+    let mut generics = generics.clone();
+    let params: Vec<Ident> = generics
+        .type_params()
+        .map(|param| param.ident.clone())
+        .collect();
+
+    let where_clause = generics.make_where_clause();
+    for param in params {
+        where_clause.predicates.push(parse_quote!(#param: #bound));
+    }
+
+    generics
+}
+
+fn from_over(input: &DeriveInput) -> Result<TokenStream2> {
+    let name = &input.ident;
+    let generics = add_bounds(&input.generics, quote!(::over::convert::FromOver));
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let body = match input.data {
+        Data::Struct(ref data) => read_fields(&data.fields, quote!(Self))?,
+        Data::Enum(ref data) => {
+            let mut names = vec![];
+            let mut unit_arms = vec![];
+            let mut arms = vec![];
+
+            let has_fields = has_fields(data);
+
+            for variant in &data.variants {
+                let ident = &variant.ident;
+                let over_name = over_name(ident, Attrs::parse_rename(&variant.attrs)?);
+                names.push(over_name.clone());
+
+                if let Fields::Unit = variant.fields {
+                    unit_arms.push(quote!(#over_name => ::std::result::Result::Ok(Self::#ident),));
+                }
+                if has_fields {
+                    let body = read_fields(&variant.fields, quote!(Self::#ident))?;
+                    arms.push(quote! {
+                        #over_name => (|| -> ::over::OverResult<Self> { #body })()
+                            .map_err(|e| ::over::convert::in_field(e, #over_name)),
+                    });
+                }
+            }
+
+            let expected = if arms.is_empty() {
+                quote!(::over::types::Type::Str)
+            } else {
+                quote!(::over::types::Type::Obj)
+            };
+
+            let obj_arm = if arms.is_empty() {
+                quote!()
+            } else {
+                quote! {
+                    ::over::value::Value::Obj(ref obj) => {
+                        let (name, value) = ::over::convert::variant_field(obj)?;
+                        let value = &value;
+                        match name.as_str() {
+                            #(#arms)*
+                            _ => ::std::result::Result::Err(
+                                ::over::convert::unknown_variant(&name, VARIANTS),
+                            ),
+                        }
+                    }
+                }
+            };
+
+            quote! {
+                const VARIANTS: &[&str] = &[#(#names),*];
+
+                match *value {
+                    ::over::value::Value::Str(ref name) => match name.as_str() {
+                        #(#unit_arms)*
+                        _ => ::std::result::Result::Err(
+                            ::over::convert::unknown_variant(name, VARIANTS),
+                        ),
+                    },
+                    #obj_arm
+                    ref other => ::std::result::Result::Err(::over::OverError::TypeMismatch(
+                        #expected,
+                        other.get_type(),
+                    )),
+                }
+            }
+        }
+        Data::Union(_) => {
+            return Err(Error::new_spanned(
+                input,
+                "FromOver cannot be derived for unions",
+            ))
+        }
+    };
+
+    Ok(quote! {
+        impl #impl_generics ::over::convert::FromOver for #name #ty_generics #where_clause {
+            fn from_over(value: &::over::value::Value) -> ::over::OverResult<Self> {
+                #body
+            }
+        }
+    })
+}
+
+// Returns statements which read `fields` from `value: &Value` and return `OverResult<Self>`,
+// using `path` to construct `Self`.
+fn read_fields(fields: &Fields, path: TokenStream2) -> Result<TokenStream2> {
+    match *fields {
+        Fields::Named(ref fields) => {
+            let mut inits = vec![];
+            for field in &fields.named {
+                let ident = field.ident.as_ref().unwrap();
+                let expr = read_named_field(field)?;
+                inits.push(quote!(#ident: #expr));
+            }
+
+            Ok(quote! {
+                #[allow(unused_variables)]
+                let obj = value.get_obj()?;
+                ::std::result::Result::Ok(#path { #(#inits),* })
+            })
+        }
+        Fields::Unnamed(ref fields) if fields.unnamed.len() == 1 => {
+            let field = &fields.unnamed[0];
+            let convert = convert(field, quote!(value), tuple_frac(field)?);
+
+            Ok(quote!(::std::result::Result::Ok(#path(#convert?))))
+        }
+        Fields::Unnamed(ref fields) => {
+            let len = fields.unnamed.len();
+            let mut elements = vec![];
+            for (i, field) in fields.unnamed.iter().enumerate() {
+                let index = i.to_string();
+                elements.push(match tuple_frac(field)? {
+                    Some(policy) => quote! {
+                        ::over::convert::frac_to_f64(&values[#i], #policy)
+                            .map_err(|e| ::over::convert::in_field(e, #index))?
+                    },
+                    None => quote!(::over::convert::from_element(&values, #i)?),
+                });
+            }
+
+            Ok(quote! {
+                let values = ::over::convert::tup_values(value, #len)?;
+                ::std::result::Result::Ok(#path(#(#elements),*))
+            })
+        }
+        Fields::Unit => Ok(quote! {
+            if value.is_null() {
+                ::std::result::Result::Ok(#path)
+            } else {
+                ::std::result::Result::Err(::over::OverError::TypeMismatch(
+                    ::over::types::Type::Null,
+                    value.get_type(),
+                ))
+            }
+        }),
+    }
+}
+
+// Returns an expression reading a named field from `obj: Obj` and `value: &Value`.
+fn read_named_field(field: &Field) -> Result<TokenStream2> {
+    let attrs = Attrs::parse(&field.attrs)?;
+    let ident = field.ident.as_ref().unwrap();
+    let ty = &field.ty;
+
+    if attrs.flatten {
+        if attrs.rename.is_some() || attrs.default.is_some() || attrs.frac.is_some() {
+            return Err(Error::new_spanned(
+                ident,
+                "`flatten` cannot be combined with other attributes",
+            ));
+        }
+        return Ok(quote!(<#ty as ::over::convert::FromOver>::from_over(value)?));
+    }
+    if attrs.frac.is_some() {
+        check_f64(field)?;
+    }
+
+    let name = over_name(ident, attrs.rename);
+    if attrs.default.is_none() && attrs.frac.is_none() {
+        return Ok(quote!(::over::convert::from_field(&obj, #name)?));
+    }
+
+    let convert = convert(field, quote!(value), attrs.frac);
+    let missing = match attrs.default {
+        Some(DefaultAttr::Trait) => quote!(::std::default::Default::default()),
+        Some(DefaultAttr::Path(ref path)) => quote!(#path()),
+        None => quote! {
+            match <#ty as ::over::convert::FromOver>::from_missing() {
+                ::std::option::Option::Some(value) => value,
+                ::std::option::Option::None => {
+                    return ::std::result::Result::Err(::over::OverError::FieldNotFound(
+                        #name.into(),
+                    ))
+                }
+            }
+        },
+    };
+
+    Ok(quote! {
+        match obj.get(#name) {
+            ::std::option::Option::Some(ref value) => {
+                #convert.map_err(|e| ::over::convert::in_field(e, #name))?
+            }
+            ::std::option::Option::None => #missing,
+        }
+    })
+}
+
+// Returns an expression converting `value` to the type of `field`.
+fn convert(field: &Field, value: TokenStream2, frac: Option<TokenStream2>) -> TokenStream2 {
+    let ty = &field.ty;
+
+    match frac {
+        Some(policy) => quote!(::over::convert::frac_to_f64(#value, #policy)),
+        None => quote!(<#ty as ::over::convert::FromOver>::from_over(#value)),
+    }
+}
+
+// Returns the `frac` policy of a tuple field, which may not have other attributes.
+fn tuple_frac(field: &Field) -> Result<Option<TokenStream2>> {
+    let attrs = Attrs::parse(&field.attrs)?;
+    if attrs.rename.is_some() || attrs.default.is_some() || attrs.flatten {
+        return Err(Error::new_spanned(
+            &field.ty,
+            "only `frac` can be used on tuple fields",
+        ));
+    }
+    if attrs.frac.is_some() {
+        check_f64(field)?;
+    }
+
+    Ok(attrs.frac)
+}
+
+fn check_f64(field: &Field) -> Result<()> {
+    match field.ty {
+        Type::Path(ref ty) if ty.qself.is_none() && ty.path.is_ident("f64") => Ok(()),
+        ref ty => Err(Error::new_spanned(
+            ty,
+            "`frac` can only be used on `f64` fields",
+        )),
+    }
+}
+
+// Returns true if any variant of `data` has fields. Unit variants are then written as an `Obj`
+// like the others, so that an `Arr` of the enum has a single type, and can be read from either a
+// `Str` or an `Obj`.
+fn has_fields(data: &DataEnum) -> bool {
+    data.variants
+        .iter()
+        .any(|variant| !matches!(variant.fields, Fields::Unit))
+}
+
+fn into_over(input: &DeriveInput) -> Result<TokenStream2> {
+    let name = &input.ident;
+    let generics = add_bounds(&input.generics, quote!(::over::convert::IntoOver));
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let body = match input.data {
+        Data::Struct(ref data) => {
+            let (pattern, body) = write_fields(&data.fields, quote!(Self))?;
+            quote! {
+                let #pattern = self;
+                #body
+            }
+        }
+        Data::Enum(ref data) => {
+            let has_fields = has_fields(data);
+            let mut arms = vec![];
+            for variant in &data.variants {
+                let ident = &variant.ident;
+                let over_name = over_name(ident, Attrs::parse_rename(&variant.attrs)?);
+
+                arms.push(if !has_fields {
+                    quote! {
+                        Self::#ident => ::std::result::Result::Ok(
+                            ::over::value::Value::Str(#over_name.into()),
+                        ),
+                    }
+                } else {
+                    let (pattern, body) = write_fields(&variant.fields, quote!(Self::#ident))?;
+                    quote! {
+                        #pattern => {
+                            let value = (move || -> ::over::OverResult<::over::value::Value> {
+                                #body
+                            })()
+                            .map_err(|e| ::over::convert::in_field(e, #over_name))?;
+                            ::over::convert::variant_value(#over_name, value)
+                        }
+                    }
+                });
+            }
+
+            quote! {
+                match self {
+                    #(#arms)*
+                }
+            }
+        }
+        Data::Union(_) => {
+            return Err(Error::new_spanned(
+                input,
+                "IntoOver cannot be derived for unions",
+            ))
+        }
+    };
+
+    Ok(quote! {
+        impl #impl_generics ::over::convert::IntoOver for #name #ty_generics #where_clause {
+            fn into_over(self) -> ::over::OverResult<::over::value::Value> {
+                #body
+            }
+        }
+    })
+}
+
+// Returns a pattern binding `fields` of `path`, and statements which write the bound fields and
+// return `OverResult<Value>`.
+fn write_fields(fields: &Fields, path: TokenStream2) -> Result<(TokenStream2, TokenStream2)> {
+    match *fields {
+        Fields::Named(ref fields) => {
+            let mut idents = vec![];
+            let mut pushes = vec![];
+            for field in &fields.named {
+                let attrs = Attrs::parse(&field.attrs)?;
+                let ident = field.ident.clone().unwrap();
+
+                pushes.push(if attrs.flatten {
+                    quote! {
+                        ::over::convert::flatten_into(
+                            &mut pairs,
+                            ::over::convert::IntoOver::into_over(#ident)?,
+                        )?;
+                    }
+                } else {
+                    let name = over_name(&ident, attrs.rename);
+                    quote! {
+                        pairs.push(::over::obj::Pair(
+                            #name.into(),
+                            ::over::convert::IntoOver::into_over(#ident)
+                                .map_err(|e| ::over::convert::in_field(e, #name))?,
+                        ));
+                    }
+                });
+                idents.push(ident);
+            }
+
+            Ok((
+                quote!(#path { #(#idents),* }),
+                quote! {
+                    let mut pairs = ::std::vec::Vec::new();
+                    #(#pushes)*
+                    ::std::result::Result::Ok(::over::value::Value::Obj(
+                        ::over::obj::Obj::from_pairs(pairs, ::std::option::Option::None)?,
+                    ))
+                },
+            ))
+        }
+        Fields::Unnamed(ref fields) if fields.unnamed.len() == 1 => Ok((
+            quote!(#path(field)),
+            quote!(::over::convert::IntoOver::into_over(field)),
+        )),
+        Fields::Unnamed(ref fields) => {
+            let idents: Vec<Ident> = (0..fields.unnamed.len())
+                .map(|i| format_ident!("field{}", i))
+                .collect();
+            let indices: Vec<String> = (0..idents.len()).map(|i| i.to_string()).collect();
+
+            Ok((
+                quote!(#path(#(#idents),*)),
+                quote! {
+                    let values = ::std::vec![#(
+                        ::over::convert::IntoOver::into_over(#idents)
+                            .map_err(|e| ::over::convert::in_field(e, #indices))?
+                    ),*];
+                    ::std::result::Result::Ok(::over::value::Value::Tup(
+                        ::over::tup::Tup::from_values(values),
+                    ))
+                },
+            ))
+        }
+        Fields::Unit => Ok((
+            path,
+            quote!(::std::result::Result::Ok(::over::value::Value::Null)),
+        )),
+    }
+}
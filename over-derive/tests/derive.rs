@@ -0,0 +1,363 @@
+use over::{types::Type, value::Value, FromOver, IntoOver, Obj, OverError, OverResult};
+
+#[derive(Debug, PartialEq, FromOver, IntoOver)]
+struct Server {
+    host: String,
+    #[over(rename = "listen_port")]
+    port: u16,
+    #[over(default)]
+    workers: usize,
+    #[over(default = "default_tags")]
+    tags: Vec<String>,
+    backup: Option<Box<Server>>,
+}
+
+fn default_tags() -> Vec<String> {
+    vec!["main".into()]
+}
+
+impl FromOver for Box<Server> {
+    fn from_over(value: &Value) -> OverResult<Self> {
+        Server::from_over(value).map(Box::new)
+    }
+}
+
+impl IntoOver for Box<Server> {
+    fn into_over(self) -> OverResult<Value> {
+        (*self).into_over()
+    }
+}
+
+#[derive(Debug, PartialEq, FromOver, IntoOver)]
+struct Config {
+    name: String,
+    servers: Vec<Server>,
+}
+
+// Test reading structs, with renamed, defaulted and optional fields.
+#[test]
+fn from_over() -> OverResult<()> {
+    let obj: Obj = r#"
+name: "prod"
+servers: [
+    { host: "a" listen_port: 80 workers: 4 tags: ["x"] }
+    { host: "b" listen_port: 81 backup: { host: "c" listen_port: 82 } }
+]
+"#
+    .parse()?;
+
+    let config = Config::from_obj(&obj)?;
+    assert_eq!(
+        config,
+        Config {
+            name: "prod".into(),
+            servers: vec![
+                Server {
+                    host: "a".into(),
+                    port: 80,
+                    workers: 4,
+                    tags: vec!["x".into()],
+                    backup: None,
+                },
+                Server {
+                    host: "b".into(),
+                    port: 81,
+                    workers: 0,
+                    tags: vec!["main".into()],
+                    backup: Some(Box::new(Server {
+                        host: "c".into(),
+                        port: 82,
+                        workers: 0,
+                        tags: vec!["main".into()],
+                        backup: None,
+                    })),
+                },
+            ],
+        }
+    );
+
+    Ok(())
+}
+
+// Test that errors name the dotted path to the field.
+#[test]
+fn from_over_errors() -> OverResult<()> {
+    let check = |text: &str, error: OverError| -> OverResult<()> {
+        let obj: Obj = text.parse()?;
+        assert_eq!(Config::from_obj(&obj), Err(error));
+        Ok(())
+    };
+
+    check(
+        r#"name: "prod" servers: [{ host: "a" }]"#,
+        OverError::FieldNotFound("servers.0.listen_port".into()),
+    )?;
+    check(
+        r#"name: "prod" servers: [{ host: "a" listen_port: "80" }]"#,
+        OverError::FieldTypeMismatch("servers.0.listen_port".into(), Type::Int, Type::Str),
+    )?;
+    check(
+        r#"name: "prod" servers: [{ host: "a" listen_port: 80 backup: { host: "b" listen_port: 70000 } }]"#,
+        OverError::InvalidValue(
+            "servers.0.backup.listen_port".into(),
+            "70000 is out of range for u16".into(),
+        ),
+    )?;
+    check(
+        r#"name: "prod" servers: 1"#,
+        OverError::FieldTypeMismatch("servers".into(), Type::Arr(Box::new(Type::Any)), Type::Int),
+    )?;
+
+    let error = Config::from_over(&Value::Null).unwrap_err();
+    assert_eq!(error, OverError::TypeMismatch(Type::Obj, Type::Null));
+
+    Ok(())
+}
+
+#[derive(Debug, PartialEq, FromOver, IntoOver)]
+struct Base {
+    color: String,
+    size: u8,
+}
+
+#[derive(Debug, PartialEq, FromOver, IntoOver)]
+struct Shape {
+    name: String,
+    #[over(flatten)]
+    base: Base,
+}
+
+// Test flattened fields, which may be inherited from parents.
+#[test]
+fn flatten() -> OverResult<()> {
+    let obj: Obj = r#"
+@base: { color: "red" size: 2 }
+shape: { ^: @base name: "circle" size: 3 }
+"#
+    .parse()?;
+
+    let shape: Shape = over::convert::from_field(&obj, "shape")?;
+    assert_eq!(
+        shape,
+        Shape {
+            name: "circle".into(),
+            base: Base {
+                color: "red".into(),
+                size: 3,
+            },
+        }
+    );
+
+    let obj = shape.into_over()?.get_obj()?;
+    assert_eq!(
+        obj.write_to_string(),
+        "name: \"circle\"\ncolor: \"red\"\nsize: 3\n"
+    );
+    assert!(!obj.has_parent());
+
+    Ok(())
+}
+
+#[derive(Debug, PartialEq, FromOver)]
+struct Ratios {
+    nearest: f64,
+    #[over(frac = "exact")]
+    exact: f64,
+    #[over(frac = "exact", default)]
+    optional: f64,
+}
+
+// Test the policies for reading Fracs into f64s.
+#[test]
+fn frac_policies() -> OverResult<()> {
+    let ratios = Ratios::from_obj(&"nearest: 1/3 exact: 1/4".parse()?)?;
+    assert_eq!(
+        ratios,
+        Ratios {
+            nearest: 1.0 / 3.0,
+            exact: 0.25,
+            optional: 0.0,
+        }
+    );
+
+    let ratios = Ratios::from_obj(&"nearest: -2 exact: 3".parse()?)?;
+    assert_eq!((ratios.nearest, ratios.exact), (-2.0, 3.0));
+
+    assert_eq!(
+        Ratios::from_obj(&"nearest: 0.1 exact: 0.1".parse()?),
+        Err(OverError::InvalidValue(
+            "exact".into(),
+            "0.1 is not exactly an f64".into()
+        ))
+    );
+
+    Ok(())
+}
+
+#[derive(Debug, PartialEq, FromOver, IntoOver)]
+enum Fill {
+    None,
+    #[over(rename = "solid")]
+    Solid(String),
+    Gradient(String, String),
+    Pattern {
+        name: String,
+        scale: u8,
+    },
+}
+
+#[derive(Debug, PartialEq, FromOver, IntoOver)]
+enum Direction {
+    #[over(rename = "up")]
+    Up,
+    Down,
+}
+
+// Test reading and writing enums.
+#[test]
+fn enums() -> OverResult<()> {
+    let obj: Obj = r#"
+fills: [
+    { None: null }
+    { solid: "red" }
+    { Gradient: ("red" "blue") }
+    { Pattern: { name: "dots" scale: 2 } }
+]
+"#
+    .parse()?;
+
+    let fills: Vec<Fill> = over::convert::from_field(&obj, "fills")?;
+    assert_eq!(
+        fills,
+        vec![
+            Fill::None,
+            Fill::Solid("red".into()),
+            Fill::Gradient("red".into(), "blue".into()),
+            Fill::Pattern {
+                name: "dots".into(),
+                scale: 2,
+            },
+        ]
+    );
+    assert_eq!(fills.into_over()?, obj.get("fills").unwrap());
+    assert_eq!(Fill::from_over(&"None".into())?, Fill::None);
+
+    let directions: Vec<Direction> =
+        over::convert::from_field(&"d: [\"up\" \"Down\"]".parse()?, "d")?;
+    assert_eq!(directions, vec![Direction::Up, Direction::Down]);
+    assert_eq!(Direction::Up.into_over()?, Value::from("up"));
+
+    let check = |text: &str, error: OverError| -> OverResult<()> {
+        let obj: Obj = text.parse()?;
+        assert_eq!(over::convert::from_field::<Fill>(&obj, "fill"), Err(error));
+        Ok(())
+    };
+    let invalid = |field: &str, error: &str| OverError::InvalidValue(field.into(), error.into());
+
+    check(
+        "fill: \"Solid\"",
+        invalid(
+            "fill",
+            "unknown variant \"Solid\", expected one of: None, solid, Gradient, Pattern",
+        ),
+    )?;
+    check(
+        "fill: { Pattern: { name: \"dots\" } }",
+        OverError::FieldNotFound("fill.Pattern.scale".into()),
+    )?;
+    check(
+        "fill: { Gradient: (\"red\") }",
+        OverError::FieldTypeMismatch(
+            "fill.Gradient".into(),
+            Type::Tup(vec![Type::Any, Type::Any]),
+            Type::Tup(vec![Type::Str]),
+        ),
+    )?;
+    check(
+        "fill: { None: 1 }",
+        OverError::FieldTypeMismatch("fill.None".into(), Type::Null, Type::Int),
+    )?;
+    check(
+        "fill: { solid: \"red\" None: null }",
+        invalid(
+            "fill",
+            "expected an Obj with one field naming the variant, found 2 fields",
+        ),
+    )?;
+    check(
+        "fill: 1",
+        OverError::FieldTypeMismatch("fill".into(), Type::Obj, Type::Int),
+    )?;
+
+    Ok(())
+}
+
+#[derive(Debug, PartialEq, FromOver, IntoOver)]
+struct Point<T>(T, T);
+
+#[derive(Debug, PartialEq, FromOver, IntoOver)]
+struct Meters(f64);
+
+#[derive(Debug, PartialEq, FromOver, IntoOver)]
+struct Marker;
+
+#[derive(Debug, PartialEq, FromOver, IntoOver)]
+struct Path<T> {
+    points: Vec<Point<T>>,
+    length: Meters,
+    end: Marker,
+}
+
+// Test tuple, newtype and unit structs, generic structs, and writing structs back.
+#[test]
+fn into_over() -> OverResult<()> {
+    let path = Path {
+        points: vec![Point(1, 2), Point(3, 4)],
+        length: Meters(2.5),
+        end: Marker,
+    };
+
+    let obj = path.into_over()?.get_obj()?;
+    let expected: Obj = "points: [(1 2) (3 4)] length: 2.5 end: null".parse()?;
+    assert_eq!(obj, expected);
+    assert_eq!(
+        Path::<i32>::from_obj(&obj)?,
+        Path {
+            points: vec![Point(1, 2), Point(3, 4)],
+            length: Meters(2.5),
+            end: Marker,
+        }
+    );
+
+    let error = Path::<i32>::from_obj(&"points: [(1 2.5)] length: 1 end: null".parse()?);
+    assert_eq!(
+        error,
+        Err(OverError::FieldTypeMismatch(
+            "points.0.1".into(),
+            Type::Int,
+            Type::Frac
+        ))
+    );
+
+    let server = Server {
+        host: "a".into(),
+        port: 80,
+        workers: 1,
+        tags: vec![],
+        backup: None,
+    };
+    assert_eq!(
+        server.into_over()?.get_obj()?.write_to_string(),
+        "host: \"a\"\nlisten_port: 80\nworkers: 1\ntags: []\nbackup: null\n"
+    );
+
+    assert_eq!(
+        Meters(f64::NAN).into_over(),
+        Err(OverError::InvalidValue(
+            String::new(),
+            "NaN is not a Frac".into()
+        ))
+    );
+
+    Ok(())
+}
@@ -0,0 +1,555 @@
+//! Queries on the text of a .over file: diagnostics, definitions, hovers and completions.
+//!
+//! The structure of the file comes from `over::document`, and values come from evaluating the
+//! whole file with the regular parser.
+
+use over::{
+    document::{Document, Field, Node, NodeKind, Reference, Span},
+    error::ParseError,
+    obj::Obj,
+    value::Value,
+};
+use std::path::{Path, PathBuf};
+
+/// Converts between byte offsets and LSP positions, whose characters are UTF-16 code units.
+pub struct LineIndex<'a> {
+    text: &'a str,
+    // Byte offsets of the start of each line.
+    lines: Vec<usize>,
+}
+
+impl<'a> LineIndex<'a> {
+    pub fn new(text: &'a str) -> Self {
+        let mut lines = vec![0];
+        lines.extend(text.match_indices('\n').map(|(i, _)| i + 1));
+
+        Self { text, lines }
+    }
+
+    /// Returns the line and character of the byte offset `offset`, both counted from 0.
+    pub fn position(&self, offset: usize) -> (usize, usize) {
+        let offset = offset.min(self.text.len());
+        let line = self.lines.partition_point(|&start| start <= offset) - 1;
+        let character = self.text[self.lines[line]..offset]
+            .chars()
+            .map(char::len_utf16)
+            .sum();
+
+        (line, character)
+    }
+
+    /// Returns the byte offset of the given line and character, clamped to the end of the line.
+    pub fn offset(&self, line: usize, character: usize) -> usize {
+        let start = match self.lines.get(line) {
+            Some(&start) => start,
+            None => return self.text.len(),
+        };
+        let mut units = 0;
+
+        for (i, ch) in self.text[start..].char_indices() {
+            if units >= character || ch == '\n' {
+                return start + i;
+            }
+            units += ch.len_utf16();
+        }
+
+        self.text.len()
+    }
+
+    /// Returns the byte offset of a line and column as reported in parse errors, where both start
+    /// at 1 and columns count characters.
+    pub fn offset_of_line_col(&self, line: usize, col: usize) -> usize {
+        let start = match self.lines.get(line.saturating_sub(1)) {
+            Some(&start) => start,
+            None => return self.text.len(),
+        };
+
+        self.text[start..]
+            .char_indices()
+            .take_while(|&(_, ch)| ch != '\n')
+            .nth(col.saturating_sub(1))
+            .map_or_else(
+                || {
+                    start
+                        + self.text[start..]
+                            .find('\n')
+                            .unwrap_or(self.text.len() - start)
+                },
+                |(i, _)| start + i,
+            )
+    }
+}
+
+/// An error in a file.
+#[derive(Debug, PartialEq)]
+pub struct Diagnostic {
+    pub span: Span,
+    pub message: String,
+}
+
+/// Evaluates `text` as the contents of the file at `path`, if any.
+pub fn evaluate(text: &str, path: Option<&str>) -> Result<Obj, ParseError> {
+    Obj::parse_str_in_file(text, path)
+}
+
+/// Returns the error in `text`, if any. Errors in included files are reported at the start of
+/// `text`.
+pub fn diagnostics(text: &str, path: Option<&str>) -> Vec<Diagnostic> {
+    let e = match evaluate(text, path) {
+        Ok(_) => return vec![],
+        Err(e) => e,
+    };
+
+    // Errors in other files have no position in this one.
+    if e.file() != path {
+        return vec![whole_file(text, e.to_string())];
+    }
+
+    let index = LineIndex::new(text);
+    let start = match (e.line(), e.col()) {
+        (Some(line), Some(col)) => index.offset_of_line_col(line, col),
+        // Only the line is known for unexpected ends.
+        (Some(_), None) => text.len(),
+        (None, _) => 0,
+    };
+    let end = text[start..]
+        .chars()
+        .next()
+        .filter(|&ch| ch != '\n')
+        .map_or(start, |ch| start + ch.len_utf8());
+
+    vec![Diagnostic {
+        span: start..end,
+        message: e.message(),
+    }]
+}
+
+fn whole_file(text: &str, message: String) -> Diagnostic {
+    Diagnostic {
+        span: 0..text.find('\n').unwrap_or(text.len()),
+        message,
+    }
+}
+
+/// A step from an `Obj` into one of its fields, or from an `Arr` or `Tup` into an element.
+#[derive(Clone, Copy, Debug)]
+pub enum Step<'a> {
+    Field(&'a Field),
+    Element(usize, &'a Node),
+}
+
+impl<'a> Step<'a> {
+    pub fn node(&self) -> &'a Node {
+        match *self {
+            Step::Field(field) => field.value(),
+            Step::Element(_, node) => node,
+        }
+    }
+}
+
+/// Returns the steps from the top of `doc` to the innermost field or element containing `offset`.
+pub fn steps_at(doc: &Document, offset: usize) -> Vec<Step<'_>> {
+    let mut steps = vec![];
+    let mut fields = doc.fields();
+
+    loop {
+        let step = match fields.iter().find(|field| contains(&field.span(), offset)) {
+            Some(field) => Step::Field(field),
+            None => return steps,
+        };
+        steps.push(step);
+
+        // Go into `Arr` and `Tup` elements until reaching a value which is not one.
+        let mut node = step.node();
+        loop {
+            match *node.kind() {
+                NodeKind::Obj(ref inner) => {
+                    fields = inner;
+                    break;
+                }
+                NodeKind::Arr(ref elements) | NodeKind::Tup(ref elements) => {
+                    match elements
+                        .iter()
+                        .enumerate()
+                        .find(|(_, element)| contains(&element.span(), offset))
+                    {
+                        Some((index, element)) => {
+                            steps.push(Step::Element(index, element));
+                            node = element;
+                        }
+                        None => return steps,
+                    }
+                }
+                NodeKind::Expr => return steps,
+            }
+        }
+    }
+}
+
+// Returns true if `offset` is in `span` or directly after it, where a cursor at the end of a word
+// still refers to that word.
+fn contains(span: &Span, offset: usize) -> bool {
+    span.start <= offset && offset <= span.end
+}
+
+/// What a reference in a file refers to.
+#[derive(Debug)]
+pub enum Definition<'a> {
+    /// A field or element of the file, given by the steps leading to it.
+    Local(Vec<Step<'a>>),
+    /// An included file.
+    File(PathBuf),
+}
+
+/// Returns the definition of the variable, global, parent or include at `offset`.
+pub fn definition<'a>(
+    doc: &'a Document,
+    path: Option<&str>,
+    offset: usize,
+) -> Option<Definition<'a>> {
+    let steps = steps_at(doc, offset);
+    let (last, parents) = steps.split_last()?;
+    let node = last.node();
+
+    // The definition of a parent is the definition of its value.
+    let offset = match *last {
+        Step::Field(field) if field.is_parent() && contains(&field.name_span(), offset) => {
+            node.span().start
+        }
+        _ => offset,
+    };
+    if !matches!(node.kind(), NodeKind::Expr) || !contains(&node.span(), offset) {
+        return None;
+    }
+
+    let text = &doc.text()[node.span()];
+    if text.starts_with('<') {
+        let include = include_path(text)?;
+        let dir = path
+            .and_then(|path| Path::new(path).parent())
+            .unwrap_or_else(|| Path::new(""));
+        return Some(Definition::File(dir.join(include)));
+    }
+
+    // The segments of the reference up to the one at `offset`.
+    let references = doc.references(node);
+    let segments: Vec<&str> = references.iter().find_map(|reference| {
+        let len = reference
+            .segments()
+            .iter()
+            .position(|(_, span)| contains(span, offset))?
+            + 1;
+        Some(
+            reference.segments()[..len]
+                .iter()
+                .map(|(name, _)| name.as_str())
+                .collect(),
+        )
+    })?;
+
+    resolve(doc, parents, node.span().start, &segments).map(Definition::Local)
+}
+
+// Returns the path in an include expression such as `<Obj "file.over">`.
+fn include_path(text: &str) -> Option<&str> {
+    let start = text.find('"')? + 1;
+    let len = text[start..].find('"')?;
+
+    Some(&text[start..start + len])
+}
+
+// Resolves the dotted `segments` of a reference at `start`, inside the values given by `parents`.
+fn resolve<'a>(
+    doc: &'a Document,
+    parents: &[Step<'a>],
+    start: usize,
+    segments: &[&str],
+) -> Option<Vec<Step<'a>>> {
+    let (first, rest) = segments.split_first()?;
+
+    let mut steps = if first.starts_with('@') {
+        find_global(doc.fields(), first, start)?
+    } else {
+        // Variables are fields defined earlier in the innermost `Obj`.
+        let (mut steps, fields) = scope(doc, parents, start);
+        let field = fields
+            .iter()
+            .find(|field| field.name() == *first && field.value().span().end <= start)?;
+        steps.push(Step::Field(field));
+        steps
+    };
+
+    for segment in rest {
+        let node = steps.last()?.node();
+        let step = match *node.kind() {
+            NodeKind::Obj(ref fields) => {
+                Step::Field(fields.iter().find(|field| field.name() == *segment)?)
+            }
+            NodeKind::Arr(ref elements) | NodeKind::Tup(ref elements) => {
+                let index = segment.parse().ok()?;
+                Step::Element(index, elements.get(index)?)
+            }
+            NodeKind::Expr => return None,
+        };
+        steps.push(step);
+    }
+
+    Some(steps)
+}
+
+// Returns the steps to the innermost `Obj` in `steps` containing `offset`, and its fields.
+fn scope<'a>(doc: &'a Document, steps: &[Step<'a>], offset: usize) -> (Vec<Step<'a>>, &'a [Field]) {
+    for (i, step) in steps.iter().enumerate().rev() {
+        let span = step.node().span();
+        if let Some(fields) = step.node().fields() {
+            if span.start < offset && offset < span.end {
+                return (steps[..=i].to_vec(), fields);
+            }
+        }
+    }
+
+    (vec![], doc.fields())
+}
+
+// Finds the first definition of the global `name` ending before `before`, anywhere in `fields`.
+fn find_global<'a>(fields: &'a [Field], name: &str, before: usize) -> Option<Vec<Step<'a>>> {
+    fn find_in_node<'a>(node: &'a Node, name: &str, before: usize) -> Option<Vec<Step<'a>>> {
+        match *node.kind() {
+            NodeKind::Obj(ref fields) => find_global(fields, name, before),
+            NodeKind::Arr(ref elements) | NodeKind::Tup(ref elements) => {
+                elements.iter().enumerate().find_map(|(index, element)| {
+                    let mut steps = find_in_node(element, name, before)?;
+                    steps.insert(0, Step::Element(index, element));
+                    Some(steps)
+                })
+            }
+            NodeKind::Expr => None,
+        }
+    }
+
+    fields.iter().find_map(|field| {
+        if field.name() == name && field.value().span().end <= before {
+            return Some(vec![Step::Field(field)]);
+        }
+
+        let mut steps = find_in_node(field.value(), name, before)?;
+        steps.insert(0, Step::Field(field));
+        Some(steps)
+    })
+}
+
+// Returns the globals defined before `before`, anywhere in `fields`.
+fn globals_before<'a>(fields: &'a [Field], before: usize, globals: &mut Vec<&'a str>) {
+    fn in_node<'a>(node: &'a Node, before: usize, globals: &mut Vec<&'a str>) {
+        match *node.kind() {
+            NodeKind::Obj(ref fields) => globals_before(fields, before, globals),
+            NodeKind::Arr(ref elements) | NodeKind::Tup(ref elements) => {
+                for element in elements {
+                    in_node(element, before, globals);
+                }
+            }
+            NodeKind::Expr => (),
+        }
+    }
+
+    for field in fields {
+        if field.is_global() && field.value().span().end <= before {
+            globals.push(field.name());
+        }
+        in_node(field.value(), before, globals);
+    }
+}
+
+/// Returns the evaluated value at `offset`, either of the field whose name is there or of the
+/// definition of the reference there, together with its type.
+pub fn hover(doc: &Document, path: Option<&str>, offset: usize) -> Option<(Value, Span)> {
+    let steps = steps_at(doc, offset);
+
+    let (steps, span) = match steps.last() {
+        Some(Step::Field(field)) if !field.is_parent() && contains(&field.name_span(), offset) => {
+            let span = field.name_span();
+            (steps, span)
+        }
+        _ => match definition(doc, path, offset)? {
+            Definition::Local(definition) => {
+                let span = reference_span(doc, &steps, offset)?;
+                (definition, span)
+            }
+            Definition::File(_) => return None,
+        },
+    };
+
+    // Globals and parents are not part of the evaluated `Obj`.
+    if steps.iter().any(|step| match *step {
+        Step::Field(field) => field.is_global() || field.is_parent(),
+        Step::Element(..) => false,
+    }) {
+        return None;
+    }
+
+    let obj = evaluate(doc.text(), path).ok()?;
+    let mut value = Value::Obj(obj);
+    for step in &steps {
+        value = match (step, value) {
+            (Step::Field(field), Value::Obj(obj)) => obj.get(field.name())?,
+            (Step::Element(index, _), Value::Arr(arr)) => arr.get(*index).ok()?,
+            (Step::Element(index, _), Value::Tup(tup)) => tup.get(*index).ok()?,
+            _ => return None,
+        };
+    }
+
+    Some((value, span))
+}
+
+// Returns the span of the reference segment at `offset`.
+fn reference_span(doc: &Document, steps: &[Step], offset: usize) -> Option<Span> {
+    let node = steps.last()?.node();
+
+    doc.references(node)
+        .iter()
+        .flat_map(Reference::segments)
+        .map(|(_, span)| span.clone())
+        .find(|span| contains(span, offset))
+}
+
+/// A suggested completion.
+#[derive(Debug, PartialEq)]
+pub struct Completion {
+    pub label: String,
+    pub is_global: bool,
+}
+
+/// Returns the fields and globals which can be referenced at `offset`. After a dot, returns the
+/// fields of the `Obj` before the dot instead.
+pub fn completions(doc: &Document, offset: usize) -> Vec<Completion> {
+    let text = doc.text();
+    let start = text[..offset]
+        .char_indices()
+        .rev()
+        .take_while(|&(_, ch)| ch == '@' || ch == '.' || Obj::is_valid_field_char(ch, false))
+        .last()
+        .map_or(offset, |(i, _)| i);
+    let word = &text[start..offset];
+
+    let steps = steps_at(doc, start);
+    let parents = match steps.last() {
+        Some(last) if matches!(last.node().kind(), NodeKind::Expr) => &steps[..steps.len() - 1],
+        _ => &steps[..],
+    };
+
+    let mut labels: Vec<(&str, bool)> = vec![];
+
+    if let Some(dot) = word.rfind('.') {
+        let segments: Vec<&str> = word[..dot].split('.').collect();
+
+        if let Some(steps) = resolve(doc, parents, start, &segments) {
+            if let Some(fields) = steps.last().and_then(|step| step.node().fields()) {
+                labels.extend(
+                    fields
+                        .iter()
+                        .filter(|field| !field.is_global() && !field.is_parent())
+                        .map(|field| (field.name(), false)),
+                );
+            }
+        }
+    } else {
+        if !word.starts_with('@') {
+            let (_, fields) = scope(doc, parents, start);
+            labels.extend(
+                fields
+                    .iter()
+                    .filter(|field| {
+                        !field.is_global()
+                            && !field.is_parent()
+                            && field.value().span().end <= start
+                    })
+                    .map(|field| (field.name(), false)),
+            );
+        }
+
+        let mut globals = vec![];
+        globals_before(doc.fields(), start, &mut globals);
+        labels.extend(globals.into_iter().map(|name| (name, true)));
+    }
+
+    let mut completions: Vec<Completion> = vec![];
+    for (label, is_global) in labels {
+        if !completions
+            .iter()
+            .any(|completion| completion.label == label)
+        {
+            completions.push(Completion {
+                label: label.into(),
+                is_global,
+            });
+        }
+    }
+
+    completions
+}
+
+/// Returns `doc` as written by `Obj::write_to_string`, or `None` if that would lose anything other
+/// than whitespace: comments, globals, parents, includes and expressions such as references are
+/// all evaluated away, so such documents are not formatted.
+pub fn format(doc: &Document, path: Option<&str>) -> Option<String> {
+    let text = doc.text();
+    let formatted = evaluate(text, path).ok()?.write_to_string();
+
+    // Strings and chars only occur in values, so a '#' anywhere else starts a comment.
+    let mut values = vec![];
+    expr_spans(doc.fields(), &mut values);
+    if text
+        .match_indices('#')
+        .any(|(i, _)| !values.iter().any(|span| contains(span, i)))
+    {
+        return None;
+    }
+
+    let formatted_doc = Document::parse(&formatted).ok()?;
+    if same_fields(text, doc.fields(), &formatted, formatted_doc.fields()) {
+        Some(formatted)
+    } else {
+        None
+    }
+}
+
+fn expr_spans(fields: &[Field], spans: &mut Vec<Span>) {
+    fn node_spans(node: &Node, spans: &mut Vec<Span>) {
+        match *node.kind() {
+            NodeKind::Obj(ref fields) => expr_spans(fields, spans),
+            NodeKind::Arr(ref elements) | NodeKind::Tup(ref elements) => {
+                for element in elements {
+                    node_spans(element, spans);
+                }
+            }
+            NodeKind::Expr => spans.push(node.span()),
+        }
+    }
+
+    for field in fields {
+        node_spans(field.value(), spans);
+    }
+}
+
+// Returns true if the fields have the same names and their values are written the same way, up to
+// whitespace between them.
+fn same_fields(a_text: &str, a: &[Field], b_text: &str, b: &[Field]) -> bool {
+    a.len() == b.len()
+        && a.iter()
+            .zip(b)
+            .all(|(a, b)| a.name() == b.name() && same_node(a_text, a.value(), b_text, b.value()))
+}
+
+fn same_node(a_text: &str, a: &Node, b_text: &str, b: &Node) -> bool {
+    match (a.kind(), b.kind()) {
+        (NodeKind::Obj(a), NodeKind::Obj(b)) => same_fields(a_text, a, b_text, b),
+        (NodeKind::Arr(a), NodeKind::Arr(b)) | (NodeKind::Tup(a), NodeKind::Tup(b)) => {
+            a.len() == b.len()
+                && a.iter()
+                    .zip(b)
+                    .all(|(a, b)| same_node(a_text, a, b_text, b))
+        }
+        (NodeKind::Expr, NodeKind::Expr) => a_text[a.span()] == b_text[b.span()],
+        _ => false,
+    }
+}
@@ -0,0 +1,24 @@
+//! A language server for OVER files, speaking the Language Server Protocol over stdio.
+//!
+//! Supports diagnostics for parse errors, go-to-definition for variables, globals, parents and
+//! includes, hover with the evaluated value of fields, completion of fields and globals in scope,
+//! and formatting of documents which have no comments, globals, includes or expressions.
+
+mod analysis;
+mod protocol;
+mod server;
+
+use std::{io, process};
+
+fn main() {
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+
+    match server::run(&mut stdin.lock(), &mut stdout.lock()) {
+        Ok(code) => process::exit(code),
+        Err(e) => {
+            eprintln!("over-lsp: {}", e);
+            process::exit(1);
+        }
+    }
+}
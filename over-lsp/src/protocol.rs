@@ -0,0 +1,75 @@
+//! Reading and writing LSP messages, which are JSON-RPC messages preceded by a `Content-Length`
+//! header.
+
+use serde_json::{json, Value as Json};
+use std::io::{self, BufRead, Write};
+
+/// The error code for requests with an unknown method.
+pub const METHOD_NOT_FOUND: i64 = -32601;
+/// The error code for requests with invalid parameters.
+pub const INVALID_PARAMS: i64 = -32602;
+
+/// Reads the next message, returning `None` at the end of the input.
+pub fn read_message<R: BufRead>(reader: &mut R) -> io::Result<Option<Json>> {
+    let mut len = None;
+
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("Content-Length") {
+                len = Some(value.trim().parse::<usize>().map_err(invalid_data)?);
+            }
+        }
+    }
+
+    let len = len.ok_or_else(|| invalid_data("missing Content-Length header"))?;
+    let mut content = vec![0; len];
+    reader.read_exact(&mut content)?;
+
+    serde_json::from_slice(&content)
+        .map(Some)
+        .map_err(invalid_data)
+}
+
+/// Writes a message.
+pub fn write_message<W: Write>(writer: &mut W, msg: &Json) -> io::Result<()> {
+    let content = msg.to_string();
+    write!(
+        writer,
+        "Content-Length: {}\r\n\r\n{}",
+        content.len(),
+        content
+    )?;
+    writer.flush()
+}
+
+/// Returns a successful response to the request with the given id.
+pub fn response(id: &Json, result: Json) -> Json {
+    json!({ "jsonrpc": "2.0", "id": id, "result": result })
+}
+
+/// Returns an error response to the request with the given id.
+pub fn error_response(id: &Json, code: i64, message: &str) -> Json {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
+
+/// Returns a notification.
+pub fn notification(method: &str, params: Json) -> Json {
+    json!({ "jsonrpc": "2.0", "method": method, "params": params })
+}
+
+fn invalid_data<E>(e: E) -> io::Error
+where
+    E: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    io::Error::new(io::ErrorKind::InvalidData, e)
+}
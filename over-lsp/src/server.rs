@@ -0,0 +1,326 @@
+//! The language server: keeps the open files and answers requests about them.
+
+use crate::{
+    analysis::{self, Definition, LineIndex, Step},
+    protocol::{self, INVALID_PARAMS, METHOD_NOT_FOUND},
+};
+use over::document::{Document, Span};
+use serde_json::{json, Value as Json};
+use std::{
+    collections::HashMap,
+    io::{self, BufRead, Write},
+    path::Path,
+};
+
+/// An open file.
+struct File {
+    text: String,
+    // The path of the file, if its URI is a file URI.
+    path: Option<String>,
+}
+
+/// Serves requests read from `reader`, writing responses and notifications to `writer`, until an
+/// `exit` notification or the end of the input. Returns the exit code of the server.
+pub fn run<R: BufRead, W: Write>(reader: &mut R, writer: &mut W) -> io::Result<i32> {
+    let mut server = Server {
+        files: HashMap::new(),
+        shutdown: false,
+    };
+
+    while let Some(msg) = protocol::read_message(reader)? {
+        let method = match msg.get("method").and_then(Json::as_str) {
+            Some(method) => method,
+            // Ignore responses, as the server sends no requests.
+            None => continue,
+        };
+        let params = msg.get("params").cloned().unwrap_or(Json::Null);
+
+        match msg.get("id") {
+            Some(id) => {
+                let response = match server.request(method, &params) {
+                    Ok(result) => protocol::response(id, result),
+                    Err((code, message)) => protocol::error_response(id, code, &message),
+                };
+                protocol::write_message(writer, &response)?;
+            }
+            None if method == "exit" => return Ok(if server.shutdown { 0 } else { 1 }),
+            None => {
+                for notification in server.notification(method, &params) {
+                    protocol::write_message(writer, &notification)?;
+                }
+            }
+        }
+    }
+
+    Ok(1)
+}
+
+struct Server {
+    files: HashMap<String, File>,
+    shutdown: bool,
+}
+
+type RequestResult = Result<Json, (i64, String)>;
+
+impl Server {
+    fn request(&mut self, method: &str, params: &Json) -> RequestResult {
+        match method {
+            "initialize" => Ok(json!({
+                "capabilities": {
+                    "textDocumentSync": { "openClose": true, "change": 1, "save": true },
+                    "definitionProvider": true,
+                    "hoverProvider": true,
+                    "completionProvider": { "triggerCharacters": ["@", "."] },
+                    "documentFormattingProvider": true,
+                },
+                "serverInfo": { "name": "over-lsp", "version": env!("CARGO_PKG_VERSION") },
+            })),
+            "shutdown" => {
+                self.shutdown = true;
+                Ok(Json::Null)
+            }
+            "textDocument/definition" => self.definition(params),
+            "textDocument/hover" => self.hover(params),
+            "textDocument/completion" => self.completion(params),
+            "textDocument/formatting" => self.formatting(params),
+            _ => Err((METHOD_NOT_FOUND, format!("Unknown method {}", method))),
+        }
+    }
+
+    // Returns the notifications to send in reply.
+    fn notification(&mut self, method: &str, params: &Json) -> Vec<Json> {
+        let uri = match params.pointer("/textDocument/uri").and_then(Json::as_str) {
+            Some(uri) => uri.to_string(),
+            None => return vec![],
+        };
+
+        match method {
+            "textDocument/didOpen" => {
+                let text = params.pointer("/textDocument/text").and_then(Json::as_str);
+                let text = text.unwrap_or_default().to_string();
+                let path = uri_to_path(&uri);
+                let _ = self.files.insert(uri.clone(), File { text, path });
+            }
+            "textDocument/didChange" => {
+                // Only full document changes are supported.
+                let changes = params.get("contentChanges").and_then(Json::as_array);
+                let text = changes
+                    .and_then(|changes| changes.last())
+                    .and_then(|change| change.get("text"))
+                    .and_then(Json::as_str);
+
+                match (self.files.get_mut(&uri), text) {
+                    (Some(file), Some(text)) => file.text = text.into(),
+                    _ => return vec![],
+                }
+            }
+            // Included files may have changed.
+            "textDocument/didSave" => (),
+            "textDocument/didClose" => {
+                let _ = self.files.remove(&uri);
+                return vec![publish_diagnostics(&uri, vec![])];
+            }
+            _ => return vec![],
+        }
+
+        let file = match self.files.get(&uri) {
+            Some(file) => file,
+            None => return vec![],
+        };
+        let index = LineIndex::new(&file.text);
+        let diagnostics = analysis::diagnostics(&file.text, file.path.as_deref())
+            .into_iter()
+            .map(|diagnostic| {
+                json!({
+                    "range": range(&index, diagnostic.span),
+                    "severity": 1,
+                    "source": "over",
+                    "message": diagnostic.message,
+                })
+            })
+            .collect();
+
+        vec![publish_diagnostics(&uri, diagnostics)]
+    }
+
+    fn definition(&self, params: &Json) -> RequestResult {
+        let (uri, file, offset) = self.position(params)?;
+        let doc = match Document::parse(&file.text) {
+            Ok(doc) => doc,
+            Err(_) => return Ok(Json::Null),
+        };
+
+        Ok(
+            match analysis::definition(&doc, file.path.as_deref(), offset) {
+                Some(Definition::Local(steps)) => {
+                    let span = match steps.last() {
+                        Some(Step::Field(field)) => field.name_span(),
+                        Some(Step::Element(_, node)) => node.span(),
+                        None => return Ok(Json::Null),
+                    };
+                    json!({ "uri": uri, "range": range(&LineIndex::new(&file.text), span) })
+                }
+                Some(Definition::File(path)) if path.is_file() => {
+                    let path = path.canonicalize().unwrap_or(path);
+                    json!({
+                        "uri": path_to_uri(&path),
+                        "range": { "start": position(0, 0), "end": position(0, 0) },
+                    })
+                }
+                _ => Json::Null,
+            },
+        )
+    }
+
+    fn hover(&self, params: &Json) -> RequestResult {
+        let (_, file, offset) = self.position(params)?;
+        let doc = match Document::parse(&file.text) {
+            Ok(doc) => doc,
+            Err(_) => return Ok(Json::Null),
+        };
+
+        Ok(match analysis::hover(&doc, file.path.as_deref(), offset) {
+            Some((value, span)) => json!({
+                "contents": {
+                    "kind": "markdown",
+                    "value": format!("```over\n{}\n```\n\nType: `{}`", value, value.get_type()),
+                },
+                "range": range(&LineIndex::new(&file.text), span),
+            }),
+            None => Json::Null,
+        })
+    }
+
+    fn completion(&self, params: &Json) -> RequestResult {
+        let (_, file, offset) = self.position(params)?;
+
+        // The file is usually incomplete while typing a value, so try again with a placeholder.
+        let doc = match Document::parse(&file.text) {
+            Ok(doc) => doc,
+            Err(_) => {
+                let mut text = file.text.clone();
+                text.insert_str(offset, "null");
+                match Document::parse(&text) {
+                    Ok(doc) => doc,
+                    Err(_) => return Ok(json!([])),
+                }
+            }
+        };
+
+        let items: Vec<Json> = analysis::completions(&doc, offset)
+            .into_iter()
+            .map(|completion| {
+                // Variable and field kinds.
+                let kind = if completion.is_global { 6 } else { 5 };
+                json!({ "label": completion.label, "kind": kind })
+            })
+            .collect();
+
+        Ok(Json::Array(items))
+    }
+
+    fn formatting(&self, params: &Json) -> RequestResult {
+        let (_, file) = self.file(params)?;
+
+        let formatted = match Document::parse(&file.text)
+            .ok()
+            .and_then(|doc| analysis::format(&doc, file.path.as_deref()))
+        {
+            Some(formatted) => formatted,
+            None => return Ok(Json::Null),
+        };
+        if formatted == file.text {
+            return Ok(json!([]));
+        }
+
+        let index = LineIndex::new(&file.text);
+        Ok(json!([{ "range": range(&index, 0..file.text.len()), "newText": formatted }]))
+    }
+
+    fn file<'a>(&'a self, params: &'a Json) -> Result<(&'a str, &'a File), (i64, String)> {
+        let uri = params
+            .pointer("/textDocument/uri")
+            .and_then(Json::as_str)
+            .ok_or_else(|| (INVALID_PARAMS, "Missing textDocument.uri".to_string()))?;
+
+        match self.files.get(uri) {
+            Some(file) => Ok((uri, file)),
+            None => Err((INVALID_PARAMS, format!("Unknown document {}", uri))),
+        }
+    }
+
+    // Returns the file and byte offset of a text document position.
+    fn position<'a>(
+        &'a self,
+        params: &'a Json,
+    ) -> Result<(&'a str, &'a File, usize), (i64, String)> {
+        let (uri, file) = self.file(params)?;
+
+        let get = |name| {
+            params
+                .pointer(&format!("/position/{}", name))
+                .and_then(Json::as_u64)
+                .ok_or_else(|| (INVALID_PARAMS, format!("Missing position.{}", name)))
+        };
+        let offset =
+            LineIndex::new(&file.text).offset(get("line")? as usize, get("character")? as usize);
+
+        Ok((uri, file, offset))
+    }
+}
+
+fn publish_diagnostics(uri: &str, diagnostics: Vec<Json>) -> Json {
+    protocol::notification(
+        "textDocument/publishDiagnostics",
+        json!({ "uri": uri, "diagnostics": diagnostics }),
+    )
+}
+
+fn position(line: usize, character: usize) -> Json {
+    json!({ "line": line, "character": character })
+}
+
+fn range(index: &LineIndex, span: Span) -> Json {
+    let (start_line, start_char) = index.position(span.start);
+    let (end_line, end_char) = index.position(span.end);
+
+    json!({ "start": position(start_line, start_char), "end": position(end_line, end_char) })
+}
+
+// Returns the path of a `file://` URI.
+fn uri_to_path(uri: &str) -> Option<String> {
+    let path = uri.strip_prefix("file://")?;
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match (bytes[i], path.get(i + 1..i + 3)) {
+            (b'%', Some(hex)) if u8::from_str_radix(hex, 16).is_ok() => {
+                decoded.push(u8::from_str_radix(hex, 16).unwrap());
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+
+    String::from_utf8(decoded).ok()
+}
+
+fn path_to_uri(path: &Path) -> String {
+    let mut uri = String::from("file://");
+
+    for byte in path.to_string_lossy().bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'/' | b'-' | b'_' | b'.' | b'~' => {
+                uri.push(byte as char)
+            }
+            _ => uri.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+
+    uri
+}
@@ -0,0 +1,438 @@
+//! Tests for the language server, driving the binary through a scripted client over stdio.
+
+use over::obj::Obj;
+use serde_json::{json, Value as Json};
+use std::{
+    collections::VecDeque,
+    io::{BufRead, BufReader, Read, Write},
+    path::Path,
+    process::{Child, ChildStdin, ChildStdout, Command, Stdio},
+    str::FromStr,
+};
+
+// A client which sends messages to the server and reads its replies.
+struct Client {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+    next_id: i64,
+    // Notifications received while waiting for a response.
+    notifications: VecDeque<Json>,
+}
+
+impl Client {
+    fn start() -> Self {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_over-lsp"))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let stdin = child.stdin.take().unwrap();
+        let stdout = BufReader::new(child.stdout.take().unwrap());
+
+        Self {
+            child,
+            stdin,
+            stdout,
+            next_id: 1,
+            notifications: VecDeque::new(),
+        }
+    }
+
+    fn send(&mut self, msg: Json) {
+        let content = msg.to_string();
+        write!(
+            self.stdin,
+            "Content-Length: {}\r\n\r\n{}",
+            content.len(),
+            content
+        )
+        .unwrap();
+        self.stdin.flush().unwrap();
+    }
+
+    fn receive(&mut self) -> Json {
+        let mut len = 0;
+        loop {
+            let mut line = String::new();
+            assert!(self.stdout.read_line(&mut line).unwrap() > 0);
+            let line = line.trim_end();
+            if line.is_empty() {
+                break;
+            }
+            if let Some(value) = line.strip_prefix("Content-Length: ") {
+                len = value.parse().unwrap();
+            }
+        }
+
+        let mut content = vec![0; len];
+        self.stdout.read_exact(&mut content).unwrap();
+        serde_json::from_slice(&content).unwrap()
+    }
+
+    // Sends a request and returns the whole response.
+    fn request_raw(&mut self, method: &str, params: Json) -> Json {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.send(json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }));
+
+        loop {
+            let msg = self.receive();
+            if msg.get("id") == Some(&json!(id)) {
+                return msg;
+            }
+            self.notifications.push_back(msg);
+        }
+    }
+
+    fn request(&mut self, method: &str, params: Json) -> Json {
+        let response = self.request_raw(method, params);
+        assert_eq!(response.get("error"), None);
+        response["result"].clone()
+    }
+
+    fn notify(&mut self, method: &str, params: Json) {
+        self.send(json!({ "jsonrpc": "2.0", "method": method, "params": params }));
+    }
+
+    // Returns the next diagnostics published for `uri`.
+    fn diagnostics(&mut self, uri: &str) -> Json {
+        loop {
+            let msg = match self.notifications.pop_front() {
+                Some(msg) => msg,
+                None => self.receive(),
+            };
+            if msg["method"] == "textDocument/publishDiagnostics" && msg["params"]["uri"] == uri {
+                return msg["params"]["diagnostics"].clone();
+            }
+        }
+    }
+
+    fn open(&mut self, uri: &str, text: &str) -> Json {
+        self.notify(
+            "textDocument/didOpen",
+            json!({
+                "textDocument": { "uri": uri, "languageId": "over", "version": 1, "text": text }
+            }),
+        );
+        self.diagnostics(uri)
+    }
+
+    fn change(&mut self, uri: &str, text: &str) -> Json {
+        self.notify(
+            "textDocument/didChange",
+            json!({
+                "textDocument": { "uri": uri, "version": 2 },
+                "contentChanges": [{ "text": text }],
+            }),
+        );
+        self.diagnostics(uri)
+    }
+
+    fn at(&mut self, method: &str, uri: &str, line: u64, character: u64) -> Json {
+        self.request(
+            method,
+            json!({
+                "textDocument": { "uri": uri },
+                "position": { "line": line, "character": character },
+            }),
+        )
+    }
+
+    // Shuts down the server and returns its exit code.
+    fn shutdown(mut self) -> i32 {
+        assert_eq!(self.request("shutdown", Json::Null), Json::Null);
+        self.notify("exit", Json::Null);
+        self.child.wait().unwrap().code().unwrap()
+    }
+}
+
+fn range(start: (u64, u64), end: (u64, u64)) -> Json {
+    json!({
+        "start": { "line": start.0, "character": start.1 },
+        "end": { "line": end.0, "character": end.1 },
+    })
+}
+
+fn labels(completions: &Json) -> Vec<&str> {
+    completions
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|item| item["label"].as_str().unwrap())
+        .collect()
+}
+
+fn file_uri(name: &str) -> String {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/files")
+        .join(name)
+        .canonicalize()
+        .unwrap();
+
+    format!("file://{}", path.to_str().unwrap())
+}
+
+// Test initialization, shutting down and unknown methods.
+#[test]
+fn lifecycle() {
+    let mut client = Client::start();
+
+    let result = client.request("initialize", json!({ "capabilities": {} }));
+    let capabilities = &result["capabilities"];
+    assert_eq!(capabilities["definitionProvider"], true);
+    assert_eq!(capabilities["hoverProvider"], true);
+    assert_eq!(capabilities["documentFormattingProvider"], true);
+    assert_eq!(
+        capabilities["completionProvider"]["triggerCharacters"],
+        json!(["@", "."])
+    );
+    client.notify("initialized", json!({}));
+
+    let response = client.request_raw("workspace/symbol", json!({ "query": "" }));
+    assert_eq!(response["error"]["code"], -32601);
+
+    assert_eq!(client.shutdown(), 0);
+
+    // Exiting without shutting down first is an error.
+    let mut client = Client::start();
+    client.notify("exit", Json::Null);
+    assert_eq!(client.child.wait().unwrap().code(), Some(1));
+}
+
+// Test diagnostics for parse errors.
+#[test]
+fn diagnostics() {
+    let mut client = Client::start();
+    let _ = client.request("initialize", json!({ "capabilities": {} }));
+
+    let uri = file_uri("main.over");
+    let text = std::fs::read_to_string("tests/files/main.over").unwrap();
+    assert_eq!(client.open(&uri, &text), json!([]));
+
+    let diagnostics = client.change(&uri, "a: 1\nb: [1 c]\n");
+    assert_eq!(
+        diagnostics,
+        json!([{
+            "range": range((1, 6), (1, 7)),
+            "severity": 1,
+            "source": "over",
+            "message": "Variable \"c\" at line 2, column 7 could not be found",
+        }])
+    );
+
+    // Columns count UTF-16 code units.
+    let diagnostics = client.change(&uri, "a: \"ü😀\" b: $");
+    assert_eq!(diagnostics[0]["range"], range((0, 12), (0, 13)));
+
+    // Errors with positions from evaluating values.
+    let diagnostics = client.change(&uri, "t: (1 2)\nb: t.5\n");
+    assert_eq!(
+        diagnostics[0]["message"],
+        "Tup index 5 out of bounds at line 2, col 6"
+    );
+    assert_eq!(diagnostics[0]["range"], range((1, 5), (1, 6)));
+
+    let diagnostics = client.change(&uri, "a: {\n");
+    assert_eq!(diagnostics[0]["message"], "Unexpected end at line 2");
+    assert_eq!(diagnostics[0]["range"], range((1, 0), (1, 0)));
+
+    // Errors in included files are reported at the top of the file.
+    let diagnostics = client.change(&uri, "# Includes.\na: <Obj \"colors.over\">\n");
+    let message = diagnostics[0]["message"].as_str().unwrap();
+    assert!(message.contains("colors.over"), "{}", message);
+    assert_eq!(diagnostics[0]["range"], range((0, 0), (0, 11)));
+
+    client.notify(
+        "textDocument/didClose",
+        json!({ "textDocument": { "uri": uri } }),
+    );
+    assert_eq!(client.diagnostics(&uri), json!([]));
+
+    assert_eq!(client.shutdown(), 0);
+}
+
+// Test go-to-definition.
+#[test]
+fn definition() {
+    let mut client = Client::start();
+    let _ = client.request("initialize", json!({ "capabilities": {} }));
+
+    let uri = file_uri("main.over");
+    let text = std::fs::read_to_string("tests/files/main.over").unwrap();
+    assert_eq!(client.open(&uri, &text), json!([]));
+
+    let default = json!({ "uri": uri, "range": range((2, 0), (2, 8)) });
+    let scale = json!({ "uri": uri, "range": range((7, 0), (7, 6)) });
+
+    // The parent's value.
+    assert_eq!(client.at("textDocument/definition", &uri, 10, 10), default);
+    // The `^` field itself.
+    assert_eq!(client.at("textDocument/definition", &uri, 10, 4), default);
+    // Globals, including at the end of a word.
+    assert_eq!(client.at("textDocument/definition", &uri, 12, 26), scale);
+    assert_eq!(client.at("textDocument/definition", &uri, 12, 32), scale);
+    // Dot access.
+    assert_eq!(
+        client.at("textDocument/definition", &uri, 12, 19),
+        json!({ "uri": uri, "range": range((3, 4), (3, 9)) })
+    );
+    assert_eq!(
+        client.at("textDocument/definition", &uri, 15, 24),
+        json!({ "uri": uri, "range": range((9, 0), (9, 6)) })
+    );
+    assert_eq!(
+        client.at("textDocument/definition", &uri, 15, 30),
+        json!({ "uri": uri, "range": range((12, 4), (12, 8)) })
+    );
+    // Includes.
+    assert_eq!(
+        client.at("textDocument/definition", &uri, 16, 16),
+        json!({ "uri": file_uri("colors.over"), "range": range((0, 0), (0, 0)) })
+    );
+    // Field names, strings and comments have no definitions.
+    assert_eq!(
+        client.at("textDocument/definition", &uri, 11, 6),
+        Json::Null
+    );
+    assert_eq!(
+        client.at("textDocument/definition", &uri, 11, 14),
+        Json::Null
+    );
+    assert_eq!(client.at("textDocument/definition", &uri, 0, 4), Json::Null);
+
+    // Variables are only found in the same object, before their use.
+    let uri = "untitled:scope";
+    let _ = client.open(uri, "a: 1\nb: { c: a d: c }\ne: f f: 2");
+    assert_eq!(client.at("textDocument/definition", uri, 1, 9), Json::Null);
+    assert_eq!(
+        client.at("textDocument/definition", uri, 1, 13),
+        json!({ "uri": uri, "range": range((1, 5), (1, 6)) })
+    );
+    assert_eq!(client.at("textDocument/definition", uri, 2, 3), Json::Null);
+
+    assert_eq!(client.shutdown(), 0);
+}
+
+// Test hovering over fields and references.
+#[test]
+fn hover() {
+    let mut client = Client::start();
+    let _ = client.request("initialize", json!({ "capabilities": {} }));
+
+    let uri = file_uri("main.over");
+    let text = std::fs::read_to_string("tests/files/main.over").unwrap();
+    assert_eq!(client.open(&uri, &text), json!([]));
+
+    assert_eq!(
+        client.at("textDocument/hover", &uri, 12, 6),
+        json!({
+            "contents": { "kind": "markdown", "value": "```over\n20\n```\n\nType: `Int`" },
+            "range": range((12, 4), (12, 8)),
+        })
+    );
+    // The value of a reference.
+    assert_eq!(
+        client.at("textDocument/hover", &uri, 15, 30)["contents"]["value"],
+        "```over\n20\n```\n\nType: `Int`"
+    );
+    // Included values.
+    assert_eq!(
+        client.at("textDocument/hover", &uri, 16, 2)["contents"]["value"],
+        "```over\n[\n    \"red\"\n    \"green\"\n]\n```\n\nType: `Arr(Str)`"
+    );
+    // Elements.
+    assert_eq!(
+        client.at("textDocument/hover", &uri, 15, 1)["contents"]["value"],
+        "```over\n[\n    10\n    20\n]\n```\n\nType: `Arr(Int)`"
+    );
+    // Globals are not part of the evaluated object.
+    assert_eq!(client.at("textDocument/hover", &uri, 12, 30), Json::Null);
+    assert_eq!(client.at("textDocument/hover", &uri, 1, 0), Json::Null);
+
+    // Nothing is shown for files with errors.
+    let _ = client.change(&uri, "a: 1 b: c");
+    assert_eq!(client.at("textDocument/hover", &uri, 0, 0), Json::Null);
+
+    assert_eq!(client.shutdown(), 0);
+}
+
+// Test completion of fields and globals.
+#[test]
+fn completion() {
+    let mut client = Client::start();
+    let _ = client.request("initialize", json!({ "capabilities": {} }));
+
+    let uri = "untitled:completion";
+    let _ = client.open(
+        uri,
+        "a: 1\n@g: { x: 1 y: 2 }\nb: {\n    c: 3\n    d: \n    e: 4\n}\n",
+    );
+
+    // Incomplete files are completed too.
+    let completions = client.at("textDocument/completion", uri, 4, 7);
+    assert_eq!(labels(&completions), vec!["c", "@g"]);
+    assert_eq!(completions[0]["kind"], 5);
+    assert_eq!(completions[1]["kind"], 6);
+
+    let _ = client.change(uri, "a: 1\n@g: { x: 1 y: 2 }\nb: @g.\n");
+    assert_eq!(
+        labels(&client.at("textDocument/completion", uri, 2, 6)),
+        vec!["x", "y"]
+    );
+
+    let _ = client.change(uri, "a: 1\n@g: { x: 1 y: 2 }\nb: [a @]\n");
+    assert_eq!(
+        labels(&client.at("textDocument/completion", uri, 2, 7)),
+        vec!["@g"]
+    );
+
+    assert_eq!(client.shutdown(), 0);
+}
+
+// Test document formatting.
+#[test]
+fn formatting() {
+    let mut client = Client::start();
+    let _ = client.request("initialize", json!({ "capabilities": {} }));
+
+    let uri = "untitled:formatting";
+    let text = "a:   1\nb: [1   2] c: { d: \"#\" }";
+    let _ = client.open(uri, text);
+
+    let formatted = Obj::from_str(text).unwrap().write_to_string();
+    let params = json!({
+        "textDocument": { "uri": uri },
+        "options": { "tabSize": 4, "insertSpaces": true },
+    });
+    assert_eq!(
+        client.request("textDocument/formatting", params.clone()),
+        json!([{ "range": range((0, 0), (1, 24)), "newText": formatted }])
+    );
+
+    let _ = client.change(uri, &formatted);
+    assert_eq!(
+        client.request("textDocument/formatting", params.clone()),
+        json!([])
+    );
+
+    // Files are not formatted if anything but whitespace would be lost.
+    for text in &[
+        "a: {",
+        "a: 1 # Comment.",
+        "@g: 1 a: @g",
+        "a: 1 b: a+1",
+        "a: <Obj \"colors.over\">",
+        "a: { x: 1 } b: { ^: a }",
+    ] {
+        let _ = client.change(uri, text);
+        assert_eq!(
+            client.request("textDocument/formatting", params.clone()),
+            Json::Null,
+            "{}",
+            text
+        );
+    }
+
+    assert_eq!(client.shutdown(), 0);
+}
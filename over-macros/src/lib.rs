@@ -0,0 +1,391 @@
+//! Macros for writing OVER in Rust code, checked when the code is compiled.
+//!
+//! `over!` turns OVER syntax into an `Obj`:
+//!
+//! ```
+//! use over_macros::over;
+//!
+//! let obj = over! {
+//!     @base: { currency: "USD" }
+//!     item: {
+//!         ^: @base
+//!         price: 133.70
+//!         tags: ["a" "b"]
+//!     }
+//! };
+//!
+//! let item = obj.get_obj("item").unwrap();
+//! assert_eq!(item.get_str("currency").unwrap(), "USD");
+//! assert_eq!(item.get("price").unwrap(), over::value::Value::Frac("1337/10".parse().unwrap()));
+//! ```
+//!
+//! Since the contents are read by the Rust tokenizer first, comments are written as Rust comments
+//! and strings as Rust string literals. Parse errors are compile errors pointing at the token which
+//! caused them:
+//!
+//! ```compile_fail
+//! let obj = over_macros::over! { tags: ["a" 1] };
+//! ```
+//!
+//! Rust values can be interpolated with `#name` or `#(expression)`, using `over::IntoOver`. They
+//! are available as globals, so they can be used anywhere a value can:
+//!
+//! ```
+//! use over_macros::over;
+//!
+//! let port: u16 = 8080;
+//! let hosts = vec!["a", "b"];
+//! let obj = over! { port: #port+1 hosts: #(hosts.clone()) };
+//!
+//! assert_eq!(obj.get("port").unwrap(), 8081u16);
+//! ```
+//!
+//! The values are only known when the program runs, so an `over!` with interpolations is parsed
+//! again then, and panics if an interpolated value causes an error, such as a type mismatch in an
+//! `Arr`. Includes are resolved relative to the source file, which is only known at compile time,
+//! so they cannot be used together with interpolations:
+//!
+//! ```compile_fail
+//! let port = 8080;
+//! let obj = over_macros::over! { port: #port colors: <"colors.over"> };
+//! ```
+//!
+//! `include_over!` parses a .over file at compile time and embeds it, so the file is not needed
+//! when the program runs. The path is relative to the source file, like `include_str!`:
+//!
+//! ```ignore
+//! let config = over_macros::include_over!("config.over");
+//! ```
+
+extern crate proc_macro;
+
+use over::{document::Document, obj::Obj, value::Value};
+use proc_macro::{Delimiter, Span, TokenStream, TokenTree};
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{quote, quote_spanned};
+use std::{env, fs, ops::Range, path::PathBuf};
+use syn::{parse_macro_input, LitStr};
+
+/// Returns the `Obj` written in OVER syntax.
+#[proc_macro]
+pub fn over(input: TokenStream) -> TokenStream {
+    let mut source = Source::default();
+
+    let result = source.push_stream(input).and_then(|()| {
+        if source.interpolations.is_empty() {
+            source.evaluate()
+        } else {
+            source.evaluate_at_run_time()
+        }
+    });
+
+    result.unwrap_or_else(|error| error).into()
+}
+
+/// Returns the `Obj` in the .over file at the given path, relative to the current source file.
+#[proc_macro]
+pub fn include_over(input: TokenStream) -> TokenStream {
+    let path = parse_macro_input!(input as LitStr);
+
+    include(&path).unwrap_or_else(|error| error).into()
+}
+
+fn include(path: &LitStr) -> Result<TokenStream2, TokenStream2> {
+    let error = |message: String| syn::Error::new(path.span(), message).to_compile_error();
+
+    let full_path = source_dir().join(path.value());
+    let full_path = fs::canonicalize(&full_path)
+        .map_err(|e| error(format!("Could not read {}: {}", full_path.display(), e)))?;
+    let (obj, deps) =
+        Obj::from_file_with_deps(&full_path.to_string_lossy()).map_err(|e| error(e.to_string()))?;
+
+    let obj = obj_tokens(&obj);
+
+    // Depend on every file read, so that the crate is rebuilt when one of them changes.
+    Ok(quote! {{
+        #( const _: &[u8] = ::std::include_bytes!(#deps); )*
+        #obj
+    }})
+}
+
+// The OVER source written in a macro, rebuilt from its tokens.
+#[derive(Default)]
+struct Source {
+    text: String,
+    // The range of each token in `text`, and its span.
+    tokens: Vec<(Range<usize>, Span)>,
+    // Interpolated expressions. The Nth is written in `text` as the global `@__over_N`.
+    interpolations: Vec<TokenStream2>,
+    // The line and column where the last token ended.
+    end: Option<(usize, usize)>,
+}
+
+impl Source {
+    // Adds the text of a token starting at `start` and ending at `end`. Tokens are separated as
+    // they were in the macro, since OVER does not allow spaces in some places, such as around
+    // operators.
+    fn push(&mut self, text: &str, start: Span, end: Span) {
+        let (line, col) = (start.line(), start.column());
+        match self.end {
+            Some(end) if end == (line, col) => (),
+            Some((end_line, _)) if end_line < line => self.text.push('\n'),
+            Some(_) => self.text.push(' '),
+            None => (),
+        }
+
+        let offset = self.text.len();
+        self.text.push_str(text);
+        self.tokens.push((offset..self.text.len(), start));
+        self.end = Some((end.end().line(), end.end().column()));
+    }
+
+    fn push_stream(&mut self, stream: TokenStream) -> Result<(), TokenStream2> {
+        let mut tokens = stream.into_iter();
+
+        while let Some(token) = tokens.next() {
+            match token {
+                TokenTree::Group(ref group) => {
+                    let (open, close) = match group.delimiter() {
+                        Delimiter::Brace => ("{", "}"),
+                        Delimiter::Bracket => ("[", "]"),
+                        Delimiter::Parenthesis => ("(", ")"),
+                        Delimiter::None => {
+                            self.push_stream(group.stream())?;
+                            continue;
+                        }
+                    };
+
+                    self.push(open, group.span_open(), group.span_open());
+                    self.push_stream(group.stream())?;
+                    self.push(close, group.span_close(), group.span_close());
+                }
+                TokenTree::Punct(ref punct) if punct.as_char() == '#' => {
+                    let (expr, end) = match tokens.next() {
+                        Some(TokenTree::Ident(ident)) => {
+                            let span = ident.span();
+                            (TokenStream::from(TokenTree::Ident(ident)), span)
+                        }
+                        Some(TokenTree::Group(ref group))
+                            if group.delimiter() == Delimiter::Parenthesis =>
+                        {
+                            (group.stream(), group.span_close())
+                        }
+                        _ => {
+                            return Err(error(
+                                punct.span(),
+                                "Expected a variable or a parenthesized expression after '#'",
+                            ))
+                        }
+                    };
+
+                    let name = format!("@__over_{}", self.interpolations.len());
+                    self.interpolations.push(expr.into());
+                    self.push(&name, punct.span(), end);
+                }
+                TokenTree::Punct(ref punct) => {
+                    self.push(&punct.as_char().to_string(), punct.span(), punct.span())
+                }
+                TokenTree::Ident(ref ident) => {
+                    self.push(&ident.to_string(), ident.span(), ident.span())
+                }
+                TokenTree::Literal(ref literal) => {
+                    self.push(&literal.to_string(), literal.span(), literal.span())
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    // Parses the source now, returning code which builds the resulting `Obj`.
+    fn evaluate(&self) -> Result<TokenStream2, TokenStream2> {
+        let path = source_file();
+        let path = path.to_string_lossy();
+
+        let obj = Obj::from_str_in_file(&self.text, &path)
+            .map_err(|e| self.error(&e.to_string(), Some(&path)))?;
+
+        Ok(obj_tokens(&obj))
+    }
+
+    // Checks the structure of the source now, returning code which parses it with the
+    // interpolated values when the program runs.
+    fn evaluate_at_run_time(&self) -> Result<TokenStream2, TokenStream2> {
+        Document::parse(&self.text).map_err(|e| self.error(&e.to_string(), None))?;
+
+        // '<' only starts includes, as strings are single literal tokens.
+        if let Some(&(_, span)) = self
+            .tokens
+            .iter()
+            .find(|(range, _)| &self.text[range.clone()] == "<")
+        {
+            return Err(error(
+                span,
+                "Includes cannot be used in an `over!` with interpolations, as they would be \
+                 resolved relative to the current directory when the program runs",
+            ));
+        }
+
+        let text = &self.text;
+        let names = (0..self.interpolations.len()).map(|i| format!("__over_{}", i));
+        let exprs = &self.interpolations;
+
+        // The expressions are evaluated before any variables are declared, so that they cannot
+        // refer to them.
+        Ok(quote! {{
+            let values = [#(
+                ::over::convert::IntoOver::into_over(#exprs).unwrap_or_else(|e| panic!("{}", e))
+            ),*];
+            let mut ctx = ::over::context::ParseContext::new();
+            for (name, value) in [#(#names),*].iter().zip(values.iter()) {
+                ctx.set_global(name, value.clone()).unwrap();
+            }
+            ::over::obj::Obj::from_str_with_context(#text, &ctx)
+                .unwrap_or_else(|e| panic!("{}", e))
+        }})
+    }
+
+    // Returns a compile error for a parse error `message`, pointing at the token it names. Errors
+    // from other files, such as included files, point at the whole macro. If `path` is given,
+    // errors in the source start with it.
+    fn error(&self, message: &str, path: Option<&str>) -> TokenStream2 {
+        let local = match path {
+            Some(path) => message
+                .strip_prefix(path)
+                .and_then(|message| message.strip_prefix(": ")),
+            None => Some(message),
+        };
+
+        match local.and_then(split_location) {
+            Some((message, line, col)) => error(self.span_at(line, col), &message),
+            None => error(Span::call_site(), message),
+        }
+    }
+
+    // Returns the span of the token at, or else following, the line and column in `text`, both
+    // counting from 1. A missing column means the end of the line.
+    fn span_at(&self, line: usize, col: Option<usize>) -> Span {
+        let line_start: usize = self
+            .text
+            .split('\n')
+            .take(line - 1)
+            .map(|line| line.len() + 1)
+            .sum();
+        let line_text = self.text[line_start.min(self.text.len())..]
+            .split('\n')
+            .next()
+            .unwrap_or_default();
+        let offset = line_start
+            + match col {
+                Some(col) => line_text
+                    .char_indices()
+                    .nth(col - 1)
+                    .map_or(line_text.len(), |(i, _)| i),
+                None => line_text.len().saturating_sub(1),
+            };
+
+        self.tokens
+            .iter()
+            .find(|(range, _)| range.end > offset)
+            .or_else(|| self.tokens.last())
+            .map_or_else(Span::call_site, |&(_, span)| span)
+    }
+}
+
+// Splits a parse error message into the message without its location, and the line and column of
+// the location.
+fn split_location(message: &str) -> Option<(String, usize, Option<usize>)> {
+    let start = message.find(" at line ")?;
+    let (line, rest) = split_number(&message[start + " at line ".len()..])?;
+
+    let (col, rest) = match rest.strip_prefix(", column ") {
+        Some(rest) => {
+            let (col, rest) = split_number(rest)?;
+            (Some(col), rest)
+        }
+        None => (None, rest),
+    };
+
+    Some((format!("{}{}", &message[..start], rest), line, col))
+}
+
+fn split_number(s: &str) -> Option<(usize, &str)> {
+    let len = s.find(|ch: char| !ch.is_ascii_digit()).unwrap_or(s.len());
+    let number = s[..len].parse().ok()?;
+
+    Some((number, &s[len..]))
+}
+
+fn error(span: Span, message: &str) -> TokenStream2 {
+    let span = proc_macro2::Span::from(span);
+
+    quote_spanned!(span=> ::std::compile_error!(#message))
+}
+
+// The file the macro was called from, or the crate's manifest directory if it is not known.
+fn source_file() -> PathBuf {
+    Span::call_site().local_file().unwrap_or_else(|| {
+        PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap_or_default()).join("over!")
+    })
+}
+
+fn source_dir() -> PathBuf {
+    source_file()
+        .parent()
+        .map(PathBuf::from)
+        .unwrap_or_default()
+}
+
+// Returns code building `obj`.
+fn obj_tokens(obj: &Obj) -> TokenStream2 {
+    let fields = obj.pairs_ref().iter().map(|pair| &pair.0);
+    let values = obj.pairs_ref().iter().map(|pair| value_tokens(&pair.1));
+    let parents = obj.parents_ref().iter().map(obj_tokens);
+
+    quote! {
+        ::over::obj::Obj::from_pairs_with_parents_unchecked(
+            ::std::vec![#(
+                ::over::obj::Pair(::std::string::String::from(#fields), #values)
+            ),*],
+            ::std::vec![#(#parents),*],
+        )
+    }
+}
+
+// Returns code building `value`.
+fn value_tokens(value: &Value) -> TokenStream2 {
+    match *value {
+        Value::Null => quote!(::over::value::Value::Null),
+        Value::Bool(b) => quote!(::over::value::Value::Bool(#b)),
+        Value::Int(ref int) => {
+            let int = int.to_string();
+            match int.parse::<i64>() {
+                Ok(int) => quote!(::over::value::Value::from(#int)),
+                Err(_) => quote!(::over::value::Value::Int(#int.parse().unwrap())),
+            }
+        }
+        Value::Frac(ref frac) => {
+            let frac = frac.to_string();
+            quote!(::over::value::Value::Frac(#frac.parse().unwrap()))
+        }
+        Value::Str(ref s) => quote!(::over::value::Value::Str(::std::string::String::from(#s))),
+        Value::Arr(ref arr) => {
+            let values = arr.iter().map(value_tokens);
+            quote! {
+                ::over::value::Value::Arr(
+                    ::over::arr::Arr::from_values(::std::vec![#(#values),*]).unwrap(),
+                )
+            }
+        }
+        Value::Tup(ref tup) => {
+            let values = tup.iter().map(value_tokens);
+            quote! {
+                ::over::value::Value::Tup(::over::tup::Tup::from_values(::std::vec![#(#values),*]))
+            }
+        }
+        Value::Obj(ref obj) => {
+            let obj = obj_tokens(obj);
+            quote!(::over::value::Value::Obj(#obj))
+        }
+    }
+}
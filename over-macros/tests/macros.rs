@@ -0,0 +1,93 @@
+use over::{value::Value, Obj, OverResult};
+use over_macros::{include_over, over};
+
+// Test that `over!` gives the same `Obj` as parsing the same source.
+#[test]
+fn over() -> OverResult<()> {
+    let obj = over! {
+        // Globals, parents and arithmetic.
+        @base: { currency: "USD" rate: 1,5 }
+        item: {
+            ^: @base
+            price: 133.70
+            discount: -5-1/4
+            total: @base.rate*2
+            tags: ["a" "b"]
+            sizes: ((1 2) null true)
+        }
+        colors: <Obj "files/colors.over">
+        big: 123456789012345678901234567890
+    };
+
+    let expected: Obj = r##"
+@base: { currency: "USD" rate: 1,5 }
+item: {
+    ^: @base
+    price: 133.70
+    discount: -5-1/4
+    total: @base.rate*2
+    tags: ["a" "b"]
+    sizes: ((1 2) null true)
+}
+colors: { red: "#ff0000" blue: "#0000ff" }
+big: 123456789012345678901234567890
+"##
+    .parse()?;
+
+    assert_eq!(obj, expected);
+    assert_eq!(obj.get_obj("item")?.get_str("currency")?, "USD");
+    assert_eq!(over! {}, Obj::empty());
+
+    Ok(())
+}
+
+// Test interpolating Rust values into `over!`.
+#[test]
+fn over_interpolation() -> OverResult<()> {
+    let port: u16 = 8080;
+    let hosts = vec!["a", "b"];
+    let base = over! { host: "localhost" };
+
+    let obj = over! {
+        port: #port+1
+        hosts: #(hosts.clone())
+        server: { ^: #base name: #("main".to_string()) }
+        ports: [#port #(port * 2)]
+    };
+
+    let expected: Obj = r#"
+port: 8081
+hosts: ["a" "b"]
+server: { host: "localhost" name: "main" }
+ports: [8080 16160]
+"#
+    .parse()?;
+
+    assert_eq!(obj.get("port"), expected.get("port"));
+    assert_eq!(obj.get("hosts"), expected.get("hosts"));
+    assert_eq!(obj.get("ports"), expected.get("ports"));
+    assert_eq!(obj.get_obj("server")?.get_str("host")?, "localhost");
+    assert_eq!(obj.get_obj("server")?.get_str("name")?, "main");
+
+    Ok(())
+}
+
+// Test that an interpolated value which makes the source invalid panics.
+#[test]
+#[should_panic(expected = "Expected Int")]
+fn over_interpolation_error() {
+    let name = "a";
+    let _ = over! { values: [1 #name] };
+}
+
+// Test embedding a file with `include_over!`.
+#[test]
+fn include_over() -> OverResult<()> {
+    let obj = include_over!("files/theme.over");
+
+    assert_eq!(obj, Obj::from_file("tests/files/theme.over")?);
+    assert_eq!(obj.get("accent"), Some(Value::from("#ff0000")));
+    assert_eq!(obj.get_obj("window")?.get_str("blue")?, "#0000ff");
+
+    Ok(())
+}
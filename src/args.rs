@@ -0,0 +1,85 @@
+//! Overriding values with command-line style `path=value` arguments.
+//!
+//! The path is a dotted path as used by `patch`, e.g. `server.port` or `servers.0.host`, and the
+//! value is any single value in .over syntax, e.g. `9000` or `["a" "b"]`.
+//!
+//! # Examples
+//!
+//! ```
+//! # #[macro_use] extern crate over;
+//! # fn main() {
+//!
+//! let obj = obj! { "server" => obj! { "port" => 8080 }, "features" => arr!["a"] };
+//!
+//! let obj = over::args::apply_args(&obj, &["server.port=9000", "features=[\"a\" \"b\"]"]).unwrap();
+//!
+//! assert_eq!(
+//!     obj,
+//!     obj! { "server" => obj! { "port" => 9000 }, "features" => arr!["a", "b"] }
+//! );
+//!
+//! # }
+//! ```
+
+use crate::{
+    error::OverError,
+    obj::Obj,
+    patch::{self, Patch},
+    value::Value,
+    OverResult,
+};
+use std::str::FromStr;
+
+/// Parses a `path=value` argument into the path and the value.
+///
+/// Returns `OverError::InvalidArg`, naming the argument, if there is no `=` or the value could
+/// not be parsed.
+pub fn parse_arg(arg: &str) -> OverResult<(String, Value)> {
+    let (path, value) = match arg.find('=') {
+        Some(index) => (&arg[..index], &arg[index + 1..]),
+        None => return Err(invalid_arg(arg, "expected path=value")),
+    };
+
+    match Value::from_str(value) {
+        Ok(value) => Ok((path.into(), value)),
+        Err(e) => Err(invalid_arg(arg, e)),
+    }
+}
+
+/// Applies each `path=value` argument in order to `obj`, returning the resulting `Obj`.
+///
+/// A value which already exists, including an inherited one, may only be replaced by a value of
+/// the same type. Fields which do not exist are added to their `Obj`, which must exist.
+///
+/// Returns `OverError::InvalidArg`, naming the first argument which could not be applied.
+pub fn apply_args<S>(obj: &Obj, args: &[S]) -> OverResult<Obj>
+where
+    S: AsRef<str>,
+{
+    let mut obj = obj.clone();
+
+    for arg in args {
+        let arg = arg.as_ref();
+        let (path, value) = parse_arg(arg)?;
+
+        if let Ok(existing) = patch::get_at(&obj, &path) {
+            let (t1, t2) = (existing.get_type(), value.get_type());
+            if t1 != t2 {
+                return Err(invalid_arg(arg, OverError::FieldTypeMismatch(path, t1, t2)));
+            }
+        }
+
+        let mut patch = Patch::new();
+        patch.set(&path, value);
+        obj = patch.apply(&obj).map_err(|e| invalid_arg(arg, e))?;
+    }
+
+    Ok(obj)
+}
+
+fn invalid_arg<E>(arg: &str, error: E) -> OverError
+where
+    E: ToString,
+{
+    OverError::InvalidArg(arg.into(), error.to_string())
+}
@@ -4,7 +4,17 @@ use crate::{
     parse::format::Format, types::Type, value::Value, OverError, OverResult, ReferenceType,
     INDENT_STEP,
 };
-use std::{convert::TryFrom, fmt, slice::Iter, sync::Arc};
+use std::{
+    cmp::Ordering,
+    convert::TryFrom,
+    fmt,
+    hash::{Hash, Hasher},
+    iter::FromIterator,
+    ops::Index,
+    slice::Iter,
+    sync::Arc,
+    vec,
+};
 
 #[derive(Clone, Debug)]
 struct ArrInner {
@@ -113,7 +123,7 @@ impl Arr {
     }
 
     /// Returns an iterator over the Arr.
-    pub fn iter(&self) -> Iter<Value> {
+    pub fn iter(&self) -> Iter<'_, Value> {
         self.values_ref().iter()
     }
 }
@@ -152,15 +162,74 @@ impl TryFrom<Vec<Value>> for Arr {
     }
 }
 
-impl PartialEq for Arr {
-    fn eq(&self, other: &Self) -> bool {
-        // Quickly return false if the types don't match.
-        if self.inner.inner_t != other.inner.inner_t {
-            return false;
+/// Collects `Value`s into an `Arr`, returning an error if they are not all of the same type.
+impl FromIterator<Value> for OverResult<Arr> {
+    fn from_iter<I: IntoIterator<Item = Value>>(iter: I) -> Self {
+        Arr::from_values(iter.into_iter().collect())
+    }
+}
+
+/// Returns the value at `index`.
+///
+/// # Panics
+///
+/// Panics if `index` is out of bounds. Use `get` to return an error instead.
+impl Index<usize> for Arr {
+    type Output = Value;
+
+    fn index(&self, index: usize) -> &Value {
+        &self.inner.values[index]
+    }
+}
+
+impl<'a> IntoIterator for &'a Arr {
+    type Item = &'a Value;
+    type IntoIter = Iter<'a, Value>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Moves the values out of the `Arr`, cloning them if it is shared.
+impl IntoIterator for Arr {
+    type Item = Value;
+    type IntoIter = vec::IntoIter<Value>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        match Arc::try_unwrap(self.inner) {
+            Ok(inner) => inner.values,
+            Err(inner) => inner.values.clone(),
         }
+        .into_iter()
+    }
+}
 
+/// Two `Arr`s are equal if they have equal values. The element type is not compared, as it
+/// follows from the values, so all empty `Arr`s are equal whatever their type.
+impl PartialEq for Arr {
+    fn eq(&self, other: &Self) -> bool {
         self.inner.values == other.inner.values
     }
 }
 
 impl Eq for Arr {}
+
+impl Hash for Arr {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.inner.values.hash(state)
+    }
+}
+
+/// `Arr`s are ordered by comparing their values in order, as for slices.
+impl Ord for Arr {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.inner.values.cmp(&other.inner.values)
+    }
+}
+
+impl PartialOrd for Arr {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
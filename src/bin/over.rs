@@ -0,0 +1,83 @@
+//! Command-line tool for .over files.
+
+use over::lint::{self, LintOptions, Rule};
+use std::{env, process};
+
+const USAGE: &str = "Usage:
+    over lint [--allow <rule>]... <file>...
+    over help
+
+Commands:
+    lint    Warn about suspicious fields and values. Exits with 1 if there are warnings and 2 if
+            a file could not be parsed. `--allow <rule>` turns a rule off.
+    help    Print this message.";
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    let code = match args.first().map(String::as_str) {
+        Some("lint") => run_lint(&args[1..]),
+        Some("help") | Some("--help") | Some("-h") => {
+            println!("{}", USAGE);
+            0
+        }
+        _ => {
+            eprintln!("{}", USAGE);
+            2
+        }
+    };
+
+    process::exit(code);
+}
+
+fn run_lint(args: &[String]) -> i32 {
+    let mut options = LintOptions::default();
+    let mut files = vec![];
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--allow" | "-A" => {
+                let name = args.next().map(String::as_str).unwrap_or_default();
+                match Rule::from_name(name) {
+                    Some(rule) => options.set(rule, false),
+                    None => {
+                        let names: Vec<&str> = Rule::ALL.iter().map(|rule| rule.name()).collect();
+                        eprintln!(
+                            "Unknown rule \"{}\". The rules are: {}",
+                            name,
+                            names.join(", ")
+                        );
+                        return 2;
+                    }
+                }
+            }
+            file => files.push(file),
+        }
+    }
+
+    if files.is_empty() {
+        eprintln!("{}", USAGE);
+        return 2;
+    }
+
+    let mut code = 0;
+    for file in files {
+        match lint::lint_file(file, options) {
+            Ok(warnings) => {
+                for warning in &warnings {
+                    println!("{}: warning: {} [{}]", file, warning, warning.rule);
+                }
+                if !warnings.is_empty() && code == 0 {
+                    code = 1;
+                }
+            }
+            Err(e) => {
+                eprintln!("error: {}", e);
+                code = 2;
+            }
+        }
+    }
+
+    code
+}
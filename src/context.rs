@@ -0,0 +1,188 @@
+//! Context supplied by the host application when parsing.
+
+use crate::{error::OverError, obj::Obj, value::Value, OverResult};
+use std::{
+    collections::HashMap,
+    env::{self, VarError},
+    fmt,
+    sync::Arc,
+};
+
+/// A host function which can be called from .over files.
+///
+/// It receives the evaluated arguments and returns either the resulting `Value` or an error
+/// message, which is reported as a parse error at the call site.
+pub type Function = dyn Fn(&[Value]) -> Result<Value, String> + Send + Sync;
+
+/// A lookup for environment variables read with `env("NAME")`, returning `None` if the variable is
+/// not set.
+pub type EnvLookup = dyn Fn(&str) -> Option<String> + Send + Sync;
+
+/// Options controlling what .over files are allowed to do while parsing.
+///
+/// The defaults are safe for parsing untrusted files.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ParseOptions {
+    /// Whether files may read environment variables with `env("NAME")`. Defaults to `false`.
+    pub allow_env: bool,
+    /// Whether includes with literal paths, such as `<Arr "file.over">`, are parsed concurrently
+    /// on a thread pool before they are reached. Errors are reported exactly as when parsing
+    /// sequentially. Only has an effect with the `parallel` feature. Defaults to `false`.
+    ///
+    /// Includes are parsed speculatively: an include may be parsed even if it is never reached,
+    /// for instance because of an earlier error, and an include which fails is parsed again when
+    /// it is reached. Host functions and `env()` calls in included files may therefore run
+    /// concurrently, more than once, or for includes which do not end up in the result, so they
+    /// should be free of side effects when this is enabled.
+    pub parallel_includes: bool,
+}
+
+/// Predefined globals, host functions and options made available to .over files while parsing.
+///
+/// Predefined globals are visible in every parsed file, including included files, as if they had
+/// been declared at the top of the file. Declaring a global with the same name in a file is an
+/// error.
+///
+/// Host functions are called with the same syntax as built-in functions, e.g. `secret("db")`, and
+/// take precedence over built-in functions with the same name.
+///
+/// # Examples
+///
+/// ```
+/// # #[macro_use] extern crate over;
+/// # fn main() {
+///
+/// use over::{context::ParseContext, obj::Obj, value::Value};
+///
+/// let mut ctx = ParseContext::new();
+/// ctx.set_global("build", 1234).unwrap();
+/// ctx.set_function("port_for", |args: &[Value]| match args {
+///     [Value::Str(ref s)] if s == "api" => Ok(8080.into()),
+///     _ => Err("unknown service".into()),
+/// })
+/// .unwrap();
+///
+/// let obj = Obj::from_str_with_context("build: @build port: port_for(\"api\")", &ctx).unwrap();
+///
+/// assert_eq!(obj, obj! { "build" => 1234, "port" => 8080 });
+///
+/// # }
+/// ```
+#[derive(Clone, Default)]
+pub struct ParseContext {
+    // Predefined globals, keyed by name including the leading '@'.
+    globals: HashMap<String, Value>,
+    // Host functions, keyed by name.
+    functions: HashMap<String, Arc<Function>>,
+    // Lookup for environment variables, or `None` to read the process environment.
+    env: Option<Arc<EnvLookup>>,
+    // Parse options.
+    options: ParseOptions,
+}
+
+impl ParseContext {
+    /// Returns a new, empty context.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the global `@name` to `value`, replacing any previous value.
+    ///
+    /// `name` should not include the leading '@'. Returns an error if `name` is not a valid field
+    /// name.
+    pub fn set_global<V>(&mut self, name: &str, value: V) -> OverResult<()>
+    where
+        V: Into<Value>,
+    {
+        check_name(name)?;
+
+        let _ = self.globals.insert(format!("@{}", name), value.into());
+        Ok(())
+    }
+
+    /// Returns the value of the predefined global `@name`, if there is one.
+    pub fn get_global(&self, name: &str) -> Option<Value> {
+        self.globals.get(&format!("@{}", name)).cloned()
+    }
+
+    /// Registers the host function `name`, replacing any previous function with the same name.
+    ///
+    /// Returns an error if `name` is not a valid field name.
+    pub fn set_function<F>(&mut self, name: &str, function: F) -> OverResult<()>
+    where
+        F: Fn(&[Value]) -> Result<Value, String> + Send + Sync + 'static,
+    {
+        check_name(name)?;
+
+        let _ = self.functions.insert(name.into(), Arc::new(function));
+        Ok(())
+    }
+
+    /// Returns true if a host function called `name` has been registered.
+    pub fn has_function(&self, name: &str) -> bool {
+        self.functions.contains_key(name)
+    }
+
+    /// Sets the lookup used for `env("NAME")` instead of the process environment.
+    ///
+    /// This lets applications expose only some variables, or supply them from somewhere other than
+    /// the process environment, such as in tests. Files can still only read variables if
+    /// `ParseOptions::allow_env` is set.
+    pub fn set_env_lookup<F>(&mut self, lookup: F)
+    where
+        F: Fn(&str) -> Option<String> + Send + Sync + 'static,
+    {
+        self.env = Some(Arc::new(lookup));
+    }
+
+    /// Sets the parse options.
+    pub fn set_options(&mut self, options: ParseOptions) {
+        self.options = options;
+    }
+
+    /// Returns the parse options.
+    pub fn options(&self) -> ParseOptions {
+        self.options
+    }
+
+    // Returns the predefined globals, keyed by name including the leading '@'.
+    pub(crate) fn globals(&self) -> &HashMap<String, Value> {
+        &self.globals
+    }
+
+    // Reads the environment variable `name` through the lookup, if one is set, or else from the
+    // process environment.
+    pub(crate) fn env_var(&self, name: &str) -> Result<String, VarError> {
+        match self.env {
+            Some(ref lookup) => lookup(name).ok_or(VarError::NotPresent),
+            None => env::var(name),
+        }
+    }
+
+    // Returns the host function `name`, if there is one.
+    pub(crate) fn function(&self, name: &str) -> Option<&Function> {
+        self.functions.get(name).map(|function| function.as_ref())
+    }
+}
+
+impl fmt::Debug for ParseContext {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut functions: Vec<&String> = self.functions.keys().collect();
+        functions.sort();
+
+        f.debug_struct("ParseContext")
+            .field("globals", &self.globals)
+            .field("functions", &functions)
+            .field("env_lookup", &self.env.is_some())
+            .field("options", &self.options)
+            .finish()
+    }
+}
+
+fn check_name(name: &str) -> OverResult<()> {
+    if name.is_empty() || name.starts_with('^') || !Obj::is_valid_field(name) {
+        Err(OverError::InvalidFieldName(name.into()))
+    } else {
+        Ok(())
+    }
+}
@@ -0,0 +1,470 @@
+//! Conversions between Rust types and `Value`s.
+//!
+//! `FromOver` reads a type from a `Value` and `IntoOver` writes it back. Both are implemented for
+//! the primitive types, `String`, `Vec`, tuples, maps with `String` keys, and `Option`, and can be
+//! derived for structs and enums with the `derive` feature:
+//!
+#![cfg_attr(feature = "derive", doc = "```")]
+#![cfg_attr(not(feature = "derive"), doc = "```ignore")]
+//! use over::{FromOver, IntoOver, Obj};
+//!
+//! #[derive(FromOver, IntoOver)]
+//! struct Server {
+//!     host: String,
+//!     #[over(rename = "listen_port", default)]
+//!     port: u16,
+//!     #[over(frac = "exact")]
+//!     ratio: f64,
+//! }
+//!
+//! let obj: Obj = "host: \"localhost\" ratio: 0.5".parse().unwrap();
+//! let server = Server::from_obj(&obj).unwrap();
+//! assert_eq!(server.port, 0);
+//! assert_eq!(server.ratio, 0.5);
+//! ```
+//!
+//! Structs are read from an `Obj`, including any fields it inherits from its parents. The
+//! following attributes can be put on fields:
+//!
+//! - `#[over(rename = "name")]` reads the field from `name` instead.
+//! - `#[over(default)]` uses `Default::default()` if the field is missing, and
+//!   `#[over(default = "path")]` calls the function at `path` instead. `Option` fields are `None`
+//!   if missing without needing this attribute.
+//! - `#[over(flatten)]` reads the field's own fields from the same `Obj`, so that a struct shared
+//!   by several others can be written once as their parent. When writing, its fields are put in
+//!   the same `Obj`.
+//! - `#[over(frac = "nearest")]` or `#[over(frac = "exact")]` chooses the `FracPolicy` of an
+//!   `f64` field. The default is `nearest`.
+//!
+//! Unit structs are `Null`, newtype structs are their field, and other tuple structs are `Tup`s.
+//! Enum variants are an `Obj` with a single field named after the variant, containing the
+//! variant's fields, so `Some(1)` is `{ Some: 1 }` and `None` is `{ None: null }`. If no variant has
+//! fields, variants are the `Str` of their name instead. Unit variants can always be read from
+//! either. Variants can be renamed with `#[over(rename = "name")]`.
+//!
+//! Errors in fields name the dotted path to the field, such as `servers.0.port`.
+//!
+//! # Examples
+//!
+//! ```
+//! use over::convert::{self, IntoOver};
+//! use over::{arr::Arr, value::Value, Obj};
+//!
+//! let obj: Obj = "ports: [80 443] big: [70000]".parse().unwrap();
+//!
+//! let ports: Vec<u16> = convert::from_field(&obj, "ports").unwrap();
+//! assert_eq!(ports, vec![80, 443]);
+//!
+//! let error = convert::from_field::<Vec<u16>>(&obj, "big").unwrap_err();
+//! assert_eq!(
+//!     error.to_string(),
+//!     "Invalid value for field \"big.0\": 70000 is out of range for u16"
+//! );
+//!
+//! let arr = Arr::from_values(vec![80.into(), 443.into()]).unwrap();
+//! assert_eq!(ports.into_over().unwrap(), Value::Arr(arr));
+//! ```
+
+use crate::{
+    arr::Arr,
+    error::OverError,
+    obj::{Obj, Pair},
+    tup::Tup,
+    types::Type,
+    util::join_path,
+    value::Value,
+    OverResult,
+};
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use num_traits::{Signed, ToPrimitive, Zero};
+use std::{
+    collections::{BTreeMap, HashMap},
+    hash::BuildHasher,
+};
+
+/// A type which can be read from a `Value`.
+pub trait FromOver: Sized {
+    /// Reads `Self` from `value`. Returns an error if `value` has the wrong type or shape.
+    fn from_over(value: &Value) -> OverResult<Self>;
+
+    /// Reads `Self` from `obj`.
+    fn from_obj(obj: &Obj) -> OverResult<Self> {
+        Self::from_over(&Value::Obj(obj.clone()))
+    }
+
+    /// Returns the value to use for a missing field, or `None` if a missing field is an error.
+    /// Returns `None` by default.
+    fn from_missing() -> Option<Self> {
+        None
+    }
+}
+
+/// A type which can be written to a `Value`.
+pub trait IntoOver {
+    /// Writes `self` to a `Value`. Returns an error if `self` has no `Value`, such as a NaN `f64`,
+    /// or if the result would be invalid, such as an `Arr` with mixed types.
+    fn into_over(self) -> OverResult<Value>;
+}
+
+/// How a `Frac` is converted to an `f64`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FracPolicy {
+    /// Rounds to the nearest `f64`, with ties going to the even one, so `1/3` becomes
+    /// `0.3333333333333333`. This is the default.
+    #[default]
+    Nearest,
+    /// Returns an error unless the `Frac` is exactly an `f64`, so `1/4` is allowed but `1/10` is
+    /// not.
+    Exact,
+}
+
+/// Converts the `Frac` or `Int` in `value` to an `f64` using `policy`. Returns an error if `value`
+/// is not a number or is too large for an `f64`.
+pub fn frac_to_f64(value: &Value, policy: FracPolicy) -> OverResult<f64> {
+    let frac = value.get_frac()?;
+    let float = nearest_f64(&frac);
+
+    if float.is_infinite() {
+        return Err(OverError::OutOfRange(value.to_string(), "f64".into()));
+    }
+    if policy == FracPolicy::Exact && BigRational::from_float(float) != Some(frac) {
+        return Err(invalid(format!("{} is not exactly an f64", value)));
+    }
+
+    Ok(float)
+}
+
+// Rounds `frac` to the nearest `f64`, with ties going to the even one.
+fn nearest_f64(frac: &BigRational) -> f64 {
+    let numer = frac.numer();
+    if numer.is_zero() {
+        return 0.0;
+    }
+    let (numer_abs, denom) = (numer.abs(), frac.denom());
+    let sign = if numer.is_negative() { 1u64 << 63 } else { 0 };
+
+    // Find `exp` such that 2^exp <= |frac| < 2^(exp + 1).
+    let mut exp = numer_abs.bits() as i64 - denom.bits() as i64;
+    let below = if exp >= 0 {
+        numer_abs < (denom << exp as usize)
+    } else {
+        (&numer_abs << (-exp) as usize) < *denom
+    };
+    if below {
+        exp -= 1;
+    }
+    if exp > 1023 {
+        return f64::from_bits(sign | f64::INFINITY.to_bits());
+    }
+
+    // The value of the last bit of the significand. Subnormals have fewer bits, so rounding to
+    // this once gives the correctly rounded result without rounding again when scaling.
+    let ulp_exp = (exp - 52).max(-1074);
+
+    // Divide by the ulp, rounding the quotient to the nearest integer with ties to even.
+    let (quotient, remainder, divisor) = if ulp_exp <= 0 {
+        let scaled = numer_abs << (-ulp_exp) as usize;
+        (&scaled / denom, &scaled % denom, denom.clone())
+    } else {
+        let divisor = denom << ulp_exp as usize;
+        (&numer_abs / &divisor, &numer_abs % &divisor, divisor)
+    };
+    let mut significand = quotient.to_u64().expect("quotient is at most 2^53");
+    let twice_remainder: BigInt = remainder << 1;
+    if twice_remainder > divisor || (twice_remainder == divisor && significand & 1 == 1) {
+        significand += 1;
+    }
+
+    // Rounding may carry into the next power of two, which is still exact.
+    let mut ulp_exp = ulp_exp;
+    if significand == 1 << 53 {
+        significand >>= 1;
+        ulp_exp += 1;
+    }
+
+    let bits = if significand < 1 << 52 {
+        // Subnormal, or zero if `frac` is less than half the smallest subnormal.
+        significand
+    } else {
+        let biased_exp = (ulp_exp + 1075) as u64;
+        if biased_exp >= 0x7ff {
+            return f64::from_bits(sign | f64::INFINITY.to_bits());
+        }
+        (biased_exp << 52) | (significand - (1 << 52))
+    };
+
+    f64::from_bits(sign | bits)
+}
+
+/// Adds `field` to the start of the field path in `error`, so that an error in `port` read from the
+/// field `server` names `server.port`. A `TypeMismatch` becomes a `FieldTypeMismatch` for `field`,
+/// and an `OutOfRange` becomes an `InvalidValue` for `field`.
+pub fn in_field(error: OverError, field: &str) -> OverError {
+    let join = |path: String| join_path(field, &path);
+
+    match error {
+        OverError::FieldNotFound(path) => OverError::FieldNotFound(join(path)),
+        OverError::FieldTypeMismatch(path, expected, found) => {
+            OverError::FieldTypeMismatch(join(path), expected, found)
+        }
+        OverError::InvalidValue(path, error) => OverError::InvalidValue(join(path), error),
+        OverError::TypeMismatch(expected, found) => {
+            OverError::FieldTypeMismatch(field.into(), expected, found)
+        }
+        OverError::OutOfRange(number, t) => OverError::InvalidValue(
+            field.into(),
+            format!("{} is out of range for {}", number, t),
+        ),
+        error => error,
+    }
+}
+
+/// Reads `field` of `obj`, including fields inherited from parents. A missing field is an error
+/// unless `T::from_missing` returns a value.
+pub fn from_field<T: FromOver>(obj: &Obj, field: &str) -> OverResult<T> {
+    match obj.get(field) {
+        Some(value) => T::from_over(&value).map_err(|e| in_field(e, field)),
+        None => T::from_missing().ok_or_else(|| OverError::FieldNotFound(field.into())),
+    }
+}
+
+/// Returns the elements of the `Tup` in `value`, checking that there are `len` of them.
+pub fn tup_values(value: &Value, len: usize) -> OverResult<Vec<Value>> {
+    let tup = value.get_tup()?;
+    if tup.len() != len {
+        return Err(OverError::TypeMismatch(
+            Type::Tup(vec![Type::Any; len]),
+            value.get_type(),
+        ));
+    }
+
+    Ok(tup.values_ref().clone())
+}
+
+/// Reads the element at `index` of `values`, as returned by `tup_values`.
+pub fn from_element<T: FromOver>(values: &[Value], index: usize) -> OverResult<T> {
+    T::from_over(&values[index]).map_err(|e| in_field(e, &index.to_string()))
+}
+
+// Returns the name and value of the single field of an `Obj` holding an enum variant.
+#[doc(hidden)]
+pub fn variant_field(obj: &Obj) -> OverResult<(String, Value)> {
+    match obj.pairs_ref().as_slice() {
+        [Pair(name, value)] => Ok((name.clone(), value.clone())),
+        _ => Err(invalid(format!(
+            "expected an Obj with one field naming the variant, found {} fields",
+            obj.len()
+        ))),
+    }
+}
+
+// Returns the error for an enum variant which does not exist.
+#[doc(hidden)]
+pub fn unknown_variant(name: &str, variants: &[&str]) -> OverError {
+    invalid(format!(
+        "unknown variant \"{}\", expected one of: {}",
+        name,
+        variants.join(", ")
+    ))
+}
+
+// Returns the `Obj` holding an enum variant named `name` with fields `value`.
+#[doc(hidden)]
+pub fn variant_value(name: &str, value: Value) -> OverResult<Value> {
+    Ok(Value::Obj(Obj::from_pairs(
+        vec![Pair(name.into(), value)],
+        None,
+    )?))
+}
+
+// Adds the fields of the `Obj` written by a flattened field to `pairs`.
+#[doc(hidden)]
+pub fn flatten_into(pairs: &mut Vec<Pair>, value: Value) -> OverResult<()> {
+    let obj = value.get_obj()?;
+    pairs.extend(obj.pairs_ref().iter().cloned());
+
+    Ok(())
+}
+
+fn invalid(error: String) -> OverError {
+    OverError::InvalidValue(String::new(), error)
+}
+
+macro_rules! impl_getter {
+    ( $type:ty, $getter:ident ) => {
+        impl FromOver for $type {
+            fn from_over(value: &Value) -> OverResult<Self> {
+                value.$getter()
+            }
+        }
+
+        impl IntoOver for $type {
+            fn into_over(self) -> OverResult<Value> {
+                Ok(self.into())
+            }
+        }
+    };
+}
+
+impl_getter!(bool, get_bool);
+impl_getter!(BigInt, get_int);
+impl_getter!(BigRational, get_frac);
+impl_getter!(String, get_str);
+impl_getter!(Arr, get_arr);
+impl_getter!(Tup, get_tup);
+impl_getter!(Obj, get_obj);
+
+impl_getter!(u8, get_u8);
+impl_getter!(u16, get_u16);
+impl_getter!(u32, get_u32);
+impl_getter!(u64, get_u64);
+impl_getter!(usize, get_usize);
+impl_getter!(i8, get_i8);
+impl_getter!(i16, get_i16);
+impl_getter!(i32, get_i32);
+impl_getter!(i64, get_i64);
+
+impl FromOver for Value {
+    fn from_over(value: &Value) -> OverResult<Self> {
+        Ok(value.clone())
+    }
+}
+
+impl IntoOver for Value {
+    fn into_over(self) -> OverResult<Value> {
+        Ok(self)
+    }
+}
+
+impl IntoOver for &str {
+    fn into_over(self) -> OverResult<Value> {
+        Ok(self.into())
+    }
+}
+
+/// Uses `FracPolicy::Nearest`.
+impl FromOver for f64 {
+    fn from_over(value: &Value) -> OverResult<Self> {
+        value.get_f64()
+    }
+}
+
+/// Writes the shortest decimal which reads back as the same `f64`, so `0.1` becomes `1/10`.
+impl IntoOver for f64 {
+    fn into_over(self) -> OverResult<Value> {
+        Value::frac_from_f64_shortest(self)
+    }
+}
+
+impl<T: FromOver> FromOver for Option<T> {
+    fn from_over(value: &Value) -> OverResult<Self> {
+        if value.is_null() {
+            Ok(None)
+        } else {
+            T::from_over(value).map(Some)
+        }
+    }
+
+    fn from_missing() -> Option<Self> {
+        Some(None)
+    }
+}
+
+impl<T: IntoOver> IntoOver for Option<T> {
+    fn into_over(self) -> OverResult<Value> {
+        match self {
+            Some(inner) => inner.into_over(),
+            None => Ok(Value::Null),
+        }
+    }
+}
+
+impl<T: FromOver> FromOver for Vec<T> {
+    fn from_over(value: &Value) -> OverResult<Self> {
+        let arr = value.get_arr()?;
+
+        arr.values_ref()
+            .iter()
+            .enumerate()
+            .map(|(i, value)| T::from_over(value).map_err(|e| in_field(e, &i.to_string())))
+            .collect()
+    }
+}
+
+impl<T: IntoOver> IntoOver for Vec<T> {
+    fn into_over(self) -> OverResult<Value> {
+        let values = self
+            .into_iter()
+            .enumerate()
+            .map(|(i, elem)| elem.into_over().map_err(|e| in_field(e, &i.to_string())))
+            .collect::<OverResult<_>>()?;
+
+        Ok(Value::Arr(Arr::from_values(values)?))
+    }
+}
+
+/// Reads every field of the `Obj`, including inherited fields.
+impl<T: FromOver> FromOver for BTreeMap<String, T> {
+    fn from_over(value: &Value) -> OverResult<Self> {
+        let obj = value.get_obj()?;
+
+        obj.keys_all()
+            .map(|field| Ok((field.clone(), from_field(&obj, &field)?)))
+            .collect()
+    }
+}
+
+impl<T: IntoOver> IntoOver for BTreeMap<String, T> {
+    fn into_over(self) -> OverResult<Value> {
+        let pairs = self
+            .into_iter()
+            .map(|(field, value)| {
+                let value = value.into_over().map_err(|e| in_field(e, &field))?;
+                Ok(Pair(field, value))
+            })
+            .collect::<OverResult<_>>()?;
+
+        Ok(Value::Obj(Obj::from_pairs(pairs, None)?))
+    }
+}
+
+/// Reads every field of the `Obj`, including inherited fields.
+impl<T: FromOver, S: BuildHasher + Default> FromOver for HashMap<String, T, S> {
+    fn from_over(value: &Value) -> OverResult<Self> {
+        let obj = value.get_obj()?;
+
+        obj.keys_all()
+            .map(|field| Ok((field.clone(), from_field(&obj, &field)?)))
+            .collect()
+    }
+}
+
+macro_rules! impl_tuple {
+    ( $len:expr; $( $name:ident $index:tt ),+ ) => {
+        impl<$( $name: FromOver ),+> FromOver for ( $( $name, )+ ) {
+            fn from_over(value: &Value) -> OverResult<Self> {
+                let values = tup_values(value, $len)?;
+
+                Ok(( $( from_element::<$name>(&values, $index)?, )+ ))
+            }
+        }
+
+        impl<$( $name: IntoOver ),+> IntoOver for ( $( $name, )+ ) {
+            fn into_over(self) -> OverResult<Value> {
+                let values = vec![ $(
+                    self.$index.into_over().map_err(|e| in_field(e, stringify!($index)))?
+                ),+ ];
+
+                Ok(Value::Tup(Tup::from_values(values)))
+            }
+        }
+    };
+}
+
+impl_tuple!(1; A 0);
+impl_tuple!(2; A 0, B 1);
+impl_tuple!(3; A 0, B 1, C 2);
+impl_tuple!(4; A 0, B 1, C 2, D 3);
+impl_tuple!(5; A 0, B 1, C 2, D 3, E 4);
+impl_tuple!(6; A 0, B 1, C 2, D 3, E 4, F 5);
@@ -0,0 +1,265 @@
+//! Semantic differences between two values.
+//!
+//! Differences are reported per field or element, keyed by dotted paths such as `servers.2.port`,
+//! where `Arr` and `Tup` elements are identified by their index.
+//!
+//! # Examples
+//!
+//! ```
+//! # #[macro_use] extern crate over;
+//! # fn main() {
+//!
+//! use over::diff::{self, Change};
+//!
+//! let old = obj! { "servers" => arr![obj! { "port" => 8080 }] };
+//! let new = obj! { "servers" => arr![obj! { "port" => 8081 }] };
+//!
+//! let diff = diff::diff(&old.into(), &new.into());
+//!
+//! assert_eq!(diff.len(), 1);
+//! assert_eq!(diff.entries()[0].path, "servers.0.port");
+//! assert_eq!(diff.entries()[0].change, Change::Changed(8080.into(), 8081.into()));
+//! assert_eq!(format!("{}", diff), "~ servers.0.port: 8080 → 8081\n");
+//!
+//! # }
+//! ```
+
+use crate::{
+    arr::Arr,
+    obj::{Obj, Pair},
+    types::Type,
+    util::join_path,
+    value::Value,
+};
+use std::{fmt, slice::Iter};
+
+/// Options controlling how values are compared.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DiffOptions {
+    /// Whether to compare the fields `Obj`s inherit from their parents as well as the fields
+    /// defined on them directly. Defaults to `false`.
+    pub inherited: bool,
+}
+
+/// A change to a single value.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Change {
+    /// A value present only in the new tree.
+    Added(Value),
+    /// A value present only in the old tree.
+    Removed(Value),
+    /// A value present in both trees, with the old and new values.
+    Changed(Value, Value),
+}
+
+/// A change together with the path of the value it applies to.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Entry {
+    /// Dotted path of the value, e.g. `servers.2.port`. Empty if the roots themselves differ.
+    pub path: String,
+    /// The change.
+    pub change: Change,
+}
+
+/// The differences between two values.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Diff {
+    entries: Vec<Entry>,
+}
+
+/// Returns the differences between `old` and `new`, ignoring inherited fields.
+pub fn diff(old: &Value, new: &Value) -> Diff {
+    diff_with_options(old, new, DiffOptions::default())
+}
+
+/// Returns the differences between `old` and `new`.
+pub fn diff_with_options(old: &Value, new: &Value, options: DiffOptions) -> Diff {
+    let mut entries = vec![];
+    diff_values(old, new, "", options, &mut entries);
+
+    Diff { entries }
+}
+
+impl Diff {
+    /// Returns the changes, in the order their paths were encountered. Within an `Obj`, changes to
+    /// fields of the old `Obj` come first, followed by added fields.
+    pub fn entries(&self) -> &Vec<Entry> {
+        &self.entries
+    }
+
+    /// An iterator visiting all changes in order.
+    pub fn iter(&self) -> Iter<'_, Entry> {
+        self.entries.iter()
+    }
+
+    /// Returns the number of changes.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns true if no differences were found.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the changes as an `Obj` with a single field, `changes`, holding an `Arr` with one
+    /// `Obj` per change.
+    ///
+    /// Each change has the fields `path` and `kind` (`"added"`, `"removed"` or `"changed"`), and
+    /// `old` and/or `new` holding the values before and after the change.
+    pub fn to_obj(&self) -> Obj {
+        let changes = self
+            .entries
+            .iter()
+            .map(|entry| {
+                let mut pairs = vec![Pair("path".into(), entry.path.as_str().into())];
+
+                match entry.change {
+                    Change::Added(ref new) => {
+                        pairs.push(Pair("kind".into(), "added".into()));
+                        pairs.push(Pair("new".into(), new.clone()));
+                    }
+                    Change::Removed(ref old) => {
+                        pairs.push(Pair("kind".into(), "removed".into()));
+                        pairs.push(Pair("old".into(), old.clone()));
+                    }
+                    Change::Changed(ref old, ref new) => {
+                        pairs.push(Pair("kind".into(), "changed".into()));
+                        pairs.push(Pair("old".into(), old.clone()));
+                        pairs.push(Pair("new".into(), new.clone()));
+                    }
+                }
+
+                Obj::from_pairs_unchecked(pairs, None).into()
+            })
+            .collect();
+
+        Obj::from_pairs_unchecked(
+            vec![Pair(
+                "changes".into(),
+                Arr::from_values_unchecked(changes, Type::Obj).into(),
+            )],
+            None,
+        )
+    }
+
+    /// Writes the changes to a `String` in .over format. See `to_obj`.
+    pub fn write_to_string(&self) -> String {
+        self.to_obj().write_to_string()
+    }
+}
+
+impl fmt::Display for Entry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let path = if self.path.is_empty() {
+            "."
+        } else {
+            &self.path
+        };
+
+        match self.change {
+            Change::Added(ref new) => write!(f, "+ {}: {}", path, new),
+            Change::Removed(ref old) => write!(f, "- {}: {}", path, old),
+            Change::Changed(ref old, ref new) => write!(f, "~ {}: {} → {}", path, old, new),
+        }
+    }
+}
+
+/// Writes one change per line. Added values are prefixed with `+`, removed values with `-` and
+/// changed values with `~`.
+impl fmt::Display for Diff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for entry in &self.entries {
+            writeln!(f, "{}", entry)?;
+        }
+        Ok(())
+    }
+}
+
+fn diff_values(
+    old: &Value,
+    new: &Value,
+    path: &str,
+    options: DiffOptions,
+    entries: &mut Vec<Entry>,
+) {
+    match (old, new) {
+        (Value::Obj(ref old), Value::Obj(ref new)) => diff_objs(old, new, path, options, entries),
+        (Value::Arr(ref old), Value::Arr(ref new)) => {
+            diff_seqs(old.values_ref(), new.values_ref(), path, options, entries)
+        }
+        (Value::Tup(ref old), Value::Tup(ref new)) => {
+            diff_seqs(old.values_ref(), new.values_ref(), path, options, entries)
+        }
+        _ => {
+            if old != new {
+                entries.push(Entry {
+                    path: path.into(),
+                    change: Change::Changed(old.clone(), new.clone()),
+                });
+            }
+        }
+    }
+}
+
+fn diff_objs(old: &Obj, new: &Obj, path: &str, options: DiffOptions, entries: &mut Vec<Entry>) {
+    let (old_pairs, new_pairs) = if options.inherited {
+        (
+            old.iter_all().map(|(pair, _)| pair).collect(),
+            new.iter_all().map(|(pair, _)| pair).collect(),
+        )
+    } else {
+        (old.pairs_ref().clone(), new.pairs_ref().clone())
+    };
+
+    for Pair(ref field, ref old_value) in &old_pairs {
+        let field_path = join_path(path, field);
+
+        match new_pairs.iter().find(|Pair(ref f, _)| f == field) {
+            Some(Pair(_, ref new_value)) => {
+                diff_values(old_value, new_value, &field_path, options, entries)
+            }
+            None => entries.push(Entry {
+                path: field_path,
+                change: Change::Removed(old_value.clone()),
+            }),
+        }
+    }
+
+    for Pair(ref field, ref new_value) in &new_pairs {
+        if !old_pairs.iter().any(|Pair(ref f, _)| f == field) {
+            entries.push(Entry {
+                path: join_path(path, field),
+                change: Change::Added(new_value.clone()),
+            });
+        }
+    }
+}
+
+// Compares `Arr` or `Tup` elements by index.
+fn diff_seqs(
+    old: &[Value],
+    new: &[Value],
+    path: &str,
+    options: DiffOptions,
+    entries: &mut Vec<Entry>,
+) {
+    for (index, old_value) in old.iter().enumerate() {
+        let index_path = join_path(path, &index.to_string());
+
+        match new.get(index) {
+            Some(new_value) => diff_values(old_value, new_value, &index_path, options, entries),
+            None => entries.push(Entry {
+                path: index_path,
+                change: Change::Removed(old_value.clone()),
+            }),
+        }
+    }
+
+    for (index, new_value) in new.iter().enumerate().skip(old.len()) {
+        entries.push(Entry {
+            path: join_path(path, &index.to_string()),
+            change: Change::Added(new_value.clone()),
+        });
+    }
+}
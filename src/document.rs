@@ -0,0 +1,838 @@
+//! Editing .over files while preserving their comments and layout.
+//!
+//! A `Document` keeps the exact text of a file together with a concrete syntax tree recording
+//! where each field and value is. Edits replace, insert or remove text at those positions only, so
+//! every byte outside of an edit, including comments, whitespace, expressions, globals and
+//! includes, is left as it was.
+//!
+//! Values which are not written literally as an `Obj`, `Arr` or `Tup`, such as `1+@x`,
+//! `<"file.over">` or `a.b`, are kept as opaque expressions: they can be replaced or removed, but
+//! not edited inside.
+//!
+//! # Examples
+//!
+//! ```
+//! use over::document::Document;
+//! use std::str::FromStr;
+//!
+//! let mut doc = Document::from_str("# Release info.\nversion: 1 # Bump me.\ndebug: true\n").unwrap();
+//!
+//! doc.set("version", 2).unwrap();
+//! doc.remove("debug").unwrap();
+//! doc.set("name", "over").unwrap();
+//!
+//! assert_eq!(doc.text(), "# Release info.\nversion: 2 # Bump me.\nname: \"over\"\n");
+//! ```
+
+use crate::{
+    error::OverError,
+    obj::Obj,
+    parse::{self, format::Format},
+    util,
+    value::Value,
+    OverResult, INDENT_STEP,
+};
+use std::{fmt, fs, ops::Range, str::FromStr};
+
+/// A range of byte offsets into the text of a `Document`.
+pub type Span = Range<usize>;
+
+/// A field of an `Obj`, `name: value`. The name includes any leading `@` for globals and is `^`
+/// for parents.
+#[derive(Clone, Debug)]
+pub struct Field {
+    name: String,
+    name_span: Span,
+    value: Node,
+}
+
+impl Field {
+    /// Returns the name of this field.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the span of the name of this field, not including the ':'.
+    pub fn name_span(&self) -> Span {
+        self.name_span.clone()
+    }
+
+    /// Returns the value of this field.
+    pub fn value(&self) -> &Node {
+        &self.value
+    }
+
+    /// Returns the span from the start of the name to the end of the value.
+    pub fn span(&self) -> Span {
+        self.name_span.start..self.value.span.end
+    }
+
+    /// Returns true if this field defines a global.
+    pub fn is_global(&self) -> bool {
+        self.name.starts_with('@')
+    }
+
+    /// Returns true if this field defines a parent.
+    pub fn is_parent(&self) -> bool {
+        self.name == "^"
+    }
+}
+
+/// A value in a `Document`.
+#[derive(Clone, Debug)]
+pub struct Node {
+    span: Span,
+    kind: NodeKind,
+}
+
+/// The kind of a `Node`.
+#[derive(Clone, Debug)]
+pub enum NodeKind {
+    /// An `Obj` written literally, with its fields.
+    Obj(Vec<Field>),
+    /// An `Arr` written literally, with its elements.
+    Arr(Vec<Node>),
+    /// A `Tup` written literally, with its elements.
+    Tup(Vec<Node>),
+    /// Any other value, such as a literal, a variable, an include or an expression.
+    Expr,
+}
+
+impl Node {
+    /// Returns the span of this value.
+    pub fn span(&self) -> Span {
+        self.span.clone()
+    }
+
+    /// Returns the kind of this value.
+    pub fn kind(&self) -> &NodeKind {
+        &self.kind
+    }
+
+    /// Returns the fields of this value if it is an `Obj` written literally.
+    pub fn fields(&self) -> Option<&[Field]> {
+        match self.kind {
+            NodeKind::Obj(ref fields) => Some(fields),
+            _ => None,
+        }
+    }
+
+    /// Returns the elements of this value if it is an `Arr` or `Tup` written literally.
+    pub fn elements(&self) -> Option<&[Node]> {
+        match self.kind {
+            NodeKind::Arr(ref nodes) | NodeKind::Tup(ref nodes) => Some(nodes),
+            _ => None,
+        }
+    }
+}
+
+/// A reference to a variable or global in an expression, such as `@base.port`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Reference {
+    segments: Vec<(String, Span)>,
+}
+
+impl Reference {
+    /// Returns the name of the variable or global, including any leading `@`.
+    pub fn name(&self) -> &str {
+        &self.segments[0].0
+    }
+
+    /// Returns true if this is a reference to a global.
+    pub fn is_global(&self) -> bool {
+        self.name().starts_with('@')
+    }
+
+    /// Returns the dotted segments of this reference with their spans, starting with the name.
+    pub fn segments(&self) -> &[(String, Span)] {
+        &self.segments
+    }
+
+    /// Returns the span of the whole reference.
+    pub fn span(&self) -> Span {
+        self.segments[0].1.start..self.segments[self.segments.len() - 1].1.end
+    }
+}
+
+/// A parsed .over file which keeps its original text. See the module documentation.
+#[derive(Clone, Debug)]
+pub struct Document {
+    text: String,
+    path: Option<String>,
+    fields: Vec<Field>,
+}
+
+impl Document {
+    /// Parses the given text as a document.
+    ///
+    /// Only the structure of the document is checked: brackets, strings and `name: value` pairs.
+    /// Use `to_obj` to evaluate it.
+    pub fn parse(text: &str) -> OverResult<Self> {
+        Ok(Self {
+            text: text.into(),
+            path: None,
+            fields: Parser::new(text).parse_document()?,
+        })
+    }
+
+    /// Reads and parses the file at `path`. Includes in the document are relative to `path`.
+    pub fn from_file(path: &str) -> OverResult<Self> {
+        let text = fs::read_to_string(path)?;
+        let mut doc = Self::parse(&text)?;
+        doc.path = Some(path.into());
+
+        Ok(doc)
+    }
+
+    /// Returns the text of the document.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Returns the path of the file the document was read from, if any.
+    pub fn path(&self) -> Option<&str> {
+        self.path.as_deref()
+    }
+
+    /// Returns the top-level fields of the document.
+    pub fn fields(&self) -> &[Field] {
+        &self.fields
+    }
+
+    /// Evaluates the document, returning the `Obj` it describes.
+    pub fn to_obj(&self) -> OverResult<Obj> {
+        Ok(parse::load_from_str_in_file(&self.text, self.path())?)
+    }
+
+    /// Writes the text of the document to the file at `path`.
+    pub fn write_to_file(&self, path: &str) -> OverResult<()> {
+        util::write_file_str(path, &self.text)?;
+        Ok(())
+    }
+
+    /// Returns the value at the dotted `path`, such as `server.ports.0`. `Arr` and `Tup` elements
+    /// are identified by their index, and only values written literally can be looked into.
+    pub fn get(&self, path: &str) -> Option<&Node> {
+        let segments = split_path(path).ok()?;
+        let mut node = self.root_field(segments[0])?.value();
+
+        for segment in &segments[1..] {
+            node = child(node, segment)?;
+        }
+
+        Some(node)
+    }
+
+    /// Returns the text of the value at `path`. See `get`.
+    pub fn get_text(&self, path: &str) -> Option<&str> {
+        self.get(path).map(|node| &self.text[node.span()])
+    }
+
+    /// Returns the references to variables and globals in `node`, if it is an expression. Function
+    /// names, keywords and dot access on the result of a call are not references.
+    pub fn references(&self, node: &Node) -> Vec<Reference> {
+        scan_expr(&self.text, node)
+            .into_iter()
+            .filter_map(|token| match token {
+                Token::Reference(reference) => Some(reference),
+                Token::Number(_) => None,
+            })
+            .collect()
+    }
+
+    // Returns the spans of the numbers in `node`, if it is an expression.
+    pub(crate) fn numbers(&self, node: &Node) -> Vec<Span> {
+        scan_expr(&self.text, node)
+            .into_iter()
+            .filter_map(|token| match token {
+                Token::Number(span) => Some(span),
+                Token::Reference(_) => None,
+            })
+            .collect()
+    }
+
+    /// Sets the value at `path` to `value`.
+    ///
+    /// If the last segment of `path` is a field which does not exist, the field is added after the
+    /// last field of its `Obj`, on its own line if that field is on its own line.
+    pub fn set<V>(&mut self, path: &str, value: V) -> OverResult<()>
+    where
+        V: Into<Value>,
+    {
+        let value = value.into();
+        self.set_with(path, |indent| value.format(true, indent))
+    }
+
+    /// Like `set`, but takes the new value as .over text, such as `@base+1` or `<"other.over">`.
+    ///
+    /// Returns an error if `text` is not a single value.
+    pub fn set_raw(&mut self, path: &str, text: &str) -> OverResult<()> {
+        let mut parser = Parser::new(text);
+        parser.skip_space();
+        let node = parser.parse_value()?;
+        parser.skip_space();
+        if node.span.start != 0 || parser.pos != text.len() {
+            return Err(OverError::ParseError(format!(
+                "Expected a single value, found \"{}\"",
+                text
+            )));
+        }
+
+        self.set_with(path, |_| text.into())
+    }
+
+    /// Removes the field or element at `path`.
+    ///
+    /// If it is on its own line, the whole line is removed, together with any comment following
+    /// it on that line. Comments on previous lines are kept.
+    pub fn remove(&mut self, path: &str) -> OverResult<()> {
+        let span = self.locate(path)?.ok_or_else(|| not_found(path))?.whole;
+
+        let bytes = self.text.as_bytes();
+        let line_start = self.text[..span.start].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = self.text[span.end..]
+            .find('\n')
+            .map_or(self.text.len(), |i| span.end + i);
+
+        let own_line = self.text[line_start..span.start].trim().is_empty() && {
+            let rest = self.text[span.end..line_end].trim_start();
+            rest.is_empty() || rest.starts_with('#')
+        };
+
+        let range = if own_line {
+            line_start..(line_end + 1).min(self.text.len())
+        } else {
+            // Also remove the whitespace separating it from what follows, or from what precedes
+            // it if it is last on its line or in its container.
+            let mut start = span.start;
+            while start > line_start && (bytes[start - 1] == b' ' || bytes[start - 1] == b'\t') {
+                start -= 1;
+            }
+            let mut end = span.end;
+            while end < bytes.len() && (bytes[end] == b' ' || bytes[end] == b'\t') {
+                end += 1;
+            }
+
+            match bytes.get(end) {
+                None | Some(b'\n') | Some(b'\r') => start..end,
+                Some(&ch) if is_end_delimiter(ch) => start..span.end,
+                _ => span.start..end,
+            }
+        };
+
+        self.splice(range, "")
+    }
+
+    // Replaces the value at `path`, or adds it if it is a missing field. `render` is given the
+    // indentation for the contents of the value.
+    fn set_with<F>(&mut self, path: &str, render: F) -> OverResult<()>
+    where
+        F: FnOnce(usize) -> String,
+    {
+        if let Some(found) = self.locate(path)? {
+            let text = render(found.indent + INDENT_STEP);
+            return self.splice(found.value, &text);
+        }
+
+        let segments = split_path(path)?;
+        let (name, parent_path) = segments.split_last().unwrap();
+        if !is_field_name(name) {
+            return Err(OverError::InvalidFieldName((*name).into()));
+        }
+
+        let (fields, open) = if parent_path.is_empty() {
+            (&self.fields[..], None)
+        } else {
+            let parent = self
+                .get(&parent_path.join("."))
+                .ok_or_else(|| not_found(path))?;
+            match parent.fields() {
+                Some(fields) => (fields, Some(parent.span.start)),
+                None => return Err(OverError::InvalidPath(path.into())),
+            }
+        };
+
+        let (pos, text) = match (fields.last(), open) {
+            (Some(last), _) => {
+                let end = last.value.span.end;
+                let line_end = self.text[end..]
+                    .find('\n')
+                    .map_or(self.text.len(), |i| end + i);
+                let rest = self.text[end..line_end].trim_start();
+
+                if rest.is_empty() || rest.starts_with('#') {
+                    let indent = self.indent_at(last.name_span.start);
+                    let value = render(indent.len() + INDENT_STEP);
+                    (line_end, format!("\n{}{}: {}", indent, name, value))
+                } else {
+                    let indent = self.indent_at(last.name_span.start).len();
+                    (end, format!(" {}: {}", name, render(indent + INDENT_STEP)))
+                }
+            }
+            (None, Some(open)) => {
+                let indent = self.indent_at(open).len();
+                let value = render(indent + INDENT_STEP);
+                let close = self.get(&parent_path.join(".")).unwrap().span.end - 1;
+
+                if self.text[open + 1..close].trim().is_empty() {
+                    return self.splice(open + 1..close, &format!(" {}: {} ", name, value));
+                }
+                (open + 1, format!(" {}: {}", name, value))
+            }
+            (None, None) => {
+                let value = render(INDENT_STEP);
+                let pos = self.text.len();
+                if self.text.is_empty() || self.text.ends_with('\n') {
+                    (pos, format!("{}: {}\n", name, value))
+                } else {
+                    (pos, format!("\n{}: {}\n", name, value))
+                }
+            }
+        };
+
+        self.splice(pos..pos, &text)
+    }
+
+    // Finds the field or element at `path`. Returns `None` if the last segment is a missing field
+    // of an `Obj`.
+    fn locate(&self, path: &str) -> OverResult<Option<Located>> {
+        let segments = split_path(path)?;
+        let (last, parent_path) = segments.split_last().unwrap();
+
+        let found = if parent_path.is_empty() {
+            self.root_field(last)
+                .map(|field| (field.span(), field.value.span()))
+        } else {
+            let parent = self
+                .get(&parent_path.join("."))
+                .ok_or_else(|| not_found(path))?;
+
+            match parent.kind {
+                NodeKind::Obj(ref fields) => fields
+                    .iter()
+                    .find(|field| field.name == *last)
+                    .map(|field| (field.span(), field.value.span())),
+                NodeKind::Arr(ref nodes) | NodeKind::Tup(ref nodes) => {
+                    let node = last
+                        .parse::<usize>()
+                        .ok()
+                        .and_then(|index| nodes.get(index))
+                        .ok_or_else(|| not_found(path))?;
+                    Some((node.span(), node.span()))
+                }
+                NodeKind::Expr => return Err(OverError::InvalidPath(path.into())),
+            }
+        };
+
+        Ok(found.map(|(whole, value)| Located {
+            indent: self.indent_at(whole.start).len(),
+            whole,
+            value,
+        }))
+    }
+
+    fn root_field(&self, name: &str) -> Option<&Field> {
+        self.fields.iter().find(|field| field.name == name)
+    }
+
+    // Returns the leading whitespace of the line containing `pos`.
+    fn indent_at(&self, pos: usize) -> &str {
+        let line_start = self.text[..pos].rfind('\n').map_or(0, |i| i + 1);
+        let line = &self.text[line_start..];
+        let len = line.len() - line.trim_start_matches([' ', '\t']).len();
+
+        &line[..len]
+    }
+
+    // Replaces the text in `range` and parses the document again. On error, the document is left
+    // unchanged.
+    fn splice(&mut self, range: Span, text: &str) -> OverResult<()> {
+        let mut new_text = self.text.clone();
+        new_text.replace_range(range, text);
+
+        self.fields = Parser::new(&new_text).parse_document()?;
+        self.text = new_text;
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for Document {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.text)
+    }
+}
+
+impl FromStr for Document {
+    type Err = OverError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+// The location of a field or element in a document.
+struct Located {
+    // The span of the whole field or element.
+    whole: Span,
+    // The span of the value.
+    value: Span,
+    // The indentation of the line where the field or element starts.
+    indent: usize,
+}
+
+fn split_path(path: &str) -> OverResult<Vec<&str>> {
+    let segments: Vec<&str> = path.split('.').collect();
+
+    if segments.iter().any(|segment| segment.is_empty()) {
+        Err(OverError::InvalidPath(path.into()))
+    } else {
+        Ok(segments)
+    }
+}
+
+fn not_found(path: &str) -> OverError {
+    OverError::FieldNotFound(path.into())
+}
+
+fn child<'a>(node: &'a Node, segment: &str) -> Option<&'a Node> {
+    match node.kind {
+        NodeKind::Obj(ref fields) => fields
+            .iter()
+            .find(|field| field.name == segment)
+            .map(Field::value),
+        NodeKind::Arr(ref nodes) | NodeKind::Tup(ref nodes) => {
+            nodes.get(segment.parse::<usize>().ok()?)
+        }
+        NodeKind::Expr => None,
+    }
+}
+
+// Returns true if `name` can be used as the name of a field in a document, including globals.
+fn is_field_name(name: &str) -> bool {
+    let name = name.strip_prefix('@').unwrap_or(name);
+    !name.is_empty() && !name.starts_with('^') && Obj::is_valid_field(name)
+}
+
+fn is_end_delimiter(ch: u8) -> bool {
+    matches!(ch, b')' | b']' | b'}' | b'>')
+}
+
+// A token of interest in an expression.
+enum Token {
+    Reference(Reference),
+    Number(Span),
+}
+
+// Returns the references and numbers in the expression `node`, skipping strings.
+fn scan_expr(text: &str, node: &Node) -> Vec<Token> {
+    if !matches!(node.kind, NodeKind::Expr) {
+        return vec![];
+    }
+
+    let start = node.span.start;
+    let expr = &text[node.span()];
+    let mut tokens = vec![];
+    let mut chars = expr.char_indices().peekable();
+    let mut prev = None;
+
+    while let Some((i, ch)) = chars.next() {
+        let after_dot = prev == Some('.');
+        prev = Some(ch);
+
+        if ch == '"' {
+            while let Some((_, ch)) = chars.next() {
+                match ch {
+                    '"' => break,
+                    '\\' => {
+                        let _ = chars.next();
+                    }
+                    _ => (),
+                }
+            }
+            continue;
+        }
+
+        if ch.is_ascii_digit()
+            || (ch == '.' && chars.peek().is_some_and(|&(_, ch)| ch.is_ascii_digit()))
+        {
+            let mut end = i + 1;
+            while let Some((j, ch)) =
+                chars.next_if(|&(_, ch)| ch.is_ascii_digit() || matches!(ch, '.' | ',' | '_'))
+            {
+                end = j + 1;
+                prev = Some(ch);
+            }
+            tokens.push(Token::Number(start + i..start + end));
+            continue;
+        }
+
+        if !(ch == '@' || Obj::is_valid_field_char(ch, true)) {
+            continue;
+        }
+
+        let mut segments = vec![];
+        let mut seg_start = i;
+        let mut seg_end = i + ch.len_utf8();
+        let mut is_call = false;
+
+        while let Some(&(j, ch)) = chars.peek() {
+            if Obj::is_valid_field_char(ch, false) {
+                seg_end = j + ch.len_utf8();
+            } else if ch == '.' {
+                segments.push((seg_start, seg_end));
+                seg_start = j + 1;
+                seg_end = seg_start;
+            } else {
+                is_call = ch == '(';
+                break;
+            }
+            prev = Some(ch);
+            let _ = chars.next();
+        }
+        segments.push((seg_start, seg_end));
+
+        let name = &expr[segments[0].0..segments[0].1];
+        let is_keyword = parse::util::is_reserved(name);
+        // Dot access on the result of a call, such as `f(x).a`.
+        if after_dot || is_call || is_keyword || segments.iter().any(|(s, e)| s == e) {
+            continue;
+        }
+
+        tokens.push(Token::Reference(Reference {
+            segments: segments
+                .into_iter()
+                .map(|(s, e)| (expr[s..e].to_string(), start + s..start + e))
+                .collect(),
+        }));
+    }
+
+    tokens
+}
+
+// Parses the structure of a document.
+struct Parser<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(text: &'a str) -> Self {
+        Self { text, pos: 0 }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.text[self.pos..].chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let ch = self.peek()?;
+        self.pos += ch.len_utf8();
+        Some(ch)
+    }
+
+    fn error<T>(&self, msg: &str, pos: usize) -> OverResult<T> {
+        let line_start = self.text[..pos].rfind('\n').map_or(0, |i| i + 1);
+        let line = self.text[..pos].matches('\n').count() + 1;
+        let col = self.text[line_start..pos].chars().count() + 1;
+
+        Err(OverError::ParseError(format!(
+            "{} at line {}, column {}",
+            msg, line, col
+        )))
+    }
+
+    fn unexpected_end<T>(&self) -> OverResult<T> {
+        let line = self.text.matches('\n').count() + 1;
+        Err(OverError::ParseError(format!(
+            "Unexpected end at line {}",
+            line
+        )))
+    }
+
+    // Skips whitespace and comments.
+    fn skip_space(&mut self) {
+        while let Some(ch) = self.peek() {
+            if ch == '#' {
+                self.pos = self.text[self.pos..]
+                    .find('\n')
+                    .map_or(self.text.len(), |i| self.pos + i);
+            } else if ch.is_whitespace() {
+                self.pos += ch.len_utf8();
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn parse_document(&mut self) -> OverResult<Vec<Field>> {
+        self.parse_fields(None)
+    }
+
+    // Parses fields up to and including the closing character, if any.
+    fn parse_fields(&mut self, close: Option<char>) -> OverResult<Vec<Field>> {
+        let mut fields = vec![];
+
+        loop {
+            self.skip_space();
+
+            let ch = match self.peek() {
+                Some(ch) => ch,
+                None if close.is_none() => return Ok(fields),
+                None => return self.unexpected_end(),
+            };
+            if Some(ch) == close {
+                self.pos += 1;
+                return Ok(fields);
+            }
+
+            // Read the field name.
+            let start = self.pos;
+            if ch == '@' {
+                self.pos += 1;
+            }
+            while let Some(ch) = self.peek() {
+                if ch == ':' {
+                    break;
+                }
+                if !Obj::is_valid_field_char(ch, self.pos == start) {
+                    return self.error(&format!("Invalid character '{}' for field", ch), self.pos);
+                }
+                self.pos += ch.len_utf8();
+            }
+            let name_span = start..self.pos;
+            if self.bump().is_none() {
+                return self.unexpected_end();
+            }
+            if name_span.is_empty() {
+                return self.error("Invalid character ':' for field", start);
+            }
+
+            self.skip_space();
+            if self.peek().is_none() {
+                return self.unexpected_end();
+            }
+            let value = self.parse_value()?;
+
+            fields.push(Field {
+                name: self.text[name_span.clone()].into(),
+                name_span,
+                value,
+            });
+        }
+    }
+
+    // Parses elements up to and including the closing character.
+    fn parse_elements(&mut self, close: char) -> OverResult<Vec<Node>> {
+        let mut nodes = vec![];
+
+        loop {
+            self.skip_space();
+
+            match self.peek() {
+                Some(ch) if ch == close => {
+                    self.pos += 1;
+                    return Ok(nodes);
+                }
+                Some(_) => nodes.push(self.parse_value()?),
+                None => return self.unexpected_end(),
+            }
+        }
+    }
+
+    // Parses a value starting at the current, non-whitespace character.
+    fn parse_value(&mut self) -> OverResult<Node> {
+        let start = self.pos;
+
+        let kind = match self.peek() {
+            Some('{') => {
+                self.pos += 1;
+                NodeKind::Obj(self.parse_fields(Some('}'))?)
+            }
+            Some('[') => {
+                self.pos += 1;
+                NodeKind::Arr(self.parse_elements(']')?)
+            }
+            Some('(') => {
+                self.pos += 1;
+                NodeKind::Tup(self.parse_elements(')')?)
+            }
+            Some(ch) if is_end_delimiter(ch as u8) && ch.is_ascii() => {
+                return self.error(&format!("Invalid character '{}' for value", ch), start);
+            }
+            Some(_) => {
+                self.skip_expr()?;
+                NodeKind::Expr
+            }
+            None => return self.unexpected_end(),
+        };
+
+        // A literal followed directly by an operator or dot is part of a larger expression.
+        let kind = match self.peek() {
+            Some(ch) if !matches!(kind, NodeKind::Expr) && "+-*/%.".contains(ch) => {
+                self.skip_expr()?;
+                NodeKind::Expr
+            }
+            _ => kind,
+        };
+
+        Ok(Node {
+            span: start..self.pos,
+            kind,
+        })
+    }
+
+    // Skips to the end of an expression: the first whitespace, comment or unmatched closing
+    // bracket outside of strings and brackets.
+    fn skip_expr(&mut self) -> OverResult<()> {
+        let mut stack = vec![];
+
+        while let Some(ch) = self.peek() {
+            match ch {
+                '"' => self.skip_str()?,
+                '{' | '[' | '(' | '<' => {
+                    stack.push(match ch {
+                        '{' => '}',
+                        '[' => ']',
+                        '(' => ')',
+                        _ => '>',
+                    });
+                    self.pos += 1;
+                }
+                '#' if !stack.is_empty() => self.skip_space(),
+                ch if ch.is_whitespace() && !stack.is_empty() => self.skip_space(),
+                ch if ch.is_whitespace() || ch == '#' => break,
+                ch if is_end_delimiter(ch as u8) && ch.is_ascii() => match stack.pop() {
+                    Some(expected) if expected == ch => self.pos += 1,
+                    Some(_) => {
+                        return self.error(&format!("Invalid closing bracket '{}'", ch), self.pos)
+                    }
+                    None => break,
+                },
+                ch => self.pos += ch.len_utf8(),
+            }
+        }
+
+        if stack.is_empty() {
+            Ok(())
+        } else {
+            self.unexpected_end()
+        }
+    }
+
+    // Skips a Str, including its quotes.
+    fn skip_str(&mut self) -> OverResult<()> {
+        self.pos += 1;
+
+        loop {
+            match self.bump() {
+                Some('"') => return Ok(()),
+                Some('\\') => {
+                    let _ = self.bump();
+                }
+                Some(_) => (),
+                None => return self.unexpected_end(),
+            }
+        }
+    }
+}
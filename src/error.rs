@@ -2,7 +2,9 @@
 
 #![allow(missing_docs)]
 
-use crate::{parse::error::ParseError, types::Type};
+pub use crate::parse::error::ParseError;
+
+use crate::types::Type;
 use std::{error::Error, fmt, io};
 
 /// The fabulous OVER error type.
@@ -11,8 +13,14 @@ pub enum OverError {
     ArrOutOfBounds(usize),
     ArrTypeMismatch(Type, Type),
     FieldNotFound(String),
+    FieldTypeMismatch(String, Type, Type),
+    InvalidArg(String, String),
     InvalidFieldName(String),
+    InvalidPatchOp(String),
+    InvalidPath(String),
+    InvalidValue(String, String),
     NoParentFound,
+    OutOfRange(String, String),
     ParseError(String),
     TupOutOfBounds(usize),
     TupTypeMismatch(Type, Type, usize),
@@ -33,8 +41,23 @@ impl fmt::Display for OverError {
                 expected, found
             ),
             FieldNotFound(ref field) => write!(f, "Field not found: \"{}\"", field),
+            FieldTypeMismatch(ref field, ref expected, ref found) => write!(
+                f,
+                "Type mismatch for field \"{}\": expected {}, found {}",
+                field, expected, found
+            ),
+            InvalidArg(ref arg, ref error) => write!(f, "Invalid argument \"{}\": {}", arg, error),
             InvalidFieldName(ref field) => write!(f, "Invalid field name: \"{}\"", field),
+            InvalidPatchOp(ref op) => write!(f, "Invalid patch operation: \"{}\"", op),
+            InvalidPath(ref path) => write!(f, "Invalid path: \"{}\"", path),
+            InvalidValue(ref field, ref error) if field.is_empty() => {
+                write!(f, "Invalid value: {}", error)
+            }
+            InvalidValue(ref field, ref error) => {
+                write!(f, "Invalid value for field \"{}\": {}", field, error)
+            }
             NoParentFound => write!(f, "No parent found for this obj"),
+            OutOfRange(ref number, ref t) => write!(f, "{} is out of range for {}", number, t),
             TupOutOfBounds(ref index) => write!(f, "Tup index {} out of bounds", index),
             TupTypeMismatch(ref expected, ref found, ref index) => write!(
                 f,
@@ -0,0 +1,217 @@
+//! Configuration loaded from several layers, where later layers override earlier ones.
+//!
+//! # Examples
+//!
+//! ```
+//! # #[macro_use] extern crate over;
+//! # fn main() {
+//!
+//! use over::layered::Layered;
+//!
+//! let mut layered = Layered::new();
+//! layered.add_str("base", "db: { host: \"localhost\" pool_size: 10 }");
+//! layered.add_obj("local", obj! { "db" => obj! { "pool_size" => 2 } });
+//!
+//! let config = layered.load().unwrap();
+//!
+//! assert_eq!(
+//!     config.obj(),
+//!     &obj! { "db" => obj! { "host" => "localhost", "pool_size" => 2 } }
+//! );
+//! assert_eq!(config.source_of("db.host"), Some("base"));
+//! assert_eq!(config.source_of("db.pool_size"), Some("local"));
+//!
+//! # }
+//! ```
+
+use crate::{
+    context::ParseContext,
+    obj::{MergeStep, Obj, Pair},
+    util::join_path,
+    value::Value,
+    OverResult,
+};
+use std::collections::HashMap;
+
+/// Where a layer is loaded from.
+#[derive(Clone, Debug)]
+pub enum Source {
+    /// A .over file at the given path.
+    File(String),
+    /// .over contents in a string.
+    Str(String),
+    /// An `Obj` already in memory.
+    Obj(Obj),
+}
+
+/// How an `Arr` in a later layer is combined with an `Arr` at the same path in an earlier layer.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ArrMerge {
+    /// The later `Arr` replaces the earlier one. This is the default.
+    #[default]
+    Replace,
+    /// The elements of the later `Arr` are appended to the earlier one.
+    Append,
+}
+
+/// A loader combining several layers into a single `Obj`.
+///
+/// Layers are deep-merged with `Obj::deep_merge_with` in the order they are added, using the
+/// `ArrMerge` set with `set_arr_merge`: fields in a later layer override the same fields in
+/// earlier layers, and `Obj`s present in several layers are merged field by field. A value may not
+/// change type between layers, except that `Obj`s, `Arr`s and `Tup`s with an `Any` type are
+/// compatible with more specific ones.
+///
+/// Only fields defined directly on each `Obj` are merged; parents are kept but their fields are
+/// not copied.
+#[derive(Clone, Debug, Default)]
+pub struct Layered {
+    // Layer names and sources, in order.
+    layers: Vec<(String, Source)>,
+    // How to combine `Arr`s.
+    arr_merge: ArrMerge,
+    // Context used when parsing files and strings.
+    ctx: ParseContext,
+}
+
+impl Layered {
+    /// Returns a new loader with no layers.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the .over file at `path` as the next layer. The layer is named after the path.
+    pub fn add_file(&mut self, path: &str) {
+        self.layers.push((path.into(), Source::File(path.into())));
+    }
+
+    /// Adds the .over contents in `contents` as the next layer, called `name`.
+    pub fn add_str(&mut self, name: &str, contents: &str) {
+        self.layers
+            .push((name.into(), Source::Str(contents.into())));
+    }
+
+    /// Adds `obj` as the next layer, called `name`.
+    pub fn add_obj(&mut self, name: &str, obj: Obj) {
+        self.layers.push((name.into(), Source::Obj(obj)));
+    }
+
+    /// Sets how `Arr`s in later layers are combined with earlier ones.
+    pub fn set_arr_merge(&mut self, arr_merge: ArrMerge) {
+        self.arr_merge = arr_merge;
+    }
+
+    /// Sets the context used when parsing files and strings.
+    pub fn set_context(&mut self, ctx: ParseContext) {
+        self.ctx = ctx;
+    }
+
+    /// Loads every layer and merges them in order.
+    ///
+    /// Returns an error if a layer could not be loaded, or if a value in one layer has a
+    /// different type than in an earlier layer.
+    pub fn load(&self) -> OverResult<LayeredObj> {
+        let mut obj = Obj::empty();
+        let mut sources: HashMap<String, usize> = HashMap::new();
+
+        for (index, (_, source)) in self.layers.iter().enumerate() {
+            let layer = match *source {
+                Source::File(ref path) => Obj::from_file_with_context(path, &self.ctx)?,
+                Source::Str(ref contents) => Obj::from_str_with_context(contents, &self.ctx)?,
+                Source::Obj(ref obj) => obj.clone(),
+            };
+
+            obj = obj.deep_merge_at(&layer, "", self.arr_merge, &mut |path, step| match step {
+                MergeStep::Added(value) => record(value, path, index, &mut sources),
+                MergeStep::Replaced(value) => {
+                    // Forget where the replaced value's contents came from.
+                    let prefix = format!("{}.", path);
+                    sources.retain(|p, _| !p.starts_with(&prefix));
+                    record(value, path, index, &mut sources);
+                }
+                MergeStep::Merged => {
+                    let _ = sources.insert(path.into(), index);
+                }
+                MergeStep::Appended(start, arr) => {
+                    for (i, value) in arr.iter().enumerate() {
+                        let value_path = join_path(path, &(start + i).to_string());
+                        record(value, &value_path, index, &mut sources);
+                    }
+                    let _ = sources.insert(path.into(), index);
+                }
+            })?;
+        }
+
+        Ok(LayeredObj {
+            obj,
+            names: self.layers.iter().map(|(name, _)| name.clone()).collect(),
+            sources,
+        })
+    }
+}
+
+/// The result of loading a `Layered` configuration.
+#[derive(Clone, Debug)]
+pub struct LayeredObj {
+    // The merged `Obj`.
+    obj: Obj,
+    // Layer names, in order.
+    names: Vec<String>,
+    // The index of the layer each value came from, keyed by dotted path.
+    sources: HashMap<String, usize>,
+}
+
+impl LayeredObj {
+    /// Returns a reference to the merged `Obj`.
+    pub fn obj(&self) -> &Obj {
+        &self.obj
+    }
+
+    /// Returns the merged `Obj`.
+    pub fn into_obj(self) -> Obj {
+        self.obj
+    }
+
+    /// Returns the names of the layers, in the order they were merged.
+    pub fn layer_names(&self) -> &Vec<String> {
+        &self.names
+    }
+
+    /// Returns the index of the layer the value at the dotted `path` came from, e.g. `db.port`
+    /// or `servers.0`. `Arr` and `Tup` elements are identified by their index.
+    ///
+    /// For an `Obj` present in several layers, this is the last layer which defined any of its
+    /// fields.
+    pub fn layer_of(&self, path: &str) -> Option<usize> {
+        self.sources.get(path).cloned()
+    }
+
+    /// Returns the name of the layer the value at the dotted `path` came from. See `layer_of`.
+    pub fn source_of(&self, path: &str) -> Option<&str> {
+        self.layer_of(path).map(|index| self.names[index].as_str())
+    }
+}
+
+// Records `value`, and everything it contains, as coming from the layer at `index`.
+fn record(value: &Value, path: &str, index: usize, sources: &mut HashMap<String, usize>) {
+    let _ = sources.insert(path.into(), index);
+
+    match *value {
+        Value::Obj(ref obj) => {
+            for Pair(field, value) in obj.iter() {
+                record(value, &join_path(path, field), index, sources);
+            }
+        }
+        Value::Arr(ref arr) => {
+            for (i, value) in arr.iter().enumerate() {
+                record(value, &join_path(path, &i.to_string()), index, sources);
+            }
+        }
+        Value::Tup(ref tup) => {
+            for (i, value) in tup.iter().enumerate() {
+                record(value, &join_path(path, &i.to_string()), index, sources);
+            }
+        }
+        _ => (),
+    }
+}
@@ -7,6 +7,8 @@ extern crate lazy_static;
 extern crate num_bigint;
 extern crate num_rational;
 extern crate num_traits;
+#[cfg(feature = "parallel")]
+extern crate rayon;
 
 #[macro_use]
 mod util;
@@ -14,19 +16,36 @@ mod util;
 #[macro_use]
 pub mod macros;
 
+pub mod args;
 pub mod arr;
+pub mod context;
+pub mod convert;
+pub mod diff;
+pub mod document;
 pub mod error;
+pub mod layered;
+pub mod lint;
+pub mod loader;
 pub mod obj;
+pub mod patch;
 pub mod tup;
 pub mod types;
 pub mod value;
+pub mod watcher;
 
 mod parse;
 
 #[cfg(test)]
 mod tests;
 
-pub use crate::{error::OverError, obj::Obj};
+pub use crate::{
+    convert::{FromOver, IntoOver},
+    error::OverError,
+    obj::Obj,
+};
+
+#[cfg(feature = "derive")]
+pub use over_derive::{FromOver, IntoOver};
 
 use std::sync::atomic::{AtomicUsize, Ordering};
 
@@ -0,0 +1,376 @@
+//! Warnings for .over files which parse but are probably mistakes.
+//!
+//! Each kind of mistake is checked by a `Rule`, and every rule can be turned off in
+//! `LintOptions`. Files must parse successfully before they can be checked.
+//!
+//! # Examples
+//!
+//! ```
+//! use over::lint::{self, LintOptions, Rule};
+//!
+//! let warnings = lint::lint_str("@unused: 1\nratio: 1,5", LintOptions::default()).unwrap();
+//!
+//! assert_eq!(warnings.len(), 2);
+//! assert_eq!(warnings[0].rule, Rule::UnusedGlobal);
+//! assert_eq!(
+//!     warnings[1].to_string(),
+//!     "Number \"1,5\" uses ',' as its decimal separator at line 2, column 9"
+//! );
+//!
+//! let mut options = LintOptions::default();
+//! options.set(Rule::CommaDecimal, false);
+//! assert_eq!(lint::lint_str("ratio: 1,5", options).unwrap(), vec![]);
+//! ```
+
+use crate::{
+    document::{Document, Field, Node, NodeKind, Span},
+    obj::Obj,
+    value::Value,
+    OverResult,
+};
+use std::{collections::HashSet, fmt, fs};
+
+/// A kind of mistake to warn about.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Rule {
+    /// A global which is never referenced.
+    UnusedGlobal,
+    /// A field whose type differs from the type of the field it overrides in a parent.
+    ParentTypeMismatch,
+    /// A number with `,` as its decimal separator, such as `1,5`. It is parsed as `1.5`, but is
+    /// easily mistaken for two values.
+    CommaDecimal,
+    /// A .over file included into a global which is never referenced.
+    UnusedInclude,
+    /// A field or global named like a reserved word, such as `True` or `obj`.
+    ReservedName,
+}
+
+impl Rule {
+    /// Every rule.
+    pub const ALL: [Rule; 5] = [
+        Rule::UnusedGlobal,
+        Rule::ParentTypeMismatch,
+        Rule::CommaDecimal,
+        Rule::UnusedInclude,
+        Rule::ReservedName,
+    ];
+
+    /// Returns the name of this rule, such as `unused-global`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Rule::UnusedGlobal => "unused-global",
+            Rule::ParentTypeMismatch => "parent-type-mismatch",
+            Rule::CommaDecimal => "comma-decimal",
+            Rule::UnusedInclude => "unused-include",
+            Rule::ReservedName => "reserved-name",
+        }
+    }
+
+    /// Returns the rule with the given name, if any. See `name`.
+    pub fn from_name(name: &str) -> Option<Rule> {
+        Self::ALL.iter().copied().find(|rule| rule.name() == name)
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Options controlling which rules are checked.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LintOptions {
+    /// Whether to check `Rule::UnusedGlobal`. Defaults to `true`.
+    pub unused_globals: bool,
+    /// Whether to check `Rule::ParentTypeMismatch`. Defaults to `true`.
+    pub parent_type_mismatches: bool,
+    /// Whether to check `Rule::CommaDecimal`. Defaults to `true`.
+    pub comma_decimals: bool,
+    /// Whether to check `Rule::UnusedInclude`. Defaults to `true`.
+    pub unused_includes: bool,
+    /// Whether to check `Rule::ReservedName`. Defaults to `true`.
+    pub reserved_names: bool,
+}
+
+impl LintOptions {
+    /// Returns options with every rule turned off.
+    pub fn none() -> Self {
+        Self {
+            unused_globals: false,
+            parent_type_mismatches: false,
+            comma_decimals: false,
+            unused_includes: false,
+            reserved_names: false,
+        }
+    }
+
+    /// Returns true if `rule` is checked.
+    pub fn is_enabled(&self, rule: Rule) -> bool {
+        match rule {
+            Rule::UnusedGlobal => self.unused_globals,
+            Rule::ParentTypeMismatch => self.parent_type_mismatches,
+            Rule::CommaDecimal => self.comma_decimals,
+            Rule::UnusedInclude => self.unused_includes,
+            Rule::ReservedName => self.reserved_names,
+        }
+    }
+
+    /// Sets whether `rule` is checked.
+    pub fn set(&mut self, rule: Rule, enabled: bool) {
+        let option = match rule {
+            Rule::UnusedGlobal => &mut self.unused_globals,
+            Rule::ParentTypeMismatch => &mut self.parent_type_mismatches,
+            Rule::CommaDecimal => &mut self.comma_decimals,
+            Rule::UnusedInclude => &mut self.unused_includes,
+            Rule::ReservedName => &mut self.reserved_names,
+        };
+        *option = enabled;
+    }
+}
+
+impl Default for LintOptions {
+    fn default() -> Self {
+        Self {
+            unused_globals: true,
+            parent_type_mismatches: true,
+            comma_decimals: true,
+            unused_includes: true,
+            reserved_names: true,
+        }
+    }
+}
+
+/// A warning about part of a file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Warning {
+    /// The rule which was broken.
+    pub rule: Rule,
+    /// A description of the mistake.
+    pub message: String,
+    /// The line where the mistake starts, counting from 1.
+    pub line: usize,
+    /// The column where the mistake starts, counting characters from 1.
+    pub col: usize,
+    /// The byte offsets of the mistake in the file.
+    pub span: Span,
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} at line {}, column {}",
+            self.message, self.line, self.col
+        )
+    }
+}
+
+/// Checks the file at `path`, returning the warnings in the order they appear. Returns an error if
+/// the file does not parse.
+pub fn lint_file(path: &str, options: LintOptions) -> OverResult<Vec<Warning>> {
+    let text = fs::read_to_string(path)?;
+    let obj = Obj::from_str_in_file(&text, path)?;
+
+    lint(&text, &obj, options)
+}
+
+/// Checks a `&str`, returning the warnings in the order they appear. Includes are relative to the
+/// current directory. Returns an error if the `&str` does not parse.
+pub fn lint_str(contents: &str, options: LintOptions) -> OverResult<Vec<Warning>> {
+    let obj = contents.parse()?;
+
+    lint(contents, &obj, options)
+}
+
+fn lint(text: &str, obj: &Obj, options: LintOptions) -> OverResult<Vec<Warning>> {
+    let doc = Document::parse(text)?;
+    let mut fields = vec![];
+    let mut exprs = vec![];
+    collect_fields(doc.fields(), &mut fields, &mut exprs);
+
+    let mut lints = Lints {
+        text,
+        options,
+        warnings: vec![],
+    };
+
+    let used: HashSet<String> = exprs
+        .iter()
+        .flat_map(|expr| doc.references(expr))
+        .filter(|reference| reference.is_global())
+        .map(|reference| reference.name().to_string())
+        .collect();
+
+    for field in &fields {
+        let name = field.name();
+
+        if field.is_global() && !used.contains(name) {
+            let value = &text[field.value().span()];
+            match include_path(value) {
+                Some(path) if path.ends_with(".over") => lints.warn(
+                    Rule::UnusedInclude,
+                    format!(
+                        "Included file \"{}\" is only used by the unused global \"{}\"",
+                        path, name
+                    ),
+                    field.value().span(),
+                ),
+                _ => lints.warn(
+                    Rule::UnusedGlobal,
+                    format!("Global \"{}\" is never used", name),
+                    field.name_span(),
+                ),
+            }
+        }
+
+        let bare = name.trim_start_matches('@');
+        if let Some(reserved) = RESERVED.iter().find(|word| word.eq_ignore_ascii_case(bare)) {
+            let kind = if field.is_global() { "Global" } else { "Field" };
+            lints.warn(
+                Rule::ReservedName,
+                format!(
+                    "{} \"{}\" looks like the reserved word \"{}\"",
+                    kind, name, reserved
+                ),
+                field.name_span(),
+            );
+        }
+    }
+
+    for expr in &exprs {
+        for span in doc.numbers(expr) {
+            let number = &text[span.clone()];
+            if let Some(comma) = number.find(',') {
+                let start = span.start + comma;
+                lints.warn(
+                    Rule::CommaDecimal,
+                    format!("Number \"{}\" uses ',' as its decimal separator", number),
+                    start..start + 1,
+                );
+            }
+        }
+    }
+
+    check_parents(doc.fields(), obj, &mut lints);
+
+    let mut warnings = lints.warnings;
+    warnings.sort_by_key(|warning| warning.span.start);
+    Ok(warnings)
+}
+
+// Words which are reserved in .over files.
+const RESERVED: [&str; 7] = ["null", "true", "false", "Obj", "Str", "Arr", "Tup"];
+
+struct Lints<'a> {
+    text: &'a str,
+    options: LintOptions,
+    warnings: Vec<Warning>,
+}
+
+impl<'a> Lints<'a> {
+    fn warn(&mut self, rule: Rule, message: String, span: Span) {
+        if !self.options.is_enabled(rule) {
+            return;
+        }
+
+        let before = &self.text[..span.start];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+
+        self.warnings.push(Warning {
+            rule,
+            message,
+            line: before.matches('\n').count() + 1,
+            col: before[line_start..].chars().count() + 1,
+            span,
+        });
+    }
+}
+
+// Collects every field and every expression in `fields`, recursively.
+fn collect_fields<'a>(fields: &'a [Field], all: &mut Vec<&'a Field>, exprs: &mut Vec<&'a Node>) {
+    for field in fields {
+        all.push(field);
+        collect_node(field.value(), all, exprs);
+    }
+}
+
+fn collect_node<'a>(node: &'a Node, all: &mut Vec<&'a Field>, exprs: &mut Vec<&'a Node>) {
+    match *node.kind() {
+        NodeKind::Obj(ref fields) => collect_fields(fields, all, exprs),
+        NodeKind::Arr(ref elements) | NodeKind::Tup(ref elements) => {
+            for element in elements {
+                collect_node(element, all, exprs);
+            }
+        }
+        NodeKind::Expr => exprs.push(node),
+    }
+}
+
+// Returns the path in an include expression such as `<Obj "file.over">`.
+fn include_path(expr: &str) -> Option<&str> {
+    if !expr.starts_with('<') {
+        return None;
+    }
+    let start = expr.find('"')? + 1;
+    let len = expr[start..].find('"')?;
+
+    Some(&expr[start..start + len])
+}
+
+// Compares the fields written in `fields` with the fields of the parents of `obj`, their evaluated
+// `Obj`. Globals are not evaluated into `obj`, so they are not checked.
+fn check_parents(fields: &[Field], obj: &Obj, lints: &mut Lints) {
+    for field in fields {
+        if field.is_global() || field.is_parent() {
+            continue;
+        }
+        let value = match obj.get(field.name()) {
+            Some(value) => value,
+            None => continue,
+        };
+
+        // The overridden value is the one `get` would find without this field, so search the
+        // ancestors in the same order.
+        let inherited = obj
+            .ancestors()
+            .iter()
+            .find(|ancestor| ancestor.contains(field.name()))
+            .and_then(|ancestor| ancestor.get(field.name()));
+        if let Some(inherited) = inherited {
+            let (t, parent_t) = (value.get_type(), inherited.get_type());
+            if t != parent_t {
+                lints.warn(
+                    Rule::ParentTypeMismatch,
+                    format!(
+                        "Field \"{}\" has type {} but overrides a field of type {} in its parent",
+                        field.name(),
+                        t,
+                        parent_t
+                    ),
+                    field.name_span(),
+                );
+            }
+        }
+
+        check_parents_in_node(field.value(), &value, lints);
+    }
+}
+
+fn check_parents_in_node(node: &Node, value: &Value, lints: &mut Lints) {
+    match (node.kind(), value) {
+        (NodeKind::Obj(ref fields), Value::Obj(ref obj)) => check_parents(fields, obj, lints),
+        (NodeKind::Arr(ref elements), Value::Arr(ref arr)) => {
+            for (element, value) in elements.iter().zip(arr.iter()) {
+                check_parents_in_node(element, value, lints);
+            }
+        }
+        (NodeKind::Tup(ref elements), Value::Tup(ref tup)) => {
+            for (element, value) in elements.iter().zip(tup.iter()) {
+                check_parents_in_node(element, value, lints);
+            }
+        }
+        _ => (),
+    }
+}
@@ -0,0 +1,95 @@
+//! Loading many .over files while reusing the files they include.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use over::loader::Loader;
+//!
+//! let loader = Loader::new();
+//!
+//! // "shared.over" is only parsed once if both files include it.
+//! let a = loader.load_file("a.over").unwrap();
+//! let b = loader.load_file("b.over").unwrap();
+//! ```
+
+use crate::{
+    context::ParseContext,
+    obj::Obj,
+    parse::{self, cache::IncludeCache},
+    OverResult,
+};
+use std::{path::Path, sync::Arc};
+
+/// A reusable loader which caches parsed include files between loads.
+///
+/// Includes are cached by their full path and include type, and an include is parsed again once
+/// its modification time or length, or that of any file it includes, changes. The files being
+/// loaded are always parsed again; only the files they include are cached.
+///
+/// As only the files are checked, a cached include keeps the results of any `env()` calls and
+/// host functions from when it was first parsed, even if they would return something else now.
+/// Call `invalidate` or `clear` to parse such includes again.
+///
+/// A `Loader` can be shared between threads. Cloning a `Loader` returns a new handle to the same
+/// cache.
+#[derive(Clone, Debug, Default)]
+pub struct Loader {
+    ctx: ParseContext,
+    cache: Arc<IncludeCache>,
+}
+
+impl Loader {
+    /// Returns a new loader with an empty cache and context.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns a new loader with an empty cache, using `ctx` for every load.
+    pub fn with_context(ctx: ParseContext) -> Self {
+        Self {
+            ctx,
+            cache: Default::default(),
+        }
+    }
+
+    /// Returns the context used for every load.
+    pub fn context(&self) -> &ParseContext {
+        &self.ctx
+    }
+
+    /// Loads an `Obj` from the file at `path`.
+    pub fn load_file(&self, path: &str) -> OverResult<Obj> {
+        Ok(parse::load_from_file_cached(path, &self.ctx, &self.cache)?)
+    }
+
+    /// Loads an `Obj` from a `&str`.
+    pub fn load_str(&self, contents: &str) -> OverResult<Obj> {
+        Ok(parse::load_from_str_cached(
+            contents,
+            &self.ctx,
+            &self.cache,
+        )?)
+    }
+
+    /// Removes the cached includes for the file at `path` and every cached include depending on
+    /// it. Returns the number of includes removed.
+    pub fn invalidate(&self, path: &str) -> usize {
+        match Path::new(path).canonicalize() {
+            Ok(full_path) => match full_path.to_str() {
+                Some(full_path) => self.cache.invalidate(full_path),
+                None => 0,
+            },
+            Err(_) => 0,
+        }
+    }
+
+    /// Removes every cached include.
+    pub fn clear(&self) {
+        self.cache.clear();
+    }
+
+    /// Returns the number of cached includes.
+    pub fn cached_len(&self) -> usize {
+        self.cache.len()
+    }
+}
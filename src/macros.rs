@@ -90,6 +90,9 @@ macro_rules! obj {
 /// Given a list of field to `Value` pairs, returns an `Obj` with the fields and values.
 /// Returns an `OverResult` instead of panicking on error. To create an empty `Obj`, use `obj!` as
 /// it will never fail.
+///
+/// Parents are given with the field `"^"`, either as an `Obj` or an `Arr` of `Obj`s. The field
+/// may be repeated to add several parents.
 #[macro_export]
 macro_rules! try_obj {
     { $( $field:expr => $inner:expr ),+ , } => {
@@ -97,27 +100,23 @@ macro_rules! try_obj {
         try_obj!{ $( $field => $inner ),* };
     };
     { $( $field:expr => $inner:expr ),+ } => {
-        #[allow(clippy::useless_let_if_seq)]
         {
             use $crate::obj::{Obj, Pair};
 
             let mut _pairs = vec![];
-            let mut _parent: Option<$crate::value::Value> = None;
+            let mut _parents: Vec<$crate::value::Value> = vec![];
 
             $(
                 if $field == "^" {
-                    _parent = Some($inner.into());
+                    _parents.push($inner.into());
                 } else {
                     _pairs.push(Pair($field.into(), $inner.into()));
                 }
             )*
 
-            match _parent {
-                Some(parent) => match parent.get_obj() {
-                    Ok(parent) => Obj::from_pairs(_pairs, Some(parent)),
-                    e @ Err(_) => e,
-                }
-                None => Obj::from_pairs(_pairs, None),
+            match Obj::parents_from_values(_parents) {
+                Ok(parents) => Obj::from_pairs_with_parents(_pairs, parents),
+                Err(e) => Err(e),
             }
         }
     };
@@ -2,27 +2,134 @@
 
 use crate::{
     arr::Arr,
-    error::OverError,
+    context::ParseContext,
+    error::{OverError, ParseError},
+    layered::ArrMerge,
     parse::{self, format::Format},
     tup::Tup,
-    util,
+    types::Type,
+    util::{self, join_path},
     value::Value,
     OverResult, ReferenceType, INDENT_STEP,
 };
 use num_bigint::BigInt;
 use num_rational::BigRational;
-use std::{fmt, slice::Iter, str::FromStr, sync::Arc};
+use std::{
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+    fmt,
+    hash::{Hash, Hasher},
+    iter::{self, FromIterator},
+    ops::Index,
+    slice::Iter,
+    str::FromStr,
+    sync::{Arc, OnceLock},
+    vec,
+};
 
 /// Field-value pair.
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub struct Pair(pub String, pub Value);
 
+impl From<(String, Value)> for Pair {
+    fn from((field, value): (String, Value)) -> Self {
+        Pair(field, value)
+    }
+}
+
+// Number of pairs above which fields are looked up through a hash index instead of a linear scan.
+const INDEX_THRESHOLD: usize = 16;
+
+// Field-value pairs, stored in the order they are added, with a hash index from each field to its
+// position. The index is only built once there are more than `INDEX_THRESHOLD` pairs and a field is
+// looked up, and is kept up to date as pairs are pushed afterwards.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct PairMap {
+    pairs: Vec<Pair>,
+    index: OnceLock<HashMap<String, usize>>,
+}
+
+impl PairMap {
+    pub(crate) fn new(pairs: Vec<Pair>) -> Self {
+        Self {
+            pairs,
+            index: OnceLock::new(),
+        }
+    }
+
+    // Returns the position of `field`, or of its first occurrence if it occurs more than once.
+    pub(crate) fn position(&self, field: &str) -> Option<usize> {
+        if self.pairs.len() <= INDEX_THRESHOLD && self.index.get().is_none() {
+            return self
+                .pairs
+                .iter()
+                .position(|Pair(ref field_name, _)| field_name == field);
+        }
+
+        self.index
+            .get_or_init(|| {
+                let mut index = HashMap::with_capacity(self.pairs.len());
+                for (i, Pair(field, _)) in self.pairs.iter().enumerate() {
+                    let _ = index.entry(field.clone()).or_insert(i);
+                }
+                index
+            })
+            .get(field)
+            .copied()
+    }
+
+    pub(crate) fn get(&self, field: &str) -> Option<&Value> {
+        self.position(field).map(|i| &self.pairs[i].1)
+    }
+
+    pub(crate) fn contains(&self, field: &str) -> bool {
+        self.position(field).is_some()
+    }
+
+    // Sets the value of `field`, adding it at the end if it is not present.
+    pub(crate) fn set(&mut self, field: &str, value: Value) {
+        match self.position(field) {
+            Some(i) => self.pairs[i].1 = value,
+            None => self.push(Pair(field.into(), value)),
+        }
+    }
+
+    pub(crate) fn value_mut(&mut self, i: usize) -> &mut Value {
+        &mut self.pairs[i].1
+    }
+
+    pub(crate) fn push(&mut self, pair: Pair) {
+        if let Some(index) = self.index.get_mut() {
+            let _ = index.entry(pair.0.clone()).or_insert(self.pairs.len());
+        }
+        self.pairs.push(pair);
+    }
+
+    pub(crate) fn as_vec(&self) -> &Vec<Pair> {
+        &self.pairs
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.pairs.len()
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.pairs.is_empty()
+    }
+}
+
+impl PartialEq for PairMap {
+    fn eq(&self, other: &Self) -> bool {
+        self.pairs == other.pairs
+    }
+}
+
 #[derive(Clone, Debug)]
 struct ObjInner {
     // Field-value pairs. Stored in the order they are added.
-    pairs: Vec<Pair>,
-    // Optional parent.
-    parent: Option<Obj>,
+    pairs: PairMap,
+    // Parents, in order of precedence.
+    parents: Vec<Obj>,
     // Unique ID.
     id: usize,
 }
@@ -40,16 +147,14 @@ macro_rules! get_fn {
         #[doc=$doc]
         pub fn $name(&self, field: &str) -> OverResult<$type> {
             match self.get(field) {
-                Some(value) => {
-                    match value.$name() {
-                        Ok(result) => Ok(result),
-                        e @ Err(_) => e,
-                    }
-                }
+                Some(value) => match value.$name() {
+                    Ok(result) => Ok(result),
+                    e @ Err(_) => e,
+                },
                 None => Err(OverError::FieldNotFound(field.into())),
             }
         }
-    }
+    };
 }
 
 impl Obj {
@@ -67,6 +172,77 @@ impl Obj {
         BigInt
     );
 
+    get_fn!(
+        "Returns the `u8` found at `field`. Returns an error if the field was not found, if the \
+         `Value` at `field` is not `Int`, or if it is out of range for `u8`.",
+        get_u8,
+        u8
+    );
+
+    get_fn!(
+        "Returns the `u16` found at `field`. Returns an error if the field was not found, if the \
+         `Value` at `field` is not `Int`, or if it is out of range for `u16`.",
+        get_u16,
+        u16
+    );
+
+    get_fn!(
+        "Returns the `u32` found at `field`. Returns an error if the field was not found, if the \
+         `Value` at `field` is not `Int`, or if it is out of range for `u32`.",
+        get_u32,
+        u32
+    );
+
+    get_fn!(
+        "Returns the `u64` found at `field`. Returns an error if the field was not found, if the \
+         `Value` at `field` is not `Int`, or if it is out of range for `u64`.",
+        get_u64,
+        u64
+    );
+
+    get_fn!(
+        "Returns the `usize` found at `field`. Returns an error if the field was not found, if the \
+         `Value` at `field` is not `Int`, or if it is out of range for `usize`.",
+        get_usize,
+        usize
+    );
+
+    get_fn!(
+        "Returns the `i8` found at `field`. Returns an error if the field was not found, if the \
+         `Value` at `field` is not `Int`, or if it is out of range for `i8`.",
+        get_i8,
+        i8
+    );
+
+    get_fn!(
+        "Returns the `i16` found at `field`. Returns an error if the field was not found, if the \
+         `Value` at `field` is not `Int`, or if it is out of range for `i16`.",
+        get_i16,
+        i16
+    );
+
+    get_fn!(
+        "Returns the `i32` found at `field`. Returns an error if the field was not found, if the \
+         `Value` at `field` is not `Int`, or if it is out of range for `i32`.",
+        get_i32,
+        i32
+    );
+
+    get_fn!(
+        "Returns the `i64` found at `field`. Returns an error if the field was not found, if the \
+         `Value` at `field` is not `Int`, or if it is out of range for `i64`.",
+        get_i64,
+        i64
+    );
+
+    get_fn!(
+        "Returns the `Int` or `Frac` found at `field` as the nearest `f64`. Returns an error if \
+         the field was not found, if the `Value` at `field` is not `Int` or `Frac`, or if it is \
+         too large for an `f64`. See `Value::get_f64` for details.",
+        get_f64,
+        f64
+    );
+
     get_fn!(
         "Returns the `BigRational` found at `field`. Returns an error if the field was not found \
          or if the `Value` at `field` is not `Frac`.",
@@ -114,13 +290,7 @@ impl Obj {
     /// A valid field name must start with an alphabetic character or '_' and subsequent characters
     /// must be alphabetic, numeric, or '_'.
     pub fn from_pairs(pairs: Vec<Pair>, parent: Option<Self>) -> OverResult<Self> {
-        for Pair(ref field, _) in &pairs {
-            if !Self::is_valid_field(field) {
-                return Err(OverError::InvalidFieldName(field.clone()));
-            }
-        }
-
-        Ok(Self::from_pairs_unchecked(pairs, parent))
+        Self::from_pairs_with_parents(pairs, parent.into_iter().collect())
     }
 
     /// Returns a new `Obj` created from the given `Vec` of `Pair`s with optional `parent`.
@@ -130,15 +300,69 @@ impl Obj {
     ///
     /// See `from_pairs` for more details.
     pub fn from_pairs_unchecked(pairs: Vec<Pair>, parent: Option<Self>) -> Self {
+        Self::from_pairs_with_parents_unchecked(pairs, parent.into_iter().collect())
+    }
+
+    /// Returns a new `Obj` created from the given `Vec` of `Pair`s with any number of `parents`.
+    ///
+    /// Parents are searched in order when looking up a field; see `ancestors` for details.
+    ///
+    /// Returns an error if a pair contains an invalid field name.
+    pub fn from_pairs_with_parents(pairs: Vec<Pair>, parents: Vec<Self>) -> OverResult<Self> {
+        for Pair(ref field, _) in &pairs {
+            if !Self::is_valid_field(field) {
+                return Err(OverError::InvalidFieldName(field.clone()));
+            }
+        }
+
+        Ok(Self::from_pairs_with_parents_unchecked(pairs, parents))
+    }
+
+    /// Returns a new `Obj` created from the given `Vec` of `Pair`s with any number of `parents`,
+    /// without checking the field names.
+    ///
+    /// See `from_pairs_with_parents` and `from_pairs_unchecked` for more details.
+    pub fn from_pairs_with_parents_unchecked(pairs: Vec<Pair>, parents: Vec<Self>) -> Self {
+        Self::from_pair_map(PairMap::new(pairs), parents)
+    }
+
+    // Returns a new `Obj` from pairs which may already be indexed, without checking the field
+    // names.
+    pub(crate) fn from_pair_map(pairs: PairMap, parents: Vec<Self>) -> Self {
         let id = crate::gen_id();
 
         Self {
-            inner: Arc::new(ObjInner { pairs, parent, id }),
+            inner: Arc::new(ObjInner { pairs, parents, id }),
         }
     }
 
+    // Converts the values given for the "^" field in `obj!` to parents. Each value must be either
+    // an `Obj` or an `Arr` of `Obj`s.
+    #[doc(hidden)]
+    pub fn parents_from_values(values: Vec<Value>) -> OverResult<Vec<Self>> {
+        let mut parents = vec![];
+
+        for value in values {
+            match value {
+                Value::Arr(ref arr) if arr.inner_type() == Type::Obj => {
+                    for parent in arr.iter() {
+                        parents.push(parent.get_obj()?);
+                    }
+                }
+                value => parents.push(value.get_obj()?),
+            }
+        }
+
+        Ok(parents)
+    }
+
     /// Returns a reference to the inner vec of this `Obj`.
     pub fn pairs_ref(&self) -> &Vec<Pair> {
+        self.inner.pairs.as_vec()
+    }
+
+    // Returns a reference to the indexed pairs of this `Obj`.
+    pub(crate) fn pair_map(&self) -> &PairMap {
         &self.inner.pairs
     }
 
@@ -147,6 +371,46 @@ impl Obj {
         Ok(parse::load_from_file(path)?)
     }
 
+    /// Returns a new `Obj` loaded from a file, with the predefined globals and host functions in
+    /// `ctx` available to it and to any files it includes.
+    pub fn from_file_with_context(path: &str, ctx: &ParseContext) -> OverResult<Self> {
+        Ok(parse::load_from_file_with_context(path, ctx)?)
+    }
+
+    /// Returns a new `Obj` loaded from a file, together with the full paths of the file and of
+    /// every file it includes, directly or indirectly, in sorted order.
+    pub fn from_file_with_deps(path: &str) -> OverResult<(Self, Vec<String>)> {
+        Self::from_file_with_context_and_deps(path, &ParseContext::new())
+    }
+
+    /// Returns a new `Obj` loaded from a file with the given context, together with the full paths
+    /// of every file it depends on. See `from_file_with_context` and `from_file_with_deps`.
+    pub fn from_file_with_context_and_deps(
+        path: &str,
+        ctx: &ParseContext,
+    ) -> OverResult<(Self, Vec<String>)> {
+        Ok(parse::load_from_file_with_deps(path, ctx)?)
+    }
+
+    /// Returns a new `Obj` parsed from a `&str`, with the predefined globals and host functions in
+    /// `ctx` available to it.
+    pub fn from_str_with_context(contents: &str, ctx: &ParseContext) -> OverResult<Self> {
+        Ok(parse::load_from_str_with_context(contents, ctx)?)
+    }
+
+    /// Returns a new `Obj` parsed from a `&str` holding the contents of the file at `path`, such as
+    /// an unsaved buffer in an editor. Includes are resolved relative to `path` and errors name it.
+    pub fn from_str_in_file(contents: &str, path: &str) -> OverResult<Self> {
+        Ok(parse::load_from_str_in_file(contents, Some(path))?)
+    }
+
+    /// Like `from_str_in_file`, but returns the `ParseError` itself on failure, which gives the
+    /// file, line and column of the error. `path` may be `None` for contents which are not backed
+    /// by a file.
+    pub fn parse_str_in_file(contents: &str, path: Option<&str>) -> Result<Self, ParseError> {
+        parse::load_from_str_in_file(contents, path)
+    }
+
     /// Writes this `Obj` to given file in `.over` representation.
     ///
     /// # Notes
@@ -155,7 +419,8 @@ impl Obj {
     ///
     /// Also note some shorthand from the original file, including mathematical operations and file
     /// includes, may not be preserved when creating the `Obj` representation, and may not appear
-    /// when writing to another file.
+    /// when writing to another file. To edit a file while keeping its comments and layout, use
+    /// `document::Document` instead.
     pub fn write_to_file(&self, path: &str) -> OverResult<()> {
         util::write_file_str(path, &self.write_to_string())?;
         Ok(())
@@ -177,7 +442,7 @@ impl Obj {
     where
         F: FnMut(&String, &Value),
     {
-        for Pair(field, value) in &self.inner.pairs {
+        for Pair(field, value) in self.iter() {
             f(field, value)
         }
     }
@@ -200,63 +465,262 @@ impl Obj {
     ///
     /// Parent fields are not included.
     pub fn contains(&self, field: &str) -> bool {
-        self.inner
-            .pairs
-            .iter()
-            .any(|Pair(pair_field, _)| field == pair_field)
+        self.inner.pairs.contains(field)
     }
 
-    /// Gets the `Value` associated with `field` in this object or the first parent with `field`.
+    /// Gets the `Value` associated with `field` in this object or the first ancestor with `field`.
+    ///
+    /// See `ancestors` for the order in which ancestors are searched.
     pub fn get(&self, field: &str) -> Option<Value> {
-        match self
-            .inner
-            .pairs
-            .iter()
-            .find(|Pair(ref field_name, _)| field_name == field)
-        {
-            Some(Pair(_, ref value)) => Some(value.clone()),
-            None => match self.inner.parent {
-                Some(ref parent) => parent.get(field),
-                None => None,
-            },
-        }
+        self.get_with_source(field).map(|(value, _)| value)
     }
 
     /// Gets the `Value` associated with `field` and the `Obj` where it was found (either `self` or
-    /// one of its parents).
+    /// one of its ancestors).
     pub fn get_with_source(&self, field: &str) -> Option<(Value, Self)> {
-        match self
-            .inner
-            .pairs
-            .iter()
-            .find(|Pair(ref field_name, _)| field_name == field)
-        {
-            Some(Pair(_, ref value)) => Some((value.clone(), self.clone())),
-            None => match self.inner.parent {
-                Some(ref parent) => parent.get_with_source(field),
-                None => None,
-            },
+        if let Some(value) = self.get_own(field) {
+            return Some((value.clone(), self.clone()));
+        }
+
+        match self.inner.parents.as_slice() {
+            [] => None,
+            // With a single parent, the ancestors are the parent followed by its own ancestors.
+            [parent] => parent.get_with_source(field),
+            _ => self
+                .ancestors()
+                .into_iter()
+                .find_map(|obj| obj.get_own(field).map(|value| (value.clone(), obj.clone()))),
         }
     }
 
+    // Like `get`, but borrows the `Value` from this object or the ancestor where it was found.
+    pub(crate) fn get_ref(&self, field: &str) -> Option<&Value> {
+        if let Some(value) = self.get_own(field) {
+            return Some(value);
+        }
+
+        match self.inner.parents.as_slice() {
+            [] => None,
+            [parent] => parent.get_ref(field),
+            _ => self
+                .ancestors_ref()
+                .into_iter()
+                .find_map(|obj| obj.get_own(field)),
+        }
+    }
+
+    // Gets the `Value` associated with `field` in this object, ignoring parents.
+    fn get_own(&self, field: &str) -> Option<&Value> {
+        self.inner.pairs.get(field)
+    }
+
     /// Returns whether this `Obj` has a parent.
     pub fn has_parent(&self) -> bool {
-        self.inner.parent.is_some()
+        !self.inner.parents.is_empty()
     }
 
-    /// Returns the parent for this `Obj`.
+    /// Returns the first parent for this `Obj`.
+    ///
+    /// An `Obj` may have several parents, in which case the others are ignored. Use `parents_ref`
+    /// to get all of them.
+    #[deprecated(
+        since = "0.6.5",
+        note = "an `Obj` may have several parents; use `parents_ref` instead"
+    )]
     pub fn get_parent(&self) -> Option<Self> {
-        match self.inner.parent {
-            Some(ref parent) => Some(parent.clone()),
-            None => None,
+        self.inner.parents.first().cloned()
+    }
+
+    /// Returns a reference to the parents of this `Obj`, in order of precedence.
+    pub fn parents_ref(&self) -> &Vec<Self> {
+        &self.inner.parents
+    }
+
+    /// Returns all ancestors of this `Obj` (its parents, their parents, and so on) in the order
+    /// they are searched for fields by `get`.
+    ///
+    /// The order is found by a depth-first, left-to-right search through the parents, in which
+    /// only the last occurrence of an ancestor reachable through several parents is kept. This
+    /// means that an ancestor always comes after every `Obj` that inherits from it: if `a` has
+    /// parents `b` and `c`, which both have parent `d`, the order is `b`, `c`, `d`.
+    pub fn ancestors(&self) -> Vec<Self> {
+        self.ancestors_ref().into_iter().cloned().collect()
+    }
+
+    // Like `ancestors`, but borrows each ancestor from `self`.
+    fn ancestors_ref(&self) -> Vec<&Self> {
+        // Builds the order in reverse: the parents are walked right-to-left, and each ancestor is
+        // added after its own ancestors, the first time it is reached. An ancestor which was
+        // already added is skipped together with its own ancestors, which were added before it,
+        // so every ancestor is only visited once even with many shared ancestors.
+        fn visit<'a>(obj: &'a Obj, seen: &mut HashSet<usize>, order: &mut Vec<&'a Obj>) {
+            for parent in obj.inner.parents.iter().rev() {
+                if !seen.contains(&parent.id()) {
+                    visit(parent, seen, order);
+                    let _ = seen.insert(parent.id());
+                    order.push(parent);
+                }
+            }
+        }
+
+        let mut order = vec![];
+        visit(self, &mut HashSet::new(), &mut order);
+        order.reverse();
+        order
+    }
+
+    /// Returns a new `Obj` containing the fields of both `self` and `other`. Where both contain the
+    /// same field, the value in `other` is used.
+    ///
+    /// Fields are kept in the order of `self`, followed by the fields only found in `other`.
+    ///
+    /// # Notes
+    ///
+    /// Only fields defined directly on each `Obj` are merged; parent fields are not copied. The
+    /// result has the parents of `other` followed by those of `self`, so that fields inherited
+    /// through `other` take precedence.
+    pub fn merge(&self, other: &Self) -> Self {
+        let mut pairs = self.inner.pairs.clone();
+
+        for Pair(field, value) in other.iter() {
+            pairs.set(field, value.clone());
+        }
+
+        Self::from_pair_map(pairs, self.merged_parents(other))
+    }
+
+    // Returns the parents of `other` followed by the parents of `self` not already included.
+    pub(crate) fn merged_parents(&self, other: &Self) -> Vec<Self> {
+        let mut parents = other.inner.parents.clone();
+
+        for parent in &self.inner.parents {
+            if !parents.iter().any(|p| p.ptr_eq(parent)) {
+                parents.push(parent.clone());
+            }
+        }
+
+        parents
+    }
+
+    /// Like `merge`, but fields containing an `Obj` in both `self` and `other` are merged
+    /// recursively instead of being replaced.
+    ///
+    /// Returns an error if a field is present in both but the values have different types.
+    pub fn deep_merge(&self, other: &Self) -> OverResult<Self> {
+        self.deep_merge_with(other, ArrMerge::Replace)
+    }
+
+    /// Like `deep_merge`, but `Arr`s present in both `self` and `other` are combined according to
+    /// `arr_merge`. Appended `Arr`s must have compatible types.
+    pub fn deep_merge_with(&self, other: &Self, arr_merge: ArrMerge) -> OverResult<Self> {
+        self.deep_merge_at(other, "", arr_merge, &mut |_, _| ())
+    }
+
+    // Deep merges `other` into `self`, which is found at the dotted `path`. Each change is reported
+    // to `on_step` along with the path of the value it affects.
+    pub(crate) fn deep_merge_at(
+        &self,
+        other: &Self,
+        path: &str,
+        arr_merge: ArrMerge,
+        on_step: &mut dyn FnMut(&str, MergeStep<'_>),
+    ) -> OverResult<Self> {
+        let mut pairs = self.inner.pairs.clone();
+
+        for Pair(field, value) in other.iter() {
+            let field_path = join_path(path, field);
+
+            match pairs.position(field) {
+                Some(i) => {
+                    let merged = deep_merge_values(
+                        &pairs.as_vec()[i].1,
+                        value,
+                        &field_path,
+                        arr_merge,
+                        on_step,
+                    )?;
+                    *pairs.value_mut(i) = merged;
+                }
+                None => {
+                    on_step(&field_path, MergeStep::Added(value));
+                    pairs.push(Pair(field.clone(), value.clone()));
+                }
+            }
         }
+
+        Ok(Self::from_pair_map(pairs, self.merged_parents(other)))
+    }
+
+    /// Returns a new `Obj` without the given fields. Fields which are not present are ignored.
+    ///
+    /// # Notes
+    ///
+    /// Only fields defined directly on `self` are removed. The parents are kept, so a removed field
+    /// which is also defined by a parent is still found by `get`.
+    pub fn remove_fields(&self, fields: &[&str]) -> Self {
+        let pairs = self
+            .iter()
+            .filter(|Pair(ref field, _)| !fields.contains(&field.as_str()))
+            .cloned()
+            .collect();
+
+        Self::from_pairs_with_parents_unchecked(pairs, self.inner.parents.clone())
     }
 
     /// An iterator visiting all field-value pairs in order.
-    pub fn iter(&self) -> Iter<Pair> {
+    ///
+    /// Parent fields are not included. See `iter_all`.
+    pub fn iter(&self) -> Iter<'_, Pair> {
         self.pairs_ref().iter()
     }
 
+    /// An iterator visiting every field-value pair visible through `get`, including inherited
+    /// fields, together with the `Obj` where each was found (either `self` or one of its
+    /// ancestors).
+    ///
+    /// Fields defined on `self` come first, in order, followed by the remaining fields of each
+    /// ancestor in the order given by `ancestors`. A field overridden by `self` or an earlier
+    /// ancestor is only visited once, with the overriding value.
+    pub fn iter_all(&self) -> vec::IntoIter<(Pair, Self)> {
+        let mut seen = HashSet::new();
+        let mut all = vec![];
+
+        for obj in iter::once(self.clone()).chain(self.ancestors()) {
+            for pair in obj.iter() {
+                if seen.insert(pair.0.clone()) {
+                    all.push((pair.clone(), obj.clone()));
+                }
+            }
+        }
+
+        all.into_iter()
+    }
+
+    /// An iterator visiting the names of every field visible through `get`, including inherited
+    /// fields, in the same order as `iter_all`.
+    pub fn keys_all(&self) -> vec::IntoIter<String> {
+        self.iter_all()
+            .map(|(Pair(field, _), _)| field)
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    /// Returns true if this `Obj` or one of its ancestors contains `field`.
+    pub fn contains_all(&self, field: &str) -> bool {
+        self.contains(field) || self.ancestors().iter().any(|obj| obj.contains(field))
+    }
+
+    /// Returns a new `Obj` without parents, containing every field visible through `get`.
+    ///
+    /// Inherited fields are copied into the result, and fields overridden by `self` or an earlier
+    /// ancestor keep their overriding values. Field order is the same as for `iter_all`. Nested
+    /// `Obj`s are not flattened.
+    pub fn flatten(&self) -> Self {
+        let pairs = self.iter_all().map(|(pair, _)| pair).collect();
+
+        Self::from_pairs_unchecked(pairs, None)
+    }
+
     /// Returns true if `field` is a valid field name for an `Obj`.
     ///
     /// The first character must be alphabetic or '_'. Subsequent characters are allowed to be
@@ -293,6 +757,56 @@ impl Obj {
     }
 }
 
+// A change made while deep merging, reported to the callback passed to `Obj::deep_merge_at`.
+pub(crate) enum MergeStep<'a> {
+    // The value from `other` was added, as `self` did not have the field.
+    Added(&'a Value),
+    // The value from `other` replaced the value in `self`.
+    Replaced(&'a Value),
+    // `Obj`s present in both were merged.
+    Merged,
+    // The elements of the `Arr` from `other` were appended, starting at the given index.
+    Appended(usize, &'a Arr),
+}
+
+// Deep merges `value2` into `value1`, which are found at the dotted `path`.
+fn deep_merge_values(
+    value1: &Value,
+    value2: &Value,
+    path: &str,
+    arr_merge: ArrMerge,
+    on_step: &mut dyn FnMut(&str, MergeStep<'_>),
+) -> OverResult<Value> {
+    let (t1, t2) = (value1.get_type(), value2.get_type());
+
+    match (value1, value2) {
+        (Value::Obj(ref obj1), Value::Obj(ref obj2)) => {
+            on_step(path, MergeStep::Merged);
+            Ok(obj1.deep_merge_at(obj2, path, arr_merge, on_step)?.into())
+        }
+        (Value::Arr(ref arr1), Value::Arr(ref arr2)) if arr_merge == ArrMerge::Append => {
+            let inner_t = match Type::most_specific(&arr1.inner_type(), &arr2.inner_type()) {
+                Some((t, _)) => t,
+                None => return Err(OverError::FieldTypeMismatch(path.into(), t1, t2)),
+            };
+            on_step(path, MergeStep::Appended(arr1.len(), arr2));
+
+            let mut values = arr1.values_ref().clone();
+            values.extend(arr2.iter().cloned());
+
+            Ok(Arr::from_values_unchecked(values, inner_t).into())
+        }
+        _ => {
+            if t1 != t2 {
+                return Err(OverError::FieldTypeMismatch(path.into(), t1, t2));
+            }
+            on_step(path, MergeStep::Replaced(value2));
+
+            Ok(value2.clone())
+        }
+    }
+}
+
 impl ReferenceType for Obj {
     fn id(&self) -> usize {
         self.inner.id
@@ -327,8 +841,69 @@ impl FromStr for Obj {
     }
 }
 
+/// Collects field-value pairs into an `Obj` without parents, returning an error if a field name
+/// is invalid. A field given more than once keeps its position from the first time and its value
+/// from the last time.
+///
+/// `(String, Value)` tuples can be collected by mapping them with `Pair::from` first. Collecting
+/// them directly is not possible, as neither the tuple nor `OverResult` belongs to this crate.
+impl FromIterator<Pair> for OverResult<Obj> {
+    fn from_iter<I: IntoIterator<Item = Pair>>(iter: I) -> Self {
+        let mut pairs = PairMap::default();
+
+        for Pair(field, value) in iter {
+            if !Obj::is_valid_field(&field) {
+                return Err(OverError::InvalidFieldName(field));
+            }
+            pairs.set(&field, value);
+        }
+
+        Ok(Obj::from_pair_map(pairs, vec![]))
+    }
+}
+
+/// Returns the value of `field`, which may be inherited from an ancestor as with `get`.
+///
+/// # Panics
+///
+/// Panics if neither this `Obj` nor any of its ancestors contains `field`. Use `get` to return
+/// `None` instead.
+impl Index<&str> for Obj {
+    type Output = Value;
+
+    fn index(&self, field: &str) -> &Value {
+        self.get_ref(field)
+            .unwrap_or_else(|| panic!("Field not found: \"{}\"", field))
+    }
+}
+
+/// Iterates over the field-value pairs of this `Obj`, not including parent fields. See `iter`.
+impl<'a> IntoIterator for &'a Obj {
+    type Item = &'a Pair;
+    type IntoIter = Iter<'a, Pair>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Moves the field-value pairs out of the `Obj`, cloning them if it is shared. Parent fields are
+/// not included.
+impl IntoIterator for Obj {
+    type Item = Pair;
+    type IntoIter = vec::IntoIter<Pair>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        match Arc::try_unwrap(self.inner) {
+            Ok(inner) => inner.pairs.pairs,
+            Err(inner) => inner.pairs.pairs.clone(),
+        }
+        .into_iter()
+    }
+}
+
 /// For two Objs to be equal, the following two checks must pass:
-/// 1. If either Obj has a parent, then both must have parents and the parents must be equal.
+/// 1. Both Objs must have the same number of parents, and the parents must be equal in order.
 /// 2. The two Objs must have all the same fields pointing to the same values.
 impl PartialEq for Obj {
     fn eq(&self, other: &Self) -> bool {
@@ -336,13 +911,7 @@ impl PartialEq for Obj {
         let other_inner = &other.inner;
 
         // Check parent equality.
-        if inner.parent.is_some() && other_inner.parent.is_some() {
-            let parent = self.get_parent().unwrap();
-            let other_parent = other.get_parent().unwrap();
-            if parent != other_parent {
-                return false;
-            }
-        } else if !(inner.parent.is_none() && other_inner.parent.is_none()) {
+        if inner.parents != other_inner.parents {
             return false;
         }
 
@@ -352,3 +921,32 @@ impl PartialEq for Obj {
 }
 
 impl Eq for Obj {}
+
+// The field index in `PairMap` is not hashed, so an `Obj` is safe to use as a key even though the
+// index is built lazily.
+impl Hash for Obj {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.inner.pairs.pairs.hash(state);
+        self.inner.parents.hash(state);
+    }
+}
+
+/// `Obj`s are ordered by comparing their field-value pairs in order, and then their parents in
+/// order. As with equality, inherited fields are only compared through the parents.
+impl Ord for Obj {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let (inner, other_inner) = (&self.inner, &other.inner);
+
+        inner
+            .pairs
+            .pairs
+            .cmp(&other_inner.pairs.pairs)
+            .then_with(|| inner.parents.cmp(&other_inner.parents))
+    }
+}
+
+impl PartialOrd for Obj {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
@@ -0,0 +1,290 @@
+//! Built-in functions callable from .over files.
+
+use super::Arity;
+use crate::{
+    arr::Arr,
+    types::Type::{self, *},
+    value::Value,
+};
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use num_traits::{Signed, Zero};
+use std::cmp::Ordering;
+
+/// Error returned by a built-in function.
+#[derive(Debug)]
+pub enum CallError {
+    /// The argument at the given index did not have one of the expected types.
+    ArgType(usize, Vec<Type>),
+    /// The function failed for some other reason.
+    Failed(String),
+}
+
+type CallResult = Result<Value, CallError>;
+
+/// The maximum number of elements `range` can return.
+pub const MAX_RANGE_LEN: usize = 1_000_000;
+
+/// A built-in function.
+pub struct Builtin {
+    pub name: &'static str,
+    pub arity: Arity,
+    pub call: fn(&[Value]) -> CallResult,
+}
+
+static BUILTINS: &[Builtin] = &[
+    Builtin {
+        name: "len",
+        arity: Arity::Exactly(1),
+        call: len,
+    },
+    Builtin {
+        name: "keys",
+        arity: Arity::Exactly(1),
+        call: keys,
+    },
+    Builtin {
+        name: "min",
+        arity: Arity::AtLeast(1),
+        call: min,
+    },
+    Builtin {
+        name: "max",
+        arity: Arity::AtLeast(1),
+        call: max,
+    },
+    Builtin {
+        name: "merge",
+        arity: Arity::Exactly(2),
+        call: merge,
+    },
+    Builtin {
+        name: "floor",
+        arity: Arity::Exactly(1),
+        call: floor,
+    },
+    Builtin {
+        name: "ceil",
+        arity: Arity::Exactly(1),
+        call: ceil,
+    },
+    Builtin {
+        name: "round",
+        arity: Arity::Exactly(1),
+        call: round,
+    },
+    Builtin {
+        name: "upper",
+        arity: Arity::Exactly(1),
+        call: upper,
+    },
+    Builtin {
+        name: "lower",
+        arity: Arity::Exactly(1),
+        call: lower,
+    },
+    Builtin {
+        name: "join",
+        arity: Arity::Exactly(2),
+        call: join,
+    },
+    Builtin {
+        name: "split",
+        arity: Arity::Exactly(2),
+        call: split,
+    },
+    Builtin {
+        name: "range",
+        arity: Arity::Between(1, 3),
+        call: range,
+    },
+];
+
+/// Returns the built-in function with the given name, if there is one.
+pub fn get_builtin(name: &str) -> Option<&'static Builtin> {
+    BUILTINS.iter().find(|builtin| builtin.name == name)
+}
+
+fn str_arg(args: &[Value], index: usize) -> Result<String, CallError> {
+    args[index]
+        .get_str()
+        .map_err(|_| CallError::ArgType(index, vec![Str]))
+}
+
+fn int_arg(args: &[Value], index: usize) -> Result<BigInt, CallError> {
+    args[index]
+        .get_int()
+        .map_err(|_| CallError::ArgType(index, vec![Int]))
+}
+
+fn frac_arg(args: &[Value], index: usize) -> Result<BigRational, CallError> {
+    args[index]
+        .get_frac()
+        .map_err(|_| CallError::ArgType(index, vec![Frac, Int]))
+}
+
+fn len(args: &[Value]) -> CallResult {
+    let len = match args[0] {
+        Value::Str(ref s) => s.chars().count(),
+        Value::Arr(ref arr) => arr.len(),
+        Value::Tup(ref tup) => tup.len(),
+        Value::Obj(ref obj) => obj.len(),
+        _ => {
+            return Err(CallError::ArgType(
+                0,
+                vec![Str, Arr(Box::new(Any)), Tup(vec![]), Obj],
+            ))
+        }
+    };
+
+    Ok(len.into())
+}
+
+fn keys(args: &[Value]) -> CallResult {
+    let obj = args[0]
+        .get_obj()
+        .map_err(|_| CallError::ArgType(0, vec![Obj]))?;
+    let keys = obj.iter().map(|pair| Value::Str(pair.0.clone())).collect();
+
+    Ok(Arr::from_values_unchecked(keys, Str).into())
+}
+
+// Finds the first numeric value that compares as `ordering` against every other value. Takes either
+// several numeric arguments or a single non-empty `Arr` of numbers.
+fn extremum(args: &[Value], ordering: Ordering) -> CallResult {
+    let values = match args {
+        [Value::Arr(arr)] => {
+            if arr.is_empty() {
+                return Err(CallError::Failed("Arr is empty".into()));
+            }
+            arr.values_ref().clone()
+        }
+        _ => args.to_vec(),
+    };
+
+    let mut best: Option<(Value, BigRational)> = None;
+    for (index, value) in values.into_iter().enumerate() {
+        let frac = match value.get_frac() {
+            Ok(frac) => frac,
+            Err(_) if args.len() == 1 => {
+                return Err(CallError::ArgType(
+                    0,
+                    vec![Arr(Box::new(Int)), Arr(Box::new(Frac)), Int, Frac],
+                ));
+            }
+            Err(_) => return Err(CallError::ArgType(index, vec![Int, Frac])),
+        };
+
+        best = match best {
+            Some((_, ref cur)) if frac.cmp(cur) == ordering => Some((value, frac)),
+            None => Some((value, frac)),
+            keep => keep,
+        };
+    }
+
+    Ok(best.unwrap().0)
+}
+
+fn min(args: &[Value]) -> CallResult {
+    extremum(args, Ordering::Less)
+}
+
+fn max(args: &[Value]) -> CallResult {
+    extremum(args, Ordering::Greater)
+}
+
+// Merges two Objs recursively.
+fn merge(args: &[Value]) -> CallResult {
+    let obj1 = args[0]
+        .get_obj()
+        .map_err(|_| CallError::ArgType(0, vec![Obj]))?;
+    let obj2 = args[1]
+        .get_obj()
+        .map_err(|_| CallError::ArgType(1, vec![Obj]))?;
+
+    match obj1.deep_merge(&obj2) {
+        Ok(obj) => Ok(obj.into()),
+        Err(e) => Err(CallError::Failed(format!("{}", e))),
+    }
+}
+
+fn floor(args: &[Value]) -> CallResult {
+    Ok(frac_arg(args, 0)?.floor().to_integer().into())
+}
+
+fn ceil(args: &[Value]) -> CallResult {
+    Ok(frac_arg(args, 0)?.ceil().to_integer().into())
+}
+
+// Rounds half-way cases away from zero.
+fn round(args: &[Value]) -> CallResult {
+    Ok(frac_arg(args, 0)?.round().to_integer().into())
+}
+
+fn upper(args: &[Value]) -> CallResult {
+    Ok(str_arg(args, 0)?.to_uppercase().into())
+}
+
+fn lower(args: &[Value]) -> CallResult {
+    Ok(str_arg(args, 0)?.to_lowercase().into())
+}
+
+fn join(args: &[Value]) -> CallResult {
+    let arr = match args[0] {
+        Value::Arr(ref arr) if arr.inner_type() == Str => arr.clone(),
+        _ => return Err(CallError::ArgType(0, vec![Arr(Box::new(Str))])),
+    };
+    let sep = str_arg(args, 1)?;
+
+    let strs: Vec<String> = arr.iter().map(|value| value.get_str().unwrap()).collect();
+
+    Ok(strs.join(&sep).into())
+}
+
+fn split(args: &[Value]) -> CallResult {
+    let s = str_arg(args, 0)?;
+    let sep = str_arg(args, 1)?;
+
+    if sep.is_empty() {
+        return Err(CallError::Failed("Separator must not be empty".into()));
+    }
+
+    let values = s.split(sep.as_str()).map(Value::from).collect();
+
+    Ok(Arr::from_values_unchecked(values, Str).into())
+}
+
+// Returns the Ints from `start` (inclusive) to `end` (exclusive), advancing by `step`.
+fn range(args: &[Value]) -> CallResult {
+    let (start, end, step) = match args.len() {
+        1 => (BigInt::zero(), int_arg(args, 0)?, BigInt::from(1)),
+        2 => (int_arg(args, 0)?, int_arg(args, 1)?, BigInt::from(1)),
+        _ => (int_arg(args, 0)?, int_arg(args, 1)?, int_arg(args, 2)?),
+    };
+
+    if step.is_zero() {
+        return Err(CallError::Failed("Step must not be zero".into()));
+    }
+
+    // Check the length up front so that untrusted files can't exhaust memory.
+    let len = if (step.is_positive() && start < end) || (step.is_negative() && start > end) {
+        (&end - &start - step.signum()) / &step + 1
+    } else {
+        BigInt::zero()
+    };
+    if len > BigInt::from(MAX_RANGE_LEN) {
+        return Err(CallError::Failed(format!(
+            "Range would have {} elements; the maximum is {}",
+            len, MAX_RANGE_LEN
+        )));
+    }
+
+    let mut values = vec![];
+    let mut cur = start;
+    while (step.is_positive() && cur < end) || (step.is_negative() && cur > end) {
+        values.push(Value::Int(cur.clone()));
+        cur += &step;
+    }
+
+    Ok(Arr::from_values_unchecked(values, Int).into())
+}
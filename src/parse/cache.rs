@@ -0,0 +1,97 @@
+//! Cache of parsed include files shared between loads.
+
+use super::IncludeType;
+use crate::value::Value;
+use std::{collections::HashMap, fs, sync::Mutex, time::SystemTime};
+
+/// Modification time and length of a file, or `None` if it could not be read. The length guards
+/// against changes made within the resolution of the modification time.
+pub type Stamp = Option<(SystemTime, u64)>;
+
+/// Full paths of files, with their modification times when they were parsed.
+pub type Stamps = HashMap<String, Stamp>;
+
+/// Returns the current modification time and length of the file at `path`.
+pub fn stamp(path: &str) -> Stamp {
+    fs::metadata(path)
+        .and_then(|metadata| Ok((metadata.modified()?, metadata.len())))
+        .ok()
+}
+
+#[derive(Debug)]
+struct Entry {
+    value: Value,
+    // The include file itself and every file it includes, directly or indirectly.
+    stamps: Stamps,
+}
+
+/// Parsed include files, keyed by full path and include type.
+///
+/// An entry is only used while the modification times and lengths of the include file and of
+/// every file it includes are unchanged.
+#[derive(Debug, Default)]
+pub struct IncludeCache {
+    entries: Mutex<HashMap<(String, IncludeType), Entry>>,
+}
+
+impl IncludeCache {
+    /// Returns the cached value of the include at the full path `path` together with the stamps of
+    /// the files it depends on, or `None` if it is not cached or out of date.
+    pub fn get(&self, path: &str, include_type: IncludeType) -> Option<(Value, Stamps)> {
+        let key = (path.to_string(), include_type);
+
+        // Check the files without holding the lock, so that other loads are not blocked on the
+        // file system.
+        let (value, stamps) = match self.entries.lock().unwrap().get(&key) {
+            Some(entry) => (entry.value.clone(), entry.stamps.clone()),
+            None => return None,
+        };
+
+        if stamps.iter().all(|(path, old)| stamp(path) == *old) {
+            Some((value, stamps))
+        } else {
+            // Only remove the entry if another load has not replaced it in the meantime.
+            let mut entries = self.entries.lock().unwrap();
+            if entries
+                .get(&key)
+                .is_some_and(|entry| entry.stamps == stamps)
+            {
+                let _ = entries.remove(&key);
+            }
+            None
+        }
+    }
+
+    /// Caches the value of the include at the full path `path`, which depends on the files in
+    /// `stamps`.
+    pub fn insert(&self, path: &str, include_type: IncludeType, value: Value, stamps: Stamps) {
+        let _ = self
+            .entries
+            .lock()
+            .unwrap()
+            .insert((path.into(), include_type), Entry { value, stamps });
+    }
+
+    /// Removes every entry for the full path `path` or depending on it. Returns the number of
+    /// entries removed.
+    pub fn invalidate(&self, path: &str) -> usize {
+        let mut entries = self.entries.lock().unwrap();
+        let len = entries.len();
+
+        entries.retain(|(entry_path, _), entry| {
+            entry_path != path && !entry.stamps.contains_key(path)
+        });
+
+        len - entries.len()
+    }
+
+    /// Removes every entry.
+    pub fn clear(&self) {
+        self.entries.lock().unwrap().clear();
+    }
+
+    /// Returns the number of entries.
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().len()
+    }
+}
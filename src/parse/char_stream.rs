@@ -1,27 +1,23 @@
 //! Character stream used for parsing.
 
 use std::{
-    cell::RefCell,
     fs::File,
     io::{self, Read},
-    iter::Peekable,
-    mem,
-    rc::Rc,
-    str::Chars,
+    sync::{Arc, OnceLock},
 };
 
-#[derive(Clone, Debug)]
-struct Inner {
-    file: Option<String>,
-    contents: String,
-    stream: Peekable<Chars<'static>>,
-    line: usize,
-    col: usize,
-}
-
+/// A stream of characters over a string, tracked by byte offset.
+///
+/// Line and column numbers are only computed when asked for, which should only happen when
+/// reporting an error.
 #[derive(Clone, Debug)]
 pub struct CharStream {
-    inner: Rc<RefCell<Inner>>,
+    file: Option<Arc<str>>,
+    contents: Arc<str>,
+    // Byte offset of the next character.
+    offset: usize,
+    // Byte offsets of the start of each line, computed on first use.
+    lines: OnceLock<Vec<usize>>,
 }
 
 impl CharStream {
@@ -40,79 +36,102 @@ impl CharStream {
         Self::from_string_impl(None, contents)
     }
 
-    fn from_string_impl(file: Option<String>, contents: String) -> io::Result<CharStream> {
-        let chars: Chars = unsafe { mem::transmute(contents.chars()) };
-        let stream = chars.peekable();
+    /// Creates a stream over `contents` as if they had been read from the file at `path`.
+    pub fn from_string_in_file(path: &str, contents: String) -> io::Result<CharStream> {
+        Self::from_string_impl(Some(String::from(path)), contents)
+    }
 
+    fn from_string_impl(file: Option<String>, contents: String) -> io::Result<CharStream> {
         Ok(CharStream {
-            inner: Rc::new(RefCell::new(Inner {
-                file,
-                contents,
-                stream,
-                line: 1,
-                col: 1,
-            })),
+            file: file.map(Arc::from),
+            contents: Arc::from(contents),
+            offset: 0,
+            lines: OnceLock::new(),
         })
     }
 
+    #[inline]
     pub fn peek(&self) -> Option<char> {
-        let mut inner = self.inner.borrow_mut();
-        let opt = inner.stream.peek();
-
-        match opt {
-            Some(ch) => Some(*ch),
+        match self.contents.as_bytes().get(self.offset) {
+            Some(&byte) if byte.is_ascii() => Some(byte as char),
+            Some(_) => self.rest().chars().next(),
             None => None,
         }
     }
 
     pub fn file(&self) -> Option<String> {
-        let inner = self.inner.borrow();
-        inner.file.clone()
+        self.file.as_ref().map(|file| file.to_string())
+    }
+
+    /// Returns the byte offset of the next character.
+    #[inline]
+    pub fn pos(&self) -> usize {
+        self.offset
     }
 
+    /// Moves the stream to the byte offset `pos`, which must be at a character boundary.
+    #[inline]
+    pub fn set_pos(&mut self, pos: usize) {
+        debug_assert!(self.contents.is_char_boundary(pos));
+        self.offset = pos;
+    }
+
+    /// Returns the line number of the next character.
     pub fn line(&self) -> usize {
-        let inner = self.inner.borrow();
-        inner.line
+        self.line_col(self.offset).0
     }
 
+    /// Returns the column number of the next character.
     pub fn col(&self) -> usize {
-        let inner = self.inner.borrow();
-        inner.col
+        self.line_col(self.offset).1
     }
 
-    fn set_line(&mut self, value: usize) {
-        let mut inner = self.inner.borrow_mut();
-        inner.line = value;
+    /// Returns the line and column numbers of the character at the byte offset `pos`.
+    pub fn line_col(&self, pos: usize) -> (usize, usize) {
+        let lines = self.lines.get_or_init(|| {
+            let mut lines = vec![0];
+            lines.extend(
+                self.contents
+                    .bytes()
+                    .enumerate()
+                    .filter(|&(_, byte)| byte == b'\n')
+                    .map(|(index, _)| index + 1),
+            );
+            lines
+        });
+
+        let line = lines.partition_point(|&start| start <= pos);
+        let col = self.contents[lines[line - 1]..pos].chars().count() + 1;
+
+        (line, col)
     }
 
-    fn set_col(&mut self, value: usize) {
-        let mut inner = self.inner.borrow_mut();
-        inner.col = value;
+    /// Returns the full contents of the stream, regardless of position.
+    pub fn contents(&self) -> &str {
+        &self.contents
+    }
+
+    /// Returns the contents of the stream from the next character onwards.
+    #[inline]
+    pub fn rest(&self) -> &str {
+        &self.contents[self.offset..]
+    }
+
+    /// Returns the contents between the byte offsets `start` and `end`.
+    #[inline]
+    pub fn slice(&self, start: usize, end: usize) -> &str {
+        &self.contents[start..end]
     }
 }
 
 impl Iterator for CharStream {
     type Item = char;
 
+    #[inline]
     fn next(&mut self) -> Option<Self::Item> {
-        let opt = {
-            let mut inner = self.inner.borrow_mut();
-            inner.stream.next()
-        };
-
-        match opt {
-            Some(ch) => {
-                if ch == '\n' {
-                    let line = self.line();
-                    self.set_line(line + 1);
-                    self.set_col(1);
-                } else {
-                    let col = self.col();
-                    self.set_col(col + 1);
-                }
-                Some(ch)
-            }
-            None => None,
-        }
+        let ch = self.peek()?;
+        self.offset += ch.len_utf8();
+
+        Some(ch)
     }
 }
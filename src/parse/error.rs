@@ -2,7 +2,7 @@
 
 #![allow(missing_docs)]
 
-use super::{BinaryOp, ParseResult, UnaryOp, MAX_DEPTH};
+use super::{Arity, BinaryOp, ParseResult, UnaryOp, MAX_DEPTH};
 use crate::{types::Type, OverError};
 use num_bigint::{BigInt, ParseBigIntError};
 use std::{error::Error, fmt, io, num::ParseIntError};
@@ -18,7 +18,14 @@ pub enum ParseErrorKind {
     CyclicInclude(String, usize, usize),
     DuplicateField(String, usize, usize),
     DuplicateGlobal(String, usize, usize),
+    EnvNotAllowed(usize, usize),
+    EnvVarInvalid(String, String, Type, usize, usize),
+    EnvVarNotFound(String, usize, usize),
     ExpectedType(Type, Type, usize, usize),
+    FunctionArgType(String, Vec<Type>, Type, usize, usize),
+    FunctionArity(String, Arity, usize, usize, usize),
+    FunctionError(String, String, usize, usize),
+    FunctionNotFound(String, usize, usize),
     GlobalNotFound(String, usize, usize),
     InvalidIndex(BigInt, usize, usize),
     InvalidClosingBracket(Option<char>, char, usize, usize),
@@ -37,7 +44,7 @@ pub enum ParseErrorKind {
     VariableNotFound(String, usize, usize),
 
     IoError(String),
-    OverError(String),
+    OverError(String, usize, usize),
     ParseIntError(String),
 }
 
@@ -45,20 +52,26 @@ pub enum ParseErrorKind {
 #[derive(Debug)]
 pub struct ParseError {
     /// The file this error occurred in.
-    pub file: Option<String>,
+    pub(crate) file: Option<String>,
     /// Error kind.
-    pub kind: ParseErrorKind,
+    pub(crate) kind: ParseErrorKind,
 }
 
 impl fmt::Display for ParseError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        use self::ParseErrorKind::*;
-
-        if let Some(ref file) = (*self).file {
+        if let Some(ref file) = self.file {
             write!(f, "{}: ", file)?;
         }
 
-        match (*self).kind {
+        write!(f, "{}", self.kind)
+    }
+}
+
+impl fmt::Display for ParseErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::ParseErrorKind::*;
+
+        match *self {
             BinaryOperatorError(ref expected, ref found, ref op, ref line, ref col) => write!(
                 f,
                 "Could not apply operator {} on types {} and {} at line {}, column {}",
@@ -79,11 +92,63 @@ impl fmt::Display for ParseError {
                 "Duplicate global \"{}\" at line {}, column {}",
                 field, line, col
             ),
+            EnvNotAllowed(ref line, ref col) => write!(
+                f,
+                "Environment variable access at line {}, column {} is not allowed; enable it with \
+                 the `allow_env` parse option",
+                line, col
+            ),
+            EnvVarInvalid(ref var, ref value, ref t, ref line, ref col) => write!(
+                f,
+                "Environment variable \"{}\" at line {}, column {} has value \"{}\", which is not \
+                 a valid {}",
+                var, line, col, value, t
+            ),
+            EnvVarNotFound(ref var, ref line, ref col) => write!(
+                f,
+                "Environment variable \"{}\" at line {}, column {} is not set and has no default",
+                var, line, col
+            ),
             ExpectedType(ref expected, ref found, ref line, ref col) => write!(
                 f,
                 "Expected {} at line {}, column {}; found {}",
                 expected, line, col, found
             ),
+            FunctionArgType(ref name, ref expected, ref found, ref line, ref col) => write!(
+                f,
+                "Function \"{}\" expected {} at line {}, column {}; found {}",
+                name,
+                match expected.split_last() {
+                    Some((last, [])) => format!("{}", last),
+                    Some((last, rest)) => format!(
+                        "{} or {}",
+                        rest.iter()
+                            .map(|t| format!("{}", t))
+                            .collect::<Vec<_>>()
+                            .join(", "),
+                        last
+                    ),
+                    None => String::from("nothing"),
+                },
+                line,
+                col,
+                found
+            ),
+            FunctionArity(ref name, ref arity, ref found, ref line, ref col) => write!(
+                f,
+                "Function \"{}\" at line {}, column {} takes {}; found {}",
+                name, line, col, arity, found
+            ),
+            FunctionError(ref name, ref error, ref line, ref col) => write!(
+                f,
+                "Error calling function \"{}\" at line {}, column {}: {}",
+                name, line, col, error
+            ),
+            FunctionNotFound(ref name, ref line, ref col) => write!(
+                f,
+                "Function \"{}\" at line {}, column {} could not be found",
+                name, line, col
+            ),
             GlobalNotFound(ref var, ref line, ref col) => write!(
                 f,
                 "Global \"{}\" at line {}, column {} could not be found",
@@ -168,9 +233,11 @@ impl fmt::Display for ParseError {
                 var, line, col
             ),
 
-            IoError(ref error) | OverError(ref error) | ParseIntError(ref error) => {
-                write!(f, "{}", error)
+            OverError(ref error, ref line, ref col) => {
+                write!(f, "{} at line {}, col {}", error, line, col)
             }
+
+            IoError(ref error) | ParseIntError(ref error) => write!(f, "{}", error),
         }
     }
 }
@@ -182,7 +249,66 @@ impl ParseError {
     pub fn from_over(e: &OverError, file: Option<String>, line: usize, col: usize) -> Self {
         Self {
             file,
-            kind: ParseErrorKind::OverError(format!("{} at line {}, col {}", e, line, col)),
+            kind: ParseErrorKind::OverError(format!("{}", e), line, col),
+        }
+    }
+
+    /// Returns the file this error occurred in, if any. This may be a file included by the file
+    /// being parsed.
+    pub fn file(&self) -> Option<&str> {
+        self.file.as_deref()
+    }
+
+    /// Returns the line this error occurred at, if known.
+    pub fn line(&self) -> Option<usize> {
+        self.line_col().map(|(line, _)| line)
+    }
+
+    /// Returns the column this error occurred at, if known. Only the line is known for unexpected
+    /// ends.
+    pub fn col(&self) -> Option<usize> {
+        self.line_col().and_then(|(_, col)| col)
+    }
+
+    /// Returns the error message without the file.
+    pub fn message(&self) -> String {
+        self.kind.to_string()
+    }
+
+    fn line_col(&self) -> Option<(usize, Option<usize>)> {
+        use self::ParseErrorKind::*;
+
+        match self.kind {
+            BinaryOperatorError(_, _, _, line, col)
+            | CyclicInclude(_, line, col)
+            | DuplicateField(_, line, col)
+            | DuplicateGlobal(_, line, col)
+            | EnvNotAllowed(line, col)
+            | EnvVarInvalid(_, _, _, line, col)
+            | EnvVarNotFound(_, line, col)
+            | ExpectedType(_, _, line, col)
+            | FunctionArgType(_, _, _, line, col)
+            | FunctionArity(_, _, _, line, col)
+            | FunctionError(_, _, line, col)
+            | FunctionNotFound(_, line, col)
+            | GlobalNotFound(_, line, col)
+            | InvalidIndex(_, line, col)
+            | InvalidClosingBracket(_, _, line, col)
+            | InvalidDot(_, line, col)
+            | InvalidEscapeChar(_, line, col)
+            | InvalidFieldChar(_, line, col)
+            | InvalidFieldName(_, line, col)
+            | InvalidIncludePath(_, line, col)
+            | InvalidIncludeToken(_, line, col)
+            | InvalidNumeric(line, col)
+            | InvalidValue(_, line, col)
+            | InvalidValueChar(_, line, col)
+            | MaxDepth(line, col)
+            | UnaryOperatorError(_, _, line, col)
+            | VariableNotFound(_, line, col)
+            | OverError(_, line, col) => Some((line, Some(col))),
+            UnexpectedEnd(line) => Some((line, None)),
+            IoError(_) | ParseIntError(_) => None,
         }
     }
 }
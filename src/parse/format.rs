@@ -1,9 +1,16 @@
 //! Module containing functions for formatting output of objects.
 
-use crate::{arr::Arr, obj::Obj, tup::Tup, value::Value, INDENT_STEP};
+use crate::{
+    arr::Arr,
+    obj::Obj,
+    tup::Tup,
+    types::Type,
+    value::{Rounding, Value},
+    INDENT_STEP,
+};
 use num_bigint::BigInt;
 use num_rational::BigRational;
-use num_traits::One;
+use num_traits::{pow, One, Signed, Zero};
 
 // Returns a `String` with the given amount of spaces.
 fn indent(amount: usize) -> String {
@@ -41,18 +48,109 @@ pub trait Format {
     fn format(&self, full: bool, indent_amt: usize) -> String;
 }
 
+/// Writes terminating fractions as decimals, such as `133.7` or `5.0`, and other fractions as
+/// `a/b` or as mixed numbers such as `-5-1/3`.
 impl Format for BigRational {
     fn format(&self, _full: bool, _indent_amt: usize) -> String {
-        let frac_fmt = format!("{}", *self);
+        if let Some(scale) = decimal_scale(self.denom()) {
+            // Always write a decimal point so that the number is read back as a `Frac`.
+            let scale = scale.max(1);
+            return decimal_string(&(self * pow(ten(), scale)).to_integer(), scale);
+        }
 
-        if *self.denom() == BigInt::one() {
-            format!("{}.0", frac_fmt)
+        let whole = self.trunc().to_integer();
+        if whole.is_zero() {
+            format!("{}", *self)
         } else {
-            frac_fmt
+            let rem = (self - self.trunc()).abs();
+            let sign = if self.is_negative() { '-' } else { '+' };
+            format!("{}{}{}", whole, sign, rem)
         }
     }
 }
 
+fn ten() -> BigRational {
+    BigRational::from_integer(10.into())
+}
+
+// Returns the number of decimal places needed to write a fraction with the denominator `denom`,
+// or `None` if the decimal does not terminate.
+fn decimal_scale(denom: &BigInt) -> Option<usize> {
+    let two = BigInt::from(2);
+    let five = BigInt::from(5);
+    let mut denom = denom.clone();
+    let (mut twos, mut fives) = (0, 0);
+
+    while (&denom % &two).is_zero() {
+        denom /= &two;
+        twos += 1;
+    }
+    while (&denom % &five).is_zero() {
+        denom /= &five;
+        fives += 1;
+    }
+
+    if denom.is_one() {
+        Some(twos.max(fives))
+    } else {
+        None
+    }
+}
+
+// Writes `int / 10^scale` as a decimal with exactly `scale` decimal places.
+fn decimal_string(int: &BigInt, scale: usize) -> String {
+    let digits = int.abs().to_string();
+    let digits = format!("{:0>width$}", digits, width = scale + 1);
+    let (whole, dec) = digits.split_at(digits.len() - scale);
+    let sign = if int.is_negative() { "-" } else { "" };
+
+    if scale == 0 {
+        format!("{}{}", sign, whole)
+    } else {
+        format!("{}{}.{}", sign, whole, dec)
+    }
+}
+
+/// Rounds `frac` to `precision` decimal places using `rounding`.
+pub fn round_frac(frac: &BigRational, precision: usize, rounding: Rounding) -> BigRational {
+    let factor = pow(ten(), precision);
+    let scaled = frac * &factor;
+    let floor = scaled.floor();
+    let half = BigRational::new(1.into(), 2.into());
+
+    let round_up = match rounding {
+        Rounding::Floor => false,
+        Rounding::Ceiling => !scaled.is_integer(),
+        Rounding::Down => !scaled.is_integer() && scaled.is_negative(),
+        Rounding::Up => !scaled.is_integer() && scaled.is_positive(),
+        Rounding::HalfUp | Rounding::HalfEven => {
+            let rem = &scaled - &floor;
+            if rem == half {
+                match rounding {
+                    Rounding::HalfUp => scaled.is_positive(),
+                    _ => !(floor.to_integer() % BigInt::from(2)).is_zero(),
+                }
+            } else {
+                rem > half
+            }
+        }
+    };
+
+    let rounded = if round_up {
+        floor + BigRational::one()
+    } else {
+        floor
+    };
+    rounded / factor
+}
+
+/// Writes `frac` as a decimal with exactly `precision` decimal places, rounding it with
+/// `rounding`. A `precision` of `0` gives an integer with no decimal point.
+pub fn format_decimal(frac: &BigRational, precision: usize, rounding: Rounding) -> String {
+    let rounded = round_frac(frac, precision, rounding);
+    decimal_string(&(rounded * pow(ten(), precision)).to_integer(), precision)
+}
+
 impl Format for String {
     fn format(&self, _full: bool, _indent_amt: usize) -> String {
         format!("\"{}\"", replace_all(self))
@@ -187,12 +285,22 @@ impl Format for Obj {
                 String::new()
             };
 
-            if let Some(parent) = self.get_parent() {
-                s.push_str(&format!(
+            match self.parents_ref().as_slice() {
+                [] => (),
+                [parent] => s.push_str(&format!(
                     "{}^: {}\n",
                     indent(indent_amt),
                     parent.format(true, indent_amt + INDENT_STEP)
-                ));
+                )),
+                parents => {
+                    let values = parents.iter().cloned().map(Value::Obj).collect();
+                    let arr = Arr::from_values_unchecked(values, Type::Obj);
+                    s.push_str(&format!(
+                        "{}^: {}\n",
+                        indent(indent_amt),
+                        arr.format(true, indent_amt + INDENT_STEP)
+                    ));
+                }
             }
 
             self.with_each(|field, value| {
@@ -1,15 +1,17 @@
 //! Functions for loading/writing Objs.
 
+pub mod cache;
 pub mod error;
 pub mod format;
 pub mod util;
 
+mod builtins;
 mod char_stream;
 mod parser;
 
-use self::error::ParseError;
-use crate::Obj;
-use std::fmt;
+use self::{cache::IncludeCache, error::ParseError};
+use crate::{context::ParseContext, value::Value, Obj};
+use std::{fmt, sync::Arc};
 
 type ParseResult<T> = Result<T, ParseError>;
 
@@ -17,12 +19,69 @@ const MAX_DEPTH: usize = 64;
 
 /// Load an `Obj` from a file.
 pub fn load_from_file(path: &str) -> ParseResult<Obj> {
-    parser::parse_obj_file(path)
+    parser::parse_obj_file(path, &ParseContext::new())
 }
 
 /// Load an `Obj` from a &str.
 pub fn load_from_str(contents: &str) -> ParseResult<Obj> {
-    parser::parse_obj_str(contents)
+    parser::parse_obj_str(contents, &ParseContext::new())
+}
+
+/// Load an `Obj` from a &str which holds the contents of the file at `path`, if any. Includes are
+/// resolved relative to `path`.
+pub fn load_from_str_in_file(contents: &str, path: Option<&str>) -> ParseResult<Obj> {
+    parser::parse_obj_str_in_file(contents, path, &ParseContext::new())
+}
+
+/// Load an `Obj` from a file, with the globals and functions in `ctx` available.
+pub fn load_from_file_with_context(path: &str, ctx: &ParseContext) -> ParseResult<Obj> {
+    parser::parse_obj_file(path, ctx)
+}
+
+/// Load an `Obj` from a &str, with the globals and functions in `ctx` available.
+pub fn load_from_str_with_context(contents: &str, ctx: &ParseContext) -> ParseResult<Obj> {
+    parser::parse_obj_str(contents, ctx)
+}
+
+/// Load an `Obj` from a file, with the globals and functions in `ctx` available, together with the
+/// full paths of every file it depends on.
+pub fn load_from_file_with_deps(path: &str, ctx: &ParseContext) -> ParseResult<(Obj, Vec<String>)> {
+    parser::parse_obj_file_with_deps(path, ctx)
+}
+
+/// Like `load_from_file_with_deps`, but stores the dependencies in `deps` even if loading fails,
+/// in which case they are the files reached before the error.
+pub fn load_from_file_tracking_deps(
+    path: &str,
+    ctx: &ParseContext,
+    deps: &mut Vec<String>,
+) -> ParseResult<Obj> {
+    parser::parse_obj_file_tracking_deps(path, ctx, deps)
+}
+
+/// Load an `Obj` from a file, with the globals and functions in `ctx` available, reusing and
+/// updating the parsed includes in `cache`.
+pub fn load_from_file_cached(
+    path: &str,
+    ctx: &ParseContext,
+    cache: &Arc<IncludeCache>,
+) -> ParseResult<Obj> {
+    parser::parse_obj_file_cached(path, ctx, cache)
+}
+
+/// Load an `Obj` from a &str, with the globals and functions in `ctx` available, reusing and
+/// updating the parsed includes in `cache`.
+pub fn load_from_str_cached(
+    contents: &str,
+    ctx: &ParseContext,
+    cache: &Arc<IncludeCache>,
+) -> ParseResult<Obj> {
+    parser::parse_obj_str_cached(contents, ctx, cache)
+}
+
+/// Load a single `Value` from a &str.
+pub fn load_value_from_str(contents: &str) -> ParseResult<Value> {
+    parser::parse_value_str(contents, &ParseContext::new())
 }
 
 #[derive(Debug, PartialEq)]
@@ -55,18 +114,12 @@ pub enum BinaryOp {
 
 impl BinaryOp {
     pub fn is_priority(&self) -> bool {
-        match *self {
-            Self::Mult | Self::Div | Self::Mod => true,
-            _ => false,
-        }
+        matches!(*self, Self::Mult | Self::Div | Self::Mod)
     }
 
     /// Is this a binary operator?
     pub fn is_op(ch: char) -> bool {
-        match ch {
-            '+' | '-' | '*' | '/' | '%' => true,
-            _ => false,
-        }
+        matches!(ch, '+' | '-' | '*' | '/' | '%')
     }
 
     pub fn get_op(ch: char) -> Option<Self> {
@@ -96,3 +149,43 @@ impl fmt::Display for BinaryOp {
         )
     }
 }
+
+/// The number of arguments a function accepts.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Arity {
+    Exactly(usize),
+    AtLeast(usize),
+    Between(usize, usize),
+}
+
+impl Arity {
+    /// Returns true if a function with this arity can be called with `num` arguments.
+    pub fn accepts(self, num: usize) -> bool {
+        match self {
+            Self::Exactly(n) => num == n,
+            Self::AtLeast(n) => num >= n,
+            Self::Between(lo, hi) => num >= lo && num <= hi,
+        }
+    }
+}
+
+impl fmt::Display for Arity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let plural = |n| if n == 1 { "argument" } else { "arguments" };
+
+        match *self {
+            Self::Exactly(n) => write!(f, "{} {}", n, plural(n)),
+            Self::AtLeast(n) => write!(f, "at least {} {}", n, plural(n)),
+            Self::Between(lo, hi) => write!(f, "{} to {} {}", lo, hi, plural(hi)),
+        }
+    }
+}
+
+/// The type of value an include produces, e.g. `<Arr "file.over">`.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub enum IncludeType {
+    Obj,
+    Str,
+    Arr,
+    Tup,
+}
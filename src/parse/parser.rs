@@ -3,31 +3,102 @@
 #![allow(clippy::too_many_arguments)]
 
 use super::{
+    builtins::{get_builtin, CallError},
+    cache::{stamp, IncludeCache, Stamps},
     char_stream::CharStream,
     error::{parse_err, ParseError, ParseErrorKind::*},
     util::*,
-    BinaryOp, ParseResult, UnaryOp, MAX_DEPTH,
+    Arity, BinaryOp, IncludeType, ParseResult, UnaryOp, MAX_DEPTH,
 };
 use crate::{
     arr::{self, Arr},
-    obj::{Obj, Pair},
+    context::ParseContext,
+    obj::{Obj, Pair, PairMap},
     tup::Tup,
     types::Type,
     value::Value,
     ReferenceType,
 };
-use num_bigint::BigInt;
 use num_rational::BigRational;
 use num_traits::{ToPrimitive, Zero};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 use std::{
     collections::{HashMap, HashSet, VecDeque},
+    env::VarError,
     ops::Deref,
     path::Path,
+    sync::Arc,
 };
 
-type Pairs = Vec<Pair>;
+type Pairs = PairMap;
 type GlobalMap = HashMap<String, Value>;
-type IncludedMap = (HashMap<String, Value>, HashSet<String>);
+
+// Includes encountered while parsing a file, shared with every file it includes.
+#[derive(Default)]
+struct IncludedMap {
+    // Values of files already included, keyed by full path.
+    values: HashMap<String, Value>,
+    // Full paths of the files currently being parsed, used to detect cyclic includes.
+    parsing: HashSet<String>,
+    // Full paths of every include reached, including those that failed to parse.
+    reached: HashSet<String>,
+    // Includes parsed ahead of time on other threads, keyed by full path and include type.
+    #[cfg(feature = "parallel")]
+    prefetched: HashMap<(String, IncludeType), Prefetched>,
+    // Cache of includes shared between loads, if any.
+    cache: Option<Arc<IncludeCache>>,
+    // The files each file in `values` depends on. Only tracked when there is a cache.
+    deps: HashMap<String, Stamps>,
+    // The files depended on so far by each include currently being parsed, innermost last. Only
+    // tracked when there is a cache.
+    frames: Vec<Stamps>,
+}
+
+// An include parsed ahead of time, with the includes encountered while parsing it.
+#[cfg(feature = "parallel")]
+struct Prefetched {
+    value: Value,
+    values: HashMap<String, Value>,
+    deps: HashMap<String, Stamps>,
+}
+
+impl IncludedMap {
+    fn with_cache(cache: &Arc<IncludeCache>) -> Self {
+        Self {
+            cache: Some(cache.clone()),
+            ..Default::default()
+        }
+    }
+
+    // Records that the include currently being parsed depends on `path` and its dependencies.
+    fn add_dep(&mut self, path: &str) {
+        if let Some(frame) = self.frames.last_mut() {
+            if let Some(deps) = self.deps.get(path) {
+                frame.extend(deps.iter().map(|(path, stamp)| (path.clone(), *stamp)));
+            }
+        }
+    }
+
+    // Takes the value of the include with the given key if it was parsed ahead of time, keeping
+    // the includes encountered while parsing it.
+    #[cfg(feature = "parallel")]
+    fn take_prefetched(&mut self, key: &(String, IncludeType)) -> Option<Value> {
+        let prefetched = self.prefetched.remove(key)?;
+        for (path, value) in prefetched.values {
+            let _ = self.values.entry(path).or_insert(value);
+        }
+        self.deps.extend(prefetched.deps);
+        self.values.insert(key.0.clone(), prefetched.value.clone());
+
+        Some(prefetched.value)
+    }
+
+    #[cfg(not(feature = "parallel"))]
+    fn take_prefetched(&mut self, _key: &(String, IncludeType)) -> Option<Value> {
+        None
+    }
+}
 
 lazy_static! {
     // Objs that signify that an include keyword was encountered.
@@ -38,56 +109,178 @@ lazy_static! {
 }
 
 /// Parses given file as an `Obj`.
-pub fn parse_obj_file(path: &str) -> ParseResult<Obj> {
+pub fn parse_obj_file(path: &str, ctx: &ParseContext) -> ParseResult<Obj> {
     let stream = CharStream::from_file(path)?;
-    parse_obj_stream(stream, &mut (Default::default(), Default::default()))
+    parse_obj_stream(stream, &mut Default::default(), ctx)
+}
+
+/// Parses given file as an `Obj`, also returning the full paths of the file and of every file it
+/// includes, directly or indirectly, in sorted order.
+pub fn parse_obj_file_with_deps(path: &str, ctx: &ParseContext) -> ParseResult<(Obj, Vec<String>)> {
+    let mut deps = Vec::new();
+    let obj = parse_obj_file_tracking_deps(path, ctx, &mut deps)?;
+
+    Ok((obj, deps))
+}
+
+/// Like `parse_obj_file_with_deps`, but stores the dependencies in `deps` even if parsing fails,
+/// in which case they are the files reached before the error.
+pub fn parse_obj_file_tracking_deps(
+    path: &str,
+    ctx: &ParseContext,
+    deps: &mut Vec<String>,
+) -> ParseResult<Obj> {
+    let mut included = IncludedMap::default();
+    let result = CharStream::from_file(path)
+        .map_err(ParseError::from)
+        .and_then(|stream| parse_obj_stream(stream, &mut included, ctx));
+
+    deps.clear();
+    deps.extend(included.values.into_keys());
+    deps.extend(included.reached);
+    if let Some(full_path) = Path::new(path)
+        .canonicalize()
+        .ok()
+        .as_ref()
+        .and_then(|path| path.to_str())
+    {
+        deps.push(full_path.into());
+    }
+    deps.sort();
+    deps.dedup();
+
+    result
 }
 
 // Parses given file as an `Obj`, keeping track of already encountered includes.
-fn parse_obj_file_includes(path: &str, included: &mut IncludedMap) -> ParseResult<Obj> {
+fn parse_obj_file_includes(
+    path: &str,
+    included: &mut IncludedMap,
+    ctx: &ParseContext,
+) -> ParseResult<Obj> {
     let stream = CharStream::from_file(path)?;
-    parse_obj_stream(stream, included)
+    parse_obj_stream(stream, included, ctx)
 }
 
 /// Parses given &str as an `Obj`.
-pub fn parse_obj_str(contents: &str) -> ParseResult<Obj> {
+pub fn parse_obj_str(contents: &str, ctx: &ParseContext) -> ParseResult<Obj> {
     let contents = String::from(contents);
     let stream = CharStream::from_string(contents)?;
-    parse_obj_stream(stream, &mut (Default::default(), Default::default()))
+    parse_obj_stream(stream, &mut Default::default(), ctx)
+}
+
+/// Parses given &str as an `Obj`, resolving includes and reporting errors as if it were the
+/// contents of the file at `path`.
+pub fn parse_obj_str_in_file(
+    contents: &str,
+    path: Option<&str>,
+    ctx: &ParseContext,
+) -> ParseResult<Obj> {
+    let contents = String::from(contents);
+    let stream = match path {
+        Some(path) => CharStream::from_string_in_file(path, contents)?,
+        None => CharStream::from_string(contents)?,
+    };
+    parse_obj_stream(stream, &mut Default::default(), ctx)
+}
+
+/// Parses given file as an `Obj`, reusing and updating the parsed includes in `cache`.
+pub fn parse_obj_file_cached(
+    path: &str,
+    ctx: &ParseContext,
+    cache: &Arc<IncludeCache>,
+) -> ParseResult<Obj> {
+    let stream = CharStream::from_file(path)?;
+    parse_obj_stream(stream, &mut IncludedMap::with_cache(cache), ctx)
+}
+
+/// Parses given &str as an `Obj`, reusing and updating the parsed includes in `cache`.
+pub fn parse_obj_str_cached(
+    contents: &str,
+    ctx: &ParseContext,
+    cache: &Arc<IncludeCache>,
+) -> ParseResult<Obj> {
+    let contents = String::from(contents);
+    let stream = CharStream::from_string(contents)?;
+    parse_obj_stream(stream, &mut IncludedMap::with_cache(cache), ctx)
+}
+
+/// Parses given &str as a single `Value`.
+pub fn parse_value_str(contents: &str, ctx: &ParseContext) -> ParseResult<Value> {
+    let contents = String::from(contents);
+    let mut stream = CharStream::from_string(contents)?;
+
+    // Go to the first non-whitespace character, or error if there is none.
+    if !find_char(&mut stream) {
+        return parse_err(stream.file(), UnexpectedEnd(stream.line()));
+    }
+
+    let mut globals: GlobalMap = ctx.globals().clone();
+    let pos = stream.pos();
+    let value = parse_value(
+        &mut stream,
+        &Default::default(),
+        &mut globals,
+        &mut Default::default(),
+        ctx,
+        pos,
+        1,
+        None,
+        true,
+    )?;
+
+    // Only whitespace and comments may follow the value.
+    if find_char(&mut stream) {
+        let ch = stream.peek().unwrap();
+        return parse_err(
+            stream.file(),
+            InvalidValueChar(ch, stream.line(), stream.col()),
+        );
+    }
+
+    Ok(value)
 }
 
 // Parses an Obj given a character stream.
 #[inline]
-fn parse_obj_stream(mut stream: CharStream, mut included: &mut IncludedMap) -> ParseResult<Obj> {
+fn parse_obj_stream(
+    mut stream: CharStream,
+    included: &mut IncludedMap,
+    ctx: &ParseContext,
+) -> ParseResult<Obj> {
     let mut obj_pairs: Pairs = Default::default();
 
     // Go to the first non-whitespace character, or return if there is none.
-    if !find_char(stream.clone()) {
-        return Ok(Obj::from_pairs_unchecked(obj_pairs, None));
+    if !find_char(&mut stream) {
+        return Ok(Obj::from_pair_map(obj_pairs, vec![]));
     }
 
-    let mut globals: GlobalMap = Default::default();
-    let mut parent = None;
+    prefetch_includes(&stream, included, ctx);
+
+    let mut globals: GlobalMap = ctx.globals().clone();
+    let mut parents = vec![];
 
     // Parse all field/value pairs for this Obj.
     while parse_field_value_pair(
         &mut stream,
         &mut obj_pairs,
         &mut globals,
-        &mut included,
-        &mut parent,
+        included,
+        ctx,
+        &mut parents,
         1,
         None,
     )? {}
 
-    Ok(Obj::from_pairs_unchecked(obj_pairs, parent))
+    Ok(Obj::from_pair_map(obj_pairs, parents))
 }
 
 // Parses a sub-Obj in a file. It *must* start with { and end with }.
 fn parse_obj(
-    mut stream: &mut CharStream,
+    stream: &mut CharStream,
     globals: &mut GlobalMap,
-    mut included: &mut IncludedMap,
+    included: &mut IncludedMap,
+    ctx: &ParseContext,
     depth: usize,
 ) -> ParseResult<Value> {
     // Check depth.
@@ -100,36 +293,38 @@ fn parse_obj(
     assert_eq!(ch, '{');
 
     // Go to the first non-whitespace character, or error if there is none.
-    if !find_char(stream.clone()) {
+    if !find_char(stream) {
         return parse_err(stream.file(), UnexpectedEnd(stream.line()));
     }
 
     let mut obj_pairs: Pairs = Default::default();
-    let mut parent = None;
+    let mut parents = vec![];
 
     // Parse field/value pairs.
     while parse_field_value_pair(
-        &mut stream,
+        stream,
         &mut obj_pairs,
         globals,
-        &mut included,
-        &mut parent,
+        included,
+        ctx,
+        &mut parents,
         depth,
         Some('}'),
     )? {}
 
-    let obj = Obj::from_pairs_unchecked(obj_pairs, parent);
+    let obj = Obj::from_pair_map(obj_pairs, parents);
     Ok(obj.into())
 }
 
 // Parses a field/value pair.
 #[inline]
 fn parse_field_value_pair(
-    mut stream: &mut CharStream,
+    stream: &mut CharStream,
     obj_pairs: &mut Pairs,
-    mut globals: &mut GlobalMap,
-    mut included: &mut IncludedMap,
-    parent: &mut Option<Obj>,
+    globals: &mut GlobalMap,
+    included: &mut IncludedMap,
+    ctx: &ParseContext,
+    parents: &mut Vec<Obj>,
     depth: usize,
     cur_brace: Option<char>,
 ) -> ParseResult<bool> {
@@ -145,60 +340,38 @@ fn parse_field_value_pair(
         );
     }
 
-    // Get the field line/col.
-    let (field_line, field_col) = (stream.line(), stream.col());
+    // Get the field position.
+    let field_pos = stream.pos();
 
     // Parse field.
-    let (field_name, field_type) = parse_field(stream.clone(), field_line, field_col)?;
+    let (field_name, field_type) = parse_field(stream, field_pos)?;
 
     // Check for errors.
     match field_type {
         FieldType::Global => {
             if globals.contains_key(&field_name) {
-                return parse_err(
-                    stream.file(),
-                    DuplicateGlobal(field_name, field_line, field_col),
-                );
-            }
-        }
-        FieldType::Parent => {
-            if parent.is_some() {
-                return parse_err(
-                    stream.file(),
-                    DuplicateField("^".into(), field_line, field_col),
-                );
+                let (line, col) = stream.line_col(field_pos);
+                return parse_err(stream.file(), DuplicateGlobal(field_name, line, col));
             }
         }
+        FieldType::Parent => (),
         FieldType::Regular => {
-            if obj_pairs
-                .iter()
-                .any(|Pair(ref field, _)| *field == field_name)
-            {
-                return parse_err(
-                    stream.file(),
-                    DuplicateField(field_name, field_line, field_col),
-                );
+            if obj_pairs.contains(&field_name) {
+                let (line, col) = stream.line_col(field_pos);
+                return parse_err(stream.file(), DuplicateField(field_name, line, col));
             }
         }
     }
 
     // Deal with extra whitespace between field and value.
-    if !find_char(stream.clone()) {
+    if !find_char(stream) {
         return parse_err(stream.file(), UnexpectedEnd(stream.line()));
     }
 
     // At a non-whitespace character, parse value.
-    let (value_line, value_col) = (stream.line(), stream.col());
+    let value_pos = stream.pos();
     let value = parse_value(
-        &mut stream,
-        obj_pairs,
-        &mut globals,
-        &mut included,
-        value_line,
-        value_col,
-        depth,
-        cur_brace,
-        true,
+        stream, obj_pairs, globals, included, ctx, value_pos, depth, cur_brace, true,
     )?;
 
     // Add value either to the globals map or to the current Obj.
@@ -207,10 +380,19 @@ fn parse_field_value_pair(
             let _ = globals.insert(field_name, value);
         }
         FieldType::Parent => {
-            let par = value
-                .get_obj()
-                .map_err(|e| ParseError::from_over(&e, stream.file(), value_line, value_col))?;
-            *parent = Some(par);
+            // A parent is either a single Obj or an Arr of Objs.
+            let pars = Obj::parents_from_values(vec![value]).map_err(|e| {
+                let (line, col) = stream.line_col(value_pos);
+                ParseError::from_over(&e, stream.file(), line, col)
+            })?;
+
+            for par in pars {
+                if parents.iter().any(|p| p.ptr_eq(&par)) {
+                    let (line, col) = stream.line_col(field_pos);
+                    return parse_err(stream.file(), DuplicateField("^".into(), line, col));
+                }
+                parents.push(par);
+            }
         }
         FieldType::Regular => {
             obj_pairs.push(Pair(field_name, value));
@@ -218,7 +400,7 @@ fn parse_field_value_pair(
     }
 
     // Go to the next non-whitespace character.
-    if !find_char(stream.clone()) {
+    if !find_char(stream) {
         match cur_brace {
             Some(_) => return parse_err(stream.file(), UnexpectedEnd(stream.line())),
             None => return Ok(false),
@@ -229,11 +411,12 @@ fn parse_field_value_pair(
 }
 
 // Parses an Arr given a file.
-fn parse_arr_file(path: &str, mut included: &mut IncludedMap) -> ParseResult<Arr> {
+fn parse_arr_file(path: &str, included: &mut IncludedMap, ctx: &ParseContext) -> ParseResult<Arr> {
     let mut stream = CharStream::from_file(path)?;
+    prefetch_includes(&stream, included, ctx);
 
     let obj_pairs: Pairs = Default::default();
-    let mut globals: GlobalMap = Default::default();
+    let mut globals: GlobalMap = ctx.globals().clone();
 
     let mut vec = vec![];
     let mut tcur = Type::Any;
@@ -241,19 +424,19 @@ fn parse_arr_file(path: &str, mut included: &mut IncludedMap) -> ParseResult<Arr
 
     loop {
         // Go to the first non-whitespace character, or error if there is none.
-        if !find_char(stream.clone()) {
+        if !find_char(&mut stream) {
             break;
         }
 
         // At a non-whitespace character, parse value.
-        let (value_line, value_col) = (stream.line(), stream.col());
+        let value_pos = stream.pos();
         let value = parse_value(
             &mut stream,
             &obj_pairs,
             &mut globals,
-            &mut included,
-            value_line,
-            value_col,
+            included,
+            ctx,
+            value_pos,
             1,
             None,
             true,
@@ -268,17 +451,13 @@ fn parse_arr_file(path: &str, mut included: &mut IncludedMap) -> ParseResult<Arr
                     has_any = any;
                 }
                 None => {
-                    return parse_err(
-                        stream.file(),
-                        ExpectedType(tcur, tnew, value_line, value_col),
-                    );
+                    let (line, col) = stream.line_col(value_pos);
+                    return parse_err(stream.file(), ExpectedType(tcur, tnew, line, col));
                 }
             }
         } else if tcur != tnew {
-            return parse_err(
-                stream.file(),
-                ExpectedType(tcur, tnew, value_line, value_col),
-            );
+            let (line, col) = stream.line_col(value_pos);
+            return parse_err(stream.file(), ExpectedType(tcur, tnew, line, col));
         }
 
         vec.push(value);
@@ -291,10 +470,11 @@ fn parse_arr_file(path: &str, mut included: &mut IncludedMap) -> ParseResult<Arr
 
 // Parses a sub-Arr in a file. It *must* start with [ and end with ].
 fn parse_arr(
-    mut stream: &mut CharStream,
-    obj_pairs: &[Pair],
-    mut globals: &mut GlobalMap,
-    mut included: &mut IncludedMap,
+    stream: &mut CharStream,
+    obj_pairs: &Pairs,
+    globals: &mut GlobalMap,
+    included: &mut IncludedMap,
+    ctx: &ParseContext,
     depth: usize,
 ) -> ParseResult<Value> {
     // Check depth.
@@ -312,7 +492,7 @@ fn parse_arr(
 
     loop {
         // Go to the first non-whitespace character, or error if there is none.
-        if !find_char(stream.clone()) {
+        if !find_char(stream) {
             return parse_err(stream.file(), UnexpectedEnd(stream.line()));
         }
 
@@ -328,14 +508,14 @@ fn parse_arr(
         }
 
         // At a non-whitespace character, parse value.
-        let (value_line, value_col) = (stream.line(), stream.col());
+        let value_pos = stream.pos();
         let value = parse_value(
-            &mut stream,
+            stream,
             obj_pairs,
-            &mut globals,
-            &mut included,
-            value_line,
-            value_col,
+            globals,
+            included,
+            ctx,
+            value_pos,
             depth,
             Some(']'),
             true,
@@ -350,17 +530,13 @@ fn parse_arr(
                     has_any = any;
                 }
                 None => {
-                    return parse_err(
-                        stream.file(),
-                        ExpectedType(tcur, tnew, value_line, value_col),
-                    );
+                    let (line, col) = stream.line_col(value_pos);
+                    return parse_err(stream.file(), ExpectedType(tcur, tnew, line, col));
                 }
             }
         } else if tcur != tnew {
-            return parse_err(
-                stream.file(),
-                ExpectedType(tcur, tnew, value_line, value_col),
-            );
+            let (line, col) = stream.line_col(value_pos);
+            return parse_err(stream.file(), ExpectedType(tcur, tnew, line, col));
         }
 
         vec.push(value);
@@ -372,28 +548,29 @@ fn parse_arr(
 }
 
 // Parses a Tup given a file.
-fn parse_tup_file(path: &str, mut included: &mut IncludedMap) -> ParseResult<Tup> {
+fn parse_tup_file(path: &str, included: &mut IncludedMap, ctx: &ParseContext) -> ParseResult<Tup> {
     let mut stream = CharStream::from_file(path)?;
+    prefetch_includes(&stream, included, ctx);
 
     let mut vec: Vec<Value> = Default::default();
     let obj_pairs: Pairs = Default::default();
-    let mut globals: GlobalMap = Default::default();
+    let mut globals: GlobalMap = ctx.globals().clone();
 
     loop {
         // Go to the first non-whitespace character, or error if there is none.
-        if !find_char(stream.clone()) {
+        if !find_char(&mut stream) {
             break;
         }
 
         // At a non-whitespace character, parse value.
-        let (value_line, value_col) = (stream.line(), stream.col());
+        let value_pos = stream.pos();
         let value = parse_value(
             &mut stream,
             &obj_pairs,
             &mut globals,
-            &mut included,
-            value_line,
-            value_col,
+            included,
+            ctx,
+            value_pos,
             1,
             None,
             true,
@@ -407,10 +584,11 @@ fn parse_tup_file(path: &str, mut included: &mut IncludedMap) -> ParseResult<Tup
 
 // Parses a sub-Tup in a file. It *must* start with ( and end with ).
 fn parse_tup(
-    mut stream: &mut CharStream,
-    obj_pairs: &[Pair],
-    mut globals: &mut GlobalMap,
-    mut included: &mut IncludedMap,
+    stream: &mut CharStream,
+    obj_pairs: &Pairs,
+    globals: &mut GlobalMap,
+    included: &mut IncludedMap,
+    ctx: &ParseContext,
     depth: usize,
 ) -> ParseResult<Value> {
     // Check depth.
@@ -426,7 +604,7 @@ fn parse_tup(
 
     loop {
         // Go to the first non-whitespace character, or error if there is none.
-        if !find_char(stream.clone()) {
+        if !find_char(stream) {
             return parse_err(stream.file(), UnexpectedEnd(stream.line()));
         }
 
@@ -442,14 +620,14 @@ fn parse_tup(
         }
 
         // At a non-whitespace character, parse value.
-        let (value_line, value_col) = (stream.line(), stream.col());
+        let value_pos = stream.pos();
         let value = parse_value(
-            &mut stream,
+            stream,
             obj_pairs,
-            &mut globals,
-            &mut included,
-            value_line,
-            value_col,
+            globals,
+            included,
+            ctx,
+            value_pos,
             depth,
             Some(')'),
             true,
@@ -470,30 +648,24 @@ enum FieldType {
     Regular,
 }
 
-// Gets the next field in the char stream.
-// Returns Option<(field_name, is_global, is_parent)>.
-fn parse_field(
-    mut stream: CharStream,
-    line: usize,
-    col: usize,
-) -> ParseResult<(String, FieldType)> {
-    let mut field = String::new();
+// Gets the next field in the char stream, which starts at `pos`.
+// Returns (field_name, field_type).
+fn parse_field(stream: &mut CharStream, pos: usize) -> ParseResult<(String, FieldType)> {
     let mut first = true;
     let mut is_global = false;
 
-    let ch = stream.peek().unwrap();
-    if ch == '@' {
-        let ch = stream.next().unwrap();
+    if stream.peek() == Some('@') {
+        let _ = stream.next();
         is_global = true;
-        field.push(ch);
     }
 
+    let mut end = stream.pos();
     while let Some(ch) = stream.next() {
         match ch {
             ':' if !first => {
                 break;
             }
-            ch if Obj::is_valid_field_char(ch, first) => field.push(ch),
+            ch if Obj::is_valid_field_char(ch, first) => end = stream.pos(),
             ch => {
                 return parse_err(
                     stream.file(),
@@ -505,17 +677,21 @@ fn parse_field(
         first = false;
     }
 
+    let field = stream.slice(pos, end);
+
     // Check for invalid field names.
-    match field.as_str() {
+    match field {
         _field_str if is_reserved(_field_str) => {
-            parse_err(stream.file(), InvalidFieldName(field.clone(), line, col))
+            let (line, col) = stream.line_col(pos);
+            parse_err(stream.file(), InvalidFieldName(field.into(), line, col))
         }
-        "^" => Ok((field.clone(), FieldType::Parent)),
+        "^" => Ok((field.into(), FieldType::Parent)),
         bad if bad.starts_with('^') => {
-            parse_err(stream.file(), InvalidFieldName(field.clone(), line, col))
+            let (line, col) = stream.line_col(pos);
+            parse_err(stream.file(), InvalidFieldName(field.into(), line, col))
         }
         _ => Ok((
-            field.clone(),
+            field.into(),
             if is_global {
                 FieldType::Global
             } else {
@@ -527,68 +703,64 @@ fn parse_field(
 
 // Gets the next value in the char stream.
 fn parse_value(
-    mut stream: &mut CharStream,
-    obj_pairs: &[Pair],
-    mut globals: &mut GlobalMap,
-    mut included: &mut IncludedMap,
-    line: usize,
-    col: usize,
+    stream: &mut CharStream,
+    obj_pairs: &Pairs,
+    globals: &mut GlobalMap,
+    included: &mut IncludedMap,
+    ctx: &ParseContext,
+    pos: usize,
     depth: usize,
     cur_brace: Option<char>,
     is_first: bool,
 ) -> ParseResult<Value> {
     // Peek to determine what kind of value we'll be parsing.
     let res = match stream.peek().unwrap() {
-        '"' => parse_str(&mut stream)?,
-        '{' => parse_obj(&mut stream, &mut globals, included, depth + 1)?,
-        '[' => parse_arr(&mut stream, obj_pairs, &mut globals, included, depth + 1)?,
-        '(' => parse_tup(&mut stream, obj_pairs, &mut globals, included, depth + 1)?,
-        '<' => parse_include(
-            &mut stream,
-            obj_pairs,
-            &mut globals,
-            &mut included,
-            depth + 1,
-        )?,
+        '"' => parse_str(stream)?,
+        '{' => parse_obj(stream, globals, included, ctx, depth + 1)?,
+        '[' => parse_arr(stream, obj_pairs, globals, included, ctx, depth + 1)?,
+        '(' => parse_tup(stream, obj_pairs, globals, included, ctx, depth + 1)?,
+        '<' => parse_include(stream, obj_pairs, globals, included, ctx, depth + 1)?,
         '+' => parse_unary_op(
-            &mut stream,
+            stream,
             obj_pairs,
             globals,
             included,
+            ctx,
             depth,
             cur_brace,
             UnaryOp::Plus,
         )?,
         '-' => parse_unary_op(
-            &mut stream,
+            stream,
             obj_pairs,
             globals,
             included,
+            ctx,
             depth,
             cur_brace,
             UnaryOp::Minus,
         )?,
-        ch if is_numeric_char(ch) => parse_numeric(&mut stream, line, col)?,
+        ch if is_numeric_char(ch) => parse_numeric(stream, pos)?,
         ch if Obj::is_valid_field_char(ch, true) || ch == '@' => parse_variable(
-            &mut stream,
-            obj_pairs,
-            globals,
-            included,
-            line,
-            col,
-            depth,
-            cur_brace,
+            stream, obj_pairs, globals, included, ctx, pos, depth, cur_brace,
         )?,
         ch => {
+            let (line, col) = stream.line_col(pos);
             return parse_err(stream.file(), InvalidValueChar(ch, line, col));
         }
     };
 
+    // Most values are not followed by an operator; skip setting up the operation queues.
+    if is_first && !stream.peek().is_some_and(BinaryOp::is_op) {
+        check_value_end(stream, cur_brace)?;
+        return Ok(res);
+    }
+
     // Process operations if this is the first value.
     if is_first {
-        let mut val_deque: VecDeque<(Value, usize, usize)> = VecDeque::new();
+        let mut val_deque: VecDeque<(Value, usize)> = VecDeque::new();
         let mut op_deque: VecDeque<BinaryOp> = VecDeque::new();
-        val_deque.push_back((res, line, col));
+        val_deque.push_back((res, pos));
 
         while let Some(ch) = stream.peek() {
             if let Some(op) = BinaryOp::get_op(ch) {
@@ -597,27 +769,19 @@ fn parse_value(
                     return parse_err(stream.file(), UnexpectedEnd(stream.line()));
                 }
 
-                let (line2, col2) = (stream.line(), stream.col());
+                let pos2 = stream.pos();
 
                 // Parse another value.
                 let val2 = parse_value(
-                    &mut stream,
-                    obj_pairs,
-                    &mut globals,
-                    &mut included,
-                    line2,
-                    col2,
-                    depth,
-                    cur_brace,
-                    false,
+                    stream, obj_pairs, globals, included, ctx, pos2, depth, cur_brace, false,
                 )?;
 
                 if op.is_priority() {
-                    let (val1, line1, col1) = val_deque.pop_back().unwrap();
-                    let res = binary_op_on_values(stream, val1, val2, op, line2, col2)?;
-                    val_deque.push_back((res, line1, col1));
+                    let (val1, pos1) = val_deque.pop_back().unwrap();
+                    let res = binary_op_on_values(stream, val1, val2, op, pos2)?;
+                    val_deque.push_back((res, pos1));
                 } else {
-                    val_deque.push_back((val2, line2, col2));
+                    val_deque.push_back((val2, pos2));
                     op_deque.push_back(op);
                 }
             } else {
@@ -629,17 +793,10 @@ fn parse_value(
         // Check for valid characters after the value.
         check_value_end(stream, cur_brace)?;
 
-        let (mut val1, ..) = val_deque.pop_front().unwrap();
+        let (mut val1, _) = val_deque.pop_front().unwrap();
         while !op_deque.is_empty() {
-            let (val2, line2, col2) = val_deque.pop_front().unwrap();
-            val1 = binary_op_on_values(
-                stream,
-                val1,
-                val2,
-                op_deque.pop_front().unwrap(),
-                line2,
-                col2,
-            )?;
+            let (val2, pos2) = val_deque.pop_front().unwrap();
+            val1 = binary_op_on_values(stream, val1, val2, op_deque.pop_front().unwrap(), pos2)?;
         }
         Ok(val1)
     } else {
@@ -648,10 +805,11 @@ fn parse_value(
 }
 
 fn parse_unary_op(
-    mut stream: &mut CharStream,
-    obj_pairs: &[Pair],
-    mut globals: &mut GlobalMap,
-    mut included: &mut IncludedMap,
+    stream: &mut CharStream,
+    obj_pairs: &Pairs,
+    globals: &mut GlobalMap,
+    included: &mut IncludedMap,
+    ctx: &ParseContext,
     depth: usize,
     cur_brace: Option<char>,
     op: UnaryOp,
@@ -661,197 +819,141 @@ fn parse_unary_op(
         (next == Some('-') && op == UnaryOp::Minus) || (next == Some('+') && op == UnaryOp::Plus)
     );
 
-    let line = stream.line();
-    let col = stream.col();
+    let pos = stream.pos();
 
     let res = match stream.peek() {
         Some(_) => parse_value(
-            &mut stream,
+            stream,
             obj_pairs,
-            &mut globals,
-            &mut included,
-            line,
-            col,
+            globals,
+            included,
+            ctx,
+            pos,
             depth + 1,
             cur_brace,
             false,
         )?,
-        None => return parse_err(stream.file(), UnexpectedEnd(line)),
+        None => return parse_err(stream.file(), UnexpectedEnd(stream.line())),
     };
-    unary_op_on_value(stream, res, op, line, col)
+    unary_op_on_value(stream, res, op, pos)
 }
 
-// Gets the next numeric (either Int or Frac) in the character stream.
-fn parse_numeric(stream: &mut CharStream, line: usize, col: usize) -> ParseResult<Value> {
-    let mut s1 = String::new();
-    let mut s2 = String::new();
-    let mut dec = false;
-    let mut under = false;
-
+// Gets the next numeric (either Int or Frac) in the character stream, which starts at `pos`.
+fn parse_numeric(stream: &mut CharStream, pos: usize) -> ParseResult<Value> {
     while let Some(ch) = stream.peek() {
-        match ch {
-            ch if is_value_end_char(ch) => break,
-            ch if is_digit(ch) => {
-                if !dec {
-                    s1.push(ch);
-                } else {
-                    s2.push(ch);
-                }
-            }
-            '.' | ',' => {
-                if !dec {
-                    dec = true;
-                } else {
-                    return parse_err(
-                        stream.file(),
-                        InvalidValueChar(ch, stream.line(), stream.col()),
-                    );
-                }
-            }
-            '_' => {
-                if !under {
-                    under = true;
-                } else {
-                    return parse_err(
-                        stream.file(),
-                        InvalidValueChar(ch, stream.line(), stream.col()),
-                    );
-                }
-            }
-            _ => {
-                return parse_err(
-                    stream.file(),
-                    InvalidValueChar(ch, stream.line(), stream.col()),
-                );
-            }
-        }
-
-        if ch != '_' {
-            under = false;
+        if is_value_end_char(ch) {
+            break;
         }
-
         let _ = stream.next();
     }
 
-    if dec {
-        // Parse a Frac from a number with a decimal.
-        if s1.is_empty() && s2.is_empty() {
-            return parse_err(stream.file(), InvalidNumeric(line, col));
+    match parse_unsigned_numeric(stream.slice(pos, stream.pos())) {
+        Ok(value) => Ok(value),
+        Err(NumericError::InvalidChar(ch, offset)) => {
+            let (line, col) = stream.line_col(pos + offset);
+            parse_err(stream.file(), InvalidValueChar(ch, line, col))
         }
-
-        let whole: BigInt = if s1.is_empty() {
-            0u8.into()
-        } else {
-            s1.parse()?
-        };
-
-        // Remove trailing zeros.
-        let s2 = s2.trim_end_matches('0');
-
-        let (decimal, dec_len): (BigInt, usize) = if s2.is_empty() {
-            (0u8.into(), 1)
-        } else {
-            (s2.parse()?, s2.len())
-        };
-
-        let f = frac_from_whole_and_dec(whole, decimal, dec_len);
-        Ok(f.into())
-    } else {
-        // Parse an Int.
-        if s1.is_empty() {
-            return parse_err(stream.file(), InvalidNumeric(line, col));
+        Err(NumericError::NoDigits) => {
+            let (line, col) = stream.line_col(pos);
+            parse_err(stream.file(), InvalidNumeric(line, col))
         }
-
-        let i: BigInt = s1.parse()?;
-        Ok(i.into())
+        Err(NumericError::Digits(e)) => Err(e.into()),
     }
 }
 
 // Parses a variable name and gets a value from the corresponding variable.
 fn parse_variable(
-    mut stream: &mut CharStream,
-    obj_pairs: &[Pair],
-    mut globals: &mut GlobalMap,
-    mut included: &mut IncludedMap,
-    line: usize,
-    col: usize,
+    stream: &mut CharStream,
+    obj_pairs: &Pairs,
+    globals: &mut GlobalMap,
+    included: &mut IncludedMap,
+    ctx: &ParseContext,
+    pos: usize,
     depth: usize,
     cur_brace: Option<char>,
 ) -> ParseResult<Value> {
-    let mut var = String::new();
+    let start = stream.pos();
     let mut is_global = false;
     let mut dot = false;
     let mut dot_global = false;
+    let mut call = false;
 
-    let ch = stream.peek().unwrap();
-    if ch == '@' {
-        let ch = stream.next().unwrap();
+    if stream.peek() == Some('@') {
+        let _ = stream.next();
         is_global = true;
-        var.push(ch);
     }
 
+    let mut end = stream.pos();
     while let Some(ch) = stream.peek() {
         match ch {
             '.' => {
-                let _ = stream.next();
-                match stream.peek() {
-                    Some('@') => dot_global = true,
-                    Some(ch) if Obj::is_valid_field_char(ch, true) || is_numeric_char(ch) => (),
-                    Some(ch) => {
-                        return parse_err(
-                            stream.file(),
-                            InvalidValueChar(ch, stream.line(), stream.col()),
-                        );
-                    }
-                    None => return parse_err(stream.file(), UnexpectedEnd(stream.line())),
-                }
-
+                dot_global = parse_dot(stream)?;
                 dot = true;
                 break;
             }
+            '(' if !is_global && end > start => {
+                call = true;
+                break;
+            }
             ch if is_value_end_char(ch) => break,
             ch if Obj::is_valid_field_char(ch, false) => {
                 let _ = stream.next();
-                var.push(ch);
+                end = stream.pos();
             }
             ch => {
-                return parse_err(
-                    stream.file(),
-                    InvalidValueChar(ch, stream.line(), stream.col()),
-                );
+                let (line, col) = stream.line_col(stream.pos());
+                return parse_err(stream.file(), InvalidValueChar(ch, line, col));
             }
         }
     }
 
-    let mut value = match var.as_str() {
-        "null" => Value::Null,
-        "true" => Value::Bool(true),
-        "false" => Value::Bool(false),
-
-        // If we're being called by `parse_include`, signify that one of these keywords was found.
-        "Obj" => Value::Obj(OBJ_SENTINEL.clone()),
-        "Str" => Value::Obj(STR_SENTINEL.clone()),
-        "Arr" => Value::Obj(ARR_SENTINEL.clone()),
-        "Tup" => Value::Obj(TUP_SENTINEL.clone()),
-
-        var @ "@" => return parse_err(stream.file(), InvalidValue(var.into(), line, col)),
-        var if is_global => {
-            // Global variable, get value from globals map.
-            match globals.get(var) {
-                Some(value) => value.clone(),
-                None => {
-                    let var = String::from(var);
-                    return parse_err(stream.file(), GlobalNotFound(var, line, col));
+    let mut value = if call {
+        // Function call.
+        let name = String::from(stream.slice(start, end));
+        let value = parse_call(stream, obj_pairs, globals, included, ctx, &name, pos, depth)?;
+
+        // The result of a call may be followed by dot notation.
+        if stream.peek() == Some('.') {
+            dot_global = parse_dot(stream)?;
+            dot = true;
+        }
+
+        value
+    } else {
+        match stream.slice(start, end) {
+            "null" => Value::Null,
+            "true" => Value::Bool(true),
+            "false" => Value::Bool(false),
+
+            // If we're being called by `parse_include`, signify that one of these keywords was
+            // found.
+            "Obj" => Value::Obj(OBJ_SENTINEL.clone()),
+            "Str" => Value::Obj(STR_SENTINEL.clone()),
+            "Arr" => Value::Obj(ARR_SENTINEL.clone()),
+            "Tup" => Value::Obj(TUP_SENTINEL.clone()),
+
+            var @ "@" => {
+                let (line, col) = stream.line_col(pos);
+                return parse_err(stream.file(), InvalidValue(var.into(), line, col));
+            }
+            var if is_global => {
+                // Global variable, get value from globals map.
+                match globals.get(var) {
+                    Some(value) => value.clone(),
+                    None => {
+                        let (line, col) = stream.line_col(pos);
+                        return parse_err(stream.file(), GlobalNotFound(var.into(), line, col));
+                    }
                 }
             }
-        }
-        var => {
-            // Regular variable, get value from the current Obj.
-            match obj_pairs.iter().find(|Pair(ref field, _)| field == var) {
-                Some(Pair(_, ref value)) => value.clone(),
-                None => {
-                    let var = String::from(var);
-                    return parse_err(stream.file(), VariableNotFound(var, line, col));
+            var => {
+                // Regular variable, get value from the current Obj.
+                match obj_pairs.get(var) {
+                    Some(value) => value.clone(),
+                    None => {
+                        let (line, col) = stream.line_col(pos);
+                        return parse_err(stream.file(), VariableNotFound(var.into(), line, col));
+                    }
                 }
             }
         }
@@ -860,14 +962,14 @@ fn parse_variable(
     if dot {
         value = match value {
             Value::Arr(arr) => {
-                let (line, col) = (stream.line(), stream.col());
+                let pos = stream.pos();
                 let value = parse_value(
-                    &mut stream,
+                    stream,
                     obj_pairs,
-                    &mut globals,
-                    &mut included,
-                    line,
-                    col,
+                    globals,
+                    included,
+                    ctx,
+                    pos,
                     depth + 1,
                     cur_brace,
                     false,
@@ -875,12 +977,17 @@ fn parse_variable(
 
                 match value {
                     Value::Int(int) => match int.to_usize() {
-                        Some(index) => arr
-                            .get(index)
-                            .map_err(|e| ParseError::from_over(&e, stream.file(), line, col))?,
-                        None => return parse_err(stream.file(), InvalidIndex(int, line, col)),
+                        Some(index) => arr.get(index).map_err(|e| {
+                            let (line, col) = stream.line_col(pos);
+                            ParseError::from_over(&e, stream.file(), line, col)
+                        })?,
+                        None => {
+                            let (line, col) = stream.line_col(pos);
+                            return parse_err(stream.file(), InvalidIndex(int, line, col));
+                        }
                     },
                     _ => {
+                        let (line, col) = stream.line_col(pos);
                         return parse_err(
                             stream.file(),
                             ExpectedType(Type::Int, value.get_type(), line, col),
@@ -889,14 +996,14 @@ fn parse_variable(
                 }
             }
             Value::Tup(tup) => {
-                let (line, col) = (stream.line(), stream.col());
+                let pos = stream.pos();
                 let value = parse_value(
-                    &mut stream,
+                    stream,
                     obj_pairs,
-                    &mut globals,
-                    &mut included,
-                    line,
-                    col,
+                    globals,
+                    included,
+                    ctx,
+                    pos,
                     depth + 1,
                     cur_brace,
                     false,
@@ -904,12 +1011,17 @@ fn parse_variable(
 
                 match value {
                     Value::Int(int) => match int.to_usize() {
-                        Some(index) => tup
-                            .get(index)
-                            .map_err(|e| ParseError::from_over(&e, stream.file(), line, col))?,
-                        None => return parse_err(stream.file(), InvalidIndex(int, line, col)),
+                        Some(index) => tup.get(index).map_err(|e| {
+                            let (line, col) = stream.line_col(pos);
+                            ParseError::from_over(&e, stream.file(), line, col)
+                        })?,
+                        None => {
+                            let (line, col) = stream.line_col(pos);
+                            return parse_err(stream.file(), InvalidIndex(int, line, col));
+                        }
                     },
                     _ => {
+                        let (line, col) = stream.line_col(pos);
                         return parse_err(
                             stream.file(),
                             ExpectedType(Type::Int, value.get_type(), line, col),
@@ -918,30 +1030,259 @@ fn parse_variable(
                 }
             }
             Value::Obj(obj) => {
-                let (line, col) = (stream.line(), stream.col());
+                let pos = stream.pos();
 
                 if dot_global {
+                    let (line, col) = stream.line_col(pos);
                     return parse_err(stream.file(), InvalidValueChar('@', line, col));
                 }
 
                 parse_variable(
-                    &mut stream,
-                    obj.pairs_ref(),
+                    stream,
+                    obj.pair_map(),
                     globals,
                     included,
-                    line,
-                    col,
+                    ctx,
+                    pos,
                     depth + 1,
                     cur_brace,
                 )?
             }
-            _ => return parse_err(stream.file(), InvalidDot(value.get_type(), line, col)),
+            _ => {
+                let (line, col) = stream.line_col(pos);
+                return parse_err(stream.file(), InvalidDot(value.get_type(), line, col));
+            }
         }
     }
 
     Ok(value)
 }
 
+// Consumes a '.' and checks that it is followed by a valid character for dot notation.
+// Returns true if the dot is followed by a global.
+fn parse_dot(stream: &mut CharStream) -> ParseResult<bool> {
+    let ch = stream.next().unwrap();
+    assert_eq!(ch, '.');
+
+    match stream.peek() {
+        Some('@') => Ok(true),
+        Some(ch) if Obj::is_valid_field_char(ch, true) || is_numeric_char(ch) => Ok(false),
+        Some(ch) => parse_err(
+            stream.file(),
+            InvalidValueChar(ch, stream.line(), stream.col()),
+        ),
+        None => parse_err(stream.file(), UnexpectedEnd(stream.line())),
+    }
+}
+
+// Parses the arguments to a function and returns the result of calling it.
+fn parse_call(
+    stream: &mut CharStream,
+    obj_pairs: &Pairs,
+    globals: &mut GlobalMap,
+    included: &mut IncludedMap,
+    ctx: &ParseContext,
+    name: &str,
+    pos: usize,
+    depth: usize,
+) -> ParseResult<Value> {
+    // Host functions take precedence over built-in functions.
+    if let Some(function) = ctx.function(name) {
+        let args = parse_args(stream, obj_pairs, globals, included, ctx, depth + 1)?;
+        let values: Vec<Value> = args.into_iter().map(|(value, ..)| value).collect();
+
+        return match function(&values) {
+            Ok(value) => Ok(value),
+            Err(error) => {
+                let (line, col) = stream.line_col(pos);
+                parse_err(stream.file(), FunctionError(name.into(), error, line, col))
+            }
+        };
+    }
+
+    if name == "env" {
+        return parse_env(stream, obj_pairs, globals, included, ctx, pos, depth);
+    }
+
+    let builtin = match get_builtin(name) {
+        Some(builtin) => builtin,
+        None => {
+            let (line, col) = stream.line_col(pos);
+            return parse_err(stream.file(), FunctionNotFound(name.into(), line, col));
+        }
+    };
+
+    let args = parse_args(stream, obj_pairs, globals, included, ctx, depth + 1)?;
+
+    if !builtin.arity.accepts(args.len()) {
+        let (line, col) = stream.line_col(pos);
+        return parse_err(
+            stream.file(),
+            FunctionArity(name.into(), builtin.arity, args.len(), line, col),
+        );
+    }
+
+    let values: Vec<Value> = args.iter().map(|(value, ..)| value.clone()).collect();
+
+    match (builtin.call)(&values) {
+        Ok(value) => Ok(value),
+        Err(CallError::ArgType(index, expected)) => {
+            let (ref value, arg_pos) = args[index];
+            let (arg_line, arg_col) = stream.line_col(arg_pos);
+            parse_err(
+                stream.file(),
+                FunctionArgType(name.into(), expected, value.get_type(), arg_line, arg_col),
+            )
+        }
+        Err(CallError::Failed(error)) => {
+            let (line, col) = stream.line_col(pos);
+            parse_err(stream.file(), FunctionError(name.into(), error, line, col))
+        }
+    }
+}
+
+// Parses a call to `env`, which reads an environment variable. Takes the name of the variable and
+// an optional default value. If a default is given, the variable is converted to the default's
+// type.
+fn parse_env(
+    stream: &mut CharStream,
+    obj_pairs: &Pairs,
+    globals: &mut GlobalMap,
+    included: &mut IncludedMap,
+    ctx: &ParseContext,
+    pos: usize,
+    depth: usize,
+) -> ParseResult<Value> {
+    // Untrusted files must not be able to read the environment.
+    if !ctx.options().allow_env {
+        let (line, col) = stream.line_col(pos);
+        return parse_err(stream.file(), EnvNotAllowed(line, col));
+    }
+
+    let mut args = parse_args(stream, obj_pairs, globals, included, ctx, depth + 1)?;
+
+    let arity = Arity::Between(1, 2);
+    if !arity.accepts(args.len()) {
+        let (line, col) = stream.line_col(pos);
+        return parse_err(
+            stream.file(),
+            FunctionArity("env".into(), arity, args.len(), line, col),
+        );
+    }
+
+    let default = if args.len() == 2 { args.pop() } else { None };
+    let t = match default {
+        Some((ref value, arg_pos)) => match value.get_type() {
+            t @ Type::Str | t @ Type::Int | t @ Type::Bool => t,
+            t => {
+                let (arg_line, arg_col) = stream.line_col(arg_pos);
+                return parse_err(
+                    stream.file(),
+                    FunctionArgType(
+                        "env".into(),
+                        vec![Type::Str, Type::Int, Type::Bool],
+                        t,
+                        arg_line,
+                        arg_col,
+                    ),
+                );
+            }
+        },
+        None => Type::Str,
+    };
+
+    let var = match args.pop().unwrap() {
+        (Value::Str(var), ..) => var,
+        (value, arg_pos) => {
+            let (arg_line, arg_col) = stream.line_col(arg_pos);
+            return parse_err(
+                stream.file(),
+                FunctionArgType(
+                    "env".into(),
+                    vec![Type::Str],
+                    value.get_type(),
+                    arg_line,
+                    arg_col,
+                ),
+            );
+        }
+    };
+
+    let (line, col) = stream.line_col(pos);
+    match ctx.env_var(&var) {
+        Ok(s) => match coerce_str(&s, &t) {
+            Some(value) => Ok(value),
+            None => parse_err(stream.file(), EnvVarInvalid(var, s, t, line, col)),
+        },
+        Err(VarError::NotPresent) => match default {
+            Some((value, ..)) => Ok(value),
+            None => parse_err(stream.file(), EnvVarNotFound(var, line, col)),
+        },
+        Err(VarError::NotUnicode(s)) => {
+            let s = s.to_string_lossy().into_owned();
+            parse_err(stream.file(), EnvVarInvalid(var, s, t, line, col))
+        }
+    }
+}
+
+// Parses function arguments. They *must* start with ( and end with ).
+// Returns each argument along with its position.
+fn parse_args(
+    stream: &mut CharStream,
+    obj_pairs: &Pairs,
+    globals: &mut GlobalMap,
+    included: &mut IncludedMap,
+    ctx: &ParseContext,
+    depth: usize,
+) -> ParseResult<Vec<(Value, usize)>> {
+    // Check depth.
+    if depth > MAX_DEPTH {
+        return parse_err(stream.file(), MaxDepth(stream.line(), stream.col()));
+    }
+
+    // We must already be at a '('.
+    let ch = stream.next().unwrap();
+    assert_eq!(ch, '(');
+
+    let mut args = Vec::new();
+
+    loop {
+        // Go to the first non-whitespace character, or error if there is none.
+        if !find_char(stream) {
+            return parse_err(stream.file(), UnexpectedEnd(stream.line()));
+        }
+
+        let peek = stream.peek().unwrap();
+        if peek == ')' {
+            let _ = stream.next();
+            break;
+        } else if is_end_delimiter(peek) {
+            return parse_err(
+                stream.file(),
+                InvalidClosingBracket(Some(')'), peek, stream.line(), stream.col()),
+            );
+        }
+
+        // At a non-whitespace character, parse value.
+        let value_pos = stream.pos();
+        let value = parse_value(
+            stream,
+            obj_pairs,
+            globals,
+            included,
+            ctx,
+            value_pos,
+            depth,
+            Some(')'),
+            true,
+        )?;
+
+        args.push((value, value_pos));
+    }
+
+    Ok(args)
+}
+
 fn parse_str_file(path: &str) -> ParseResult<String> {
     let s = read_file_str(path)?;
 
@@ -957,30 +1298,36 @@ fn parse_str(stream: &mut CharStream) -> ParseResult<Value> {
     assert_eq!(ch, '"');
 
     let mut s = String::new();
-    let mut escape = false;
 
+    // Copy the Str in chunks between escapes, so a Str without escapes is copied all at once.
     loop {
+        let rest = stream.rest();
+        let end = match rest.find(['"', '\\']) {
+            Some(end) => end,
+            None => {
+                stream.set_pos(stream.contents().len());
+                return parse_err(stream.file(), UnexpectedEnd(stream.line()));
+            }
+        };
+
+        s.push_str(&rest[..end]);
+        let quote = rest.as_bytes()[end] == b'"';
+        stream.set_pos(stream.pos() + end + 1);
+
+        if quote {
+            break;
+        }
+
         match stream.next() {
-            Some(ch) => {
-                if escape {
-                    match get_escape_char(ch) {
-                        Some(ch) => s.push(ch),
-                        None => {
-                            return parse_err(
-                                stream.file(),
-                                InvalidEscapeChar(ch, stream.line(), stream.col() - 1),
-                            );
-                        }
-                    }
-                    escape = false;
-                } else {
-                    match ch {
-                        '"' => break,
-                        '\\' => escape = true,
-                        _ => s.push(ch),
-                    }
+            Some(ch) => match get_escape_char(ch) {
+                Some(ch) => s.push(ch),
+                None => {
+                    return parse_err(
+                        stream.file(),
+                        InvalidEscapeChar(ch, stream.line(), stream.col() - 1),
+                    );
                 }
-            }
+            },
             None => return parse_err(stream.file(), UnexpectedEnd(stream.line())),
         }
     }
@@ -989,19 +1336,13 @@ fn parse_str(stream: &mut CharStream) -> ParseResult<Value> {
 }
 
 fn parse_include(
-    mut stream: &mut CharStream,
-    obj_pairs: &[Pair],
-    mut globals: &mut GlobalMap,
-    mut included: &mut IncludedMap,
+    stream: &mut CharStream,
+    obj_pairs: &Pairs,
+    globals: &mut GlobalMap,
+    included: &mut IncludedMap,
+    ctx: &ParseContext,
     depth: usize,
 ) -> ParseResult<Value> {
-    enum IncludeType {
-        Obj,
-        Str,
-        Arr,
-        Tup,
-    }
-
     // Check depth.
     if depth > MAX_DEPTH {
         return parse_err(stream.file(), MaxDepth(stream.line(), stream.col()));
@@ -1011,18 +1352,18 @@ fn parse_include(
     assert_eq!(ch, '<');
 
     // Go to the next non-whitespace character, or error if there is none.
-    if !find_char(stream.clone()) {
+    if !find_char(stream) {
         return parse_err(stream.file(), UnexpectedEnd(stream.line()));
     }
 
-    let (mut line, mut col) = (stream.line(), stream.col());
+    let mut pos = stream.pos();
     let mut value = parse_value(
-        &mut stream,
+        stream,
         obj_pairs,
-        &mut globals,
-        &mut included,
-        line,
-        col,
+        globals,
+        included,
+        ctx,
+        pos,
         depth,
         Some('>'),
         true,
@@ -1037,6 +1378,7 @@ fn parse_include(
         Value::Obj(ref obj) if obj.ptr_eq(&TUP_SENTINEL) => include_type = IncludeType::Tup,
         Value::Str(_) => parse_again = false,
         _ => {
+            let (line, col) = stream.line_col(pos);
             return parse_err(
                 stream.file(),
                 InvalidIncludeToken(value.get_type(), line, col),
@@ -1046,19 +1388,18 @@ fn parse_include(
 
     if parse_again {
         // Go to the next non-whitespace character, or error if there is none.
-        if !find_char(stream.clone()) {
+        if !find_char(stream) {
             return parse_err(stream.file(), UnexpectedEnd(stream.line()));
         }
 
-        line = stream.line();
-        col = stream.col();
+        pos = stream.pos();
         value = parse_value(
-            &mut stream,
+            stream,
             obj_pairs,
-            &mut globals,
-            &mut included,
-            line,
-            col,
+            globals,
+            included,
+            ctx,
+            pos,
             depth,
             Some('>'),
             true,
@@ -1066,7 +1407,7 @@ fn parse_include(
     }
 
     // Go to the next non-whitespace character, or error if there is none.
-    if !find_char(stream.clone()) {
+    if !find_char(stream) {
         return parse_err(stream.file(), UnexpectedEnd(stream.line()));
     }
 
@@ -1080,6 +1421,8 @@ fn parse_include(
         }
     }
 
+    let (line, col) = stream.line_col(pos);
+
     // Get the full path of the include file.
     let include_file = match value {
         Value::Str(s) => s,
@@ -1122,37 +1465,245 @@ fn parse_include(
     // Prevent cyclic includes by temporarily storing the current file path.
     let storing = if let Some(file) = stream.file() {
         let full_file = String::from(Path::new(&file).canonicalize().unwrap().to_str().unwrap());
-        included.1.insert(full_file.clone());
+        included.parsing.insert(full_file.clone());
         Some(full_file)
     } else {
         None
     };
-    if included.1.contains(full_path_str) {
+    if included.parsing.contains(full_path_str) {
         return parse_err(stream.file(), CyclicInclude(include_file, line, col));
     }
+    included.reached.insert(full_path_str.into());
+
+    let value = load_include(path_str, full_path_str, include_type, included, ctx)?;
+
+    // Remove the stored file path.
+    if let Some(file) = storing {
+        included.parsing.remove(&file);
+    }
 
-    // Get either the tracked value or parse it if it's our first time seeing the include.
-    let value = if included.0.contains_key(full_path_str) {
-        let value = &included.0[full_path_str];
+    Ok(value)
+}
+
+// Gets either the tracked value, the prefetched value, the cached value, or parses the include if
+// it's our first time seeing it.
+fn load_include(
+    path_str: &str,
+    full_path_str: &str,
+    include_type: IncludeType,
+    included: &mut IncludedMap,
+    ctx: &ParseContext,
+) -> ParseResult<Value> {
+    let key = (String::from(full_path_str), include_type);
+
+    let value = if included.values.contains_key(full_path_str) {
+        let value = &included.values[full_path_str];
         value.clone()
+    } else if let Some(value) = included.take_prefetched(&key) {
+        value
+    } else if let Some((value, deps)) = included
+        .cache
+        .as_ref()
+        .and_then(|cache| cache.get(full_path_str, include_type))
+    {
+        included.values.insert(key.0, value.clone());
+        included.deps.insert(full_path_str.into(), deps);
+        value
     } else {
+        let tracking = included.cache.is_some();
+        if tracking {
+            let mut frame = Stamps::new();
+            frame.insert(full_path_str.into(), stamp(full_path_str));
+            included.frames.push(frame);
+        }
+
         let value: Value = match include_type {
-            IncludeType::Obj => parse_obj_file_includes(path_str, included)?.into(),
+            IncludeType::Obj => parse_obj_file_includes(path_str, included, ctx)?.into(),
             IncludeType::Str => parse_str_file(path_str)?.into(),
-            IncludeType::Arr => parse_arr_file(path_str, included)?.into(),
-            IncludeType::Tup => parse_tup_file(path_str, included)?.into(),
+            IncludeType::Arr => parse_arr_file(path_str, included, ctx)?.into(),
+            IncludeType::Tup => parse_tup_file(path_str, included, ctx)?.into(),
         };
+
+        if tracking {
+            let deps = included.frames.pop().unwrap();
+            if let Some(ref cache) = included.cache {
+                cache.insert(full_path_str, include_type, value.clone(), deps.clone());
+            }
+            included.deps.insert(full_path_str.into(), deps);
+        }
+
         // Use full path as included key.
-        included.0.insert(full_path_str.into(), value.clone());
+        included.values.insert(key.0, value.clone());
         value
     };
+    included.add_dep(full_path_str);
 
-    // Remove the stored file path.
-    if let Some(file) = storing {
-        included.1.remove(&file);
+    Ok(value)
+}
+
+// Parses the includes with literal paths in the file being read by `stream` concurrently, if
+// enabled, so that they are ready by the time they are reached.
+//
+// Errors are ignored here. An include which failed is parsed again when it is reached, so that
+// errors, including cyclic includes, are reported exactly as when parsing sequentially. This means
+// that host functions may be called speculatively and more than once; see
+// `ParseOptions::parallel_includes`.
+#[cfg(feature = "parallel")]
+fn prefetch_includes(stream: &CharStream, included: &mut IncludedMap, ctx: &ParseContext) {
+    if !ctx.options().parallel_includes {
+        return;
     }
 
-    Ok(value)
+    let file = stream.file();
+    let current = file
+        .as_ref()
+        .and_then(|file| Path::new(file).canonicalize().ok())
+        .and_then(|path| path.to_str().map(String::from));
+
+    let mut candidates: Vec<(String, String, IncludeType)> = vec![];
+    for (include_type, include_file) in scan_includes(stream.contents()) {
+        let pathbuf = match file {
+            Some(ref file) => Path::new(file)
+                .parent()
+                .unwrap()
+                .join(Path::new(&include_file)),
+            None => Path::new(&include_file).to_path_buf(),
+        };
+        let (path_str, full_path_str) = match (pathbuf.to_str(), pathbuf.canonicalize()) {
+            (Some(path_str), Ok(full_path)) if pathbuf.is_file() => match full_path.to_str() {
+                Some(full_path_str) => (String::from(path_str), String::from(full_path_str)),
+                None => continue,
+            },
+            _ => continue,
+        };
+
+        let key = (full_path_str.clone(), include_type);
+        if included.values.contains_key(&full_path_str)
+            || included.prefetched.contains_key(&key)
+            || included.parsing.contains(&full_path_str)
+            || current.as_ref() == Some(&full_path_str)
+            || candidates
+                .iter()
+                .any(|(_, full, t)| *full == full_path_str && *t == include_type)
+        {
+            continue;
+        }
+
+        candidates.push((path_str, full_path_str, include_type));
+    }
+
+    // There is nothing to gain from parsing a single include on another thread.
+    if candidates.len() < 2 {
+        return;
+    }
+
+    let mut parsing = included.parsing.clone();
+    parsing.extend(current);
+    let cache = included.cache.clone();
+
+    let results: Vec<_> = candidates
+        .into_par_iter()
+        .map(|(path_str, full_path_str, include_type)| {
+            let mut sub_included = IncludedMap {
+                parsing: parsing.clone(),
+                cache: cache.clone(),
+                ..Default::default()
+            };
+            let result = load_include(
+                &path_str,
+                &full_path_str,
+                include_type,
+                &mut sub_included,
+                ctx,
+            );
+
+            (full_path_str, include_type, result, sub_included)
+        })
+        .collect();
+
+    for (full_path_str, include_type, result, sub_included) in results {
+        if let Ok(value) = result {
+            let _ = included.prefetched.insert(
+                (full_path_str, include_type),
+                Prefetched {
+                    value,
+                    values: sub_included.values,
+                    deps: sub_included.deps,
+                },
+            );
+        }
+    }
+}
+
+#[cfg(not(feature = "parallel"))]
+fn prefetch_includes(_stream: &CharStream, _included: &mut IncludedMap, _ctx: &ParseContext) {}
+
+// Finds the includes with literal paths, such as `<Arr "file.over">`, in the given contents,
+// skipping comments and strings.
+#[cfg(feature = "parallel")]
+fn scan_includes(contents: &str) -> Vec<(IncludeType, String)> {
+    let mut includes = vec![];
+    let mut chars = contents.char_indices().peekable();
+
+    while let Some((index, ch)) = chars.next() {
+        match ch {
+            '#' => {
+                for (_, ch) in chars.by_ref() {
+                    if ch == '\n' {
+                        break;
+                    }
+                }
+            }
+            '"' => {
+                while let Some((_, ch)) = chars.next() {
+                    match ch {
+                        '\\' => {
+                            let _ = chars.next();
+                        }
+                        '"' => break,
+                        _ => (),
+                    }
+                }
+            }
+            '<' => {
+                if let Some(include) = scan_include(&contents[index + 1..]) {
+                    includes.push(include);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    includes
+}
+
+// Tries to read a literal include following a '<'.
+#[cfg(feature = "parallel")]
+fn scan_include(rest: &str) -> Option<(IncludeType, String)> {
+    let rest = rest.trim_start();
+
+    let (include_type, rest) = match rest.find(|ch: char| !ch.is_alphanumeric()) {
+        Some(0) => (IncludeType::Obj, rest),
+        Some(end) => {
+            let include_type = match &rest[..end] {
+                "Obj" => IncludeType::Obj,
+                "Str" => IncludeType::Str,
+                "Arr" => IncludeType::Arr,
+                "Tup" => IncludeType::Tup,
+                _ => return None,
+            };
+            (include_type, rest[end..].trim_start())
+        }
+        None => return None,
+    };
+
+    let rest = rest.strip_prefix('"')?;
+    let end = rest.find(['"', '\\'])?;
+    if !rest[end..].starts_with('"') || !rest[end + 1..].trim_start().starts_with('>') {
+        return None;
+    }
+
+    Some((include_type, String::from(&rest[..end])))
 }
 
 // Tries to perform a unary operation on a single value.
@@ -1160,8 +1711,7 @@ fn unary_op_on_value(
     stream: &CharStream,
     val: Value,
     op: UnaryOp,
-    line: usize,
-    col: usize,
+    pos: usize,
 ) -> ParseResult<Value> {
     use crate::types::Type::*;
 
@@ -1170,12 +1720,18 @@ fn unary_op_on_value(
     Ok(match op {
         UnaryOp::Plus => match t {
             Int | Frac => val,
-            _ => return parse_err(stream.file(), UnaryOperatorError(t, op, line, col)),
+            _ => {
+                let (line, col) = stream.line_col(pos);
+                return parse_err(stream.file(), UnaryOperatorError(t, op, line, col));
+            }
         },
         UnaryOp::Minus => match t {
             Int => (-val.get_int().unwrap()).into(),
             Frac => (-val.get_frac().unwrap()).into(),
-            _ => return parse_err(stream.file(), UnaryOperatorError(t, op, line, col)),
+            _ => {
+                let (line, col) = stream.line_col(pos);
+                return parse_err(stream.file(), UnaryOperatorError(t, op, line, col));
+            }
         },
     })
 }
@@ -1186,8 +1742,7 @@ fn binary_op_on_values(
     mut val1: Value,
     mut val2: Value,
     op: BinaryOp,
-    line: usize,
-    col: usize,
+    pos: usize,
 ) -> ParseResult<Value> {
     use crate::types::Type::*;
 
@@ -1209,6 +1764,10 @@ fn binary_op_on_values(
                 Frac if type2 == Frac => {
                     (val1.get_frac().unwrap() + val2.get_frac().unwrap()).into()
                 }
+                Obj if type2 == Obj => {
+                    let (obj1, obj2) = (val1.get_obj().unwrap(), val2.get_obj().unwrap());
+                    obj1.merge(&obj2).into()
+                }
                 Str if type2 == Str => {
                     let str1 = val1.get_str().unwrap();
                     let str2 = val2.get_str().unwrap();
@@ -1239,6 +1798,7 @@ fn binary_op_on_values(
                             arr.into()
                         }
                         None => {
+                            let (line, col) = stream.line_col(pos);
                             return parse_err(
                                 stream.file(),
                                 BinaryOperatorError(type1, type2, op, line, col),
@@ -1247,6 +1807,7 @@ fn binary_op_on_values(
                     }
                 }
                 _ => {
+                    let (line, col) = stream.line_col(pos);
                     return parse_err(
                         stream.file(),
                         BinaryOperatorError(type1, type2, op, line, col),
@@ -1257,7 +1818,19 @@ fn binary_op_on_values(
         BinaryOp::Minus => match type1 {
             Int if type2 == Int => (val1.get_int().unwrap() - val2.get_int().unwrap()).into(),
             Frac if type2 == Frac => (val1.get_frac().unwrap() - val2.get_frac().unwrap()).into(),
+            Obj if type2 == Arr(Box::new(Str)) => {
+                let fields: Vec<String> = val2
+                    .get_arr()
+                    .unwrap()
+                    .iter()
+                    .map(|field| field.get_str().unwrap())
+                    .collect();
+                let fields: Vec<&str> = fields.iter().map(String::as_str).collect();
+
+                val1.get_obj().unwrap().remove_fields(&fields).into()
+            }
             _ => {
+                let (line, col) = stream.line_col(pos);
                 return parse_err(
                     stream.file(),
                     BinaryOperatorError(type1, type2, op, line, col),
@@ -1268,6 +1841,7 @@ fn binary_op_on_values(
             Int if type2 == Int => (val1.get_int().unwrap() * val2.get_int().unwrap()).into(),
             Frac if type2 == Frac => (val1.get_frac().unwrap() * val2.get_frac().unwrap()).into(),
             _ => {
+                let (line, col) = stream.line_col(pos);
                 return parse_err(
                     stream.file(),
                     BinaryOperatorError(type1, type2, op, line, col),
@@ -1278,6 +1852,7 @@ fn binary_op_on_values(
             Int if type2 == Int => {
                 let (int1, int2) = (val1.get_int().unwrap(), val2.get_int().unwrap());
                 if int2.is_zero() {
+                    let (line, col) = stream.line_col(pos);
                     return parse_err(stream.file(), InvalidNumeric(line, col));
                 }
                 BigRational::new(int1, int2).into()
@@ -1285,11 +1860,13 @@ fn binary_op_on_values(
             Frac if type2 == Frac => {
                 let (frac1, frac2) = (val1.get_frac().unwrap(), val2.get_frac().unwrap());
                 if frac2.is_zero() {
+                    let (line, col) = stream.line_col(pos);
                     return parse_err(stream.file(), InvalidNumeric(line, col));
                 }
                 (frac1 / frac2).into()
             }
             _ => {
+                let (line, col) = stream.line_col(pos);
                 return parse_err(
                     stream.file(),
                     BinaryOperatorError(type1, type2, op, line, col),
@@ -1300,11 +1877,13 @@ fn binary_op_on_values(
             Int if type2 == Int => {
                 let int2 = val2.get_int().unwrap();
                 if int2.is_zero() {
+                    let (line, col) = stream.line_col(pos);
                     return parse_err(stream.file(), InvalidNumeric(line, col));
                 }
                 (val1.get_int().unwrap() % int2).into()
             }
             _ => {
+                let (line, col) = stream.line_col(pos);
                 return parse_err(
                     stream.file(),
                     BinaryOperatorError(type1, type2, op, line, col),
@@ -1316,19 +1895,17 @@ fn binary_op_on_values(
 
 // Finds the next non-whitespace character, ignoring comments, and update stream position.
 // Returns true if such a character was found or false if we got to the end of the stream.
-fn find_char(mut stream: CharStream) -> bool {
+fn find_char(stream: &mut CharStream) -> bool {
     while let Some(ch) = stream.peek() {
         match ch {
             '#' => {
                 // Comment found; eat the rest of the line.
-                loop {
-                    let ch = stream.next();
-                    if ch.is_none() {
+                match stream.rest().find('\n') {
+                    Some(end) => stream.set_pos(stream.pos() + end + 1),
+                    None => {
+                        stream.set_pos(stream.contents().len());
                         return false;
                     }
-                    if ch.unwrap() == '\n' {
-                        break;
-                    }
                 }
             }
             ch if ch.is_whitespace() => {
@@ -1,10 +1,12 @@
 //! Utility functions used by the parser.
 
 use super::BinaryOp;
-use num_bigint::BigInt;
+use crate::{types::Type, value::Value};
+use num_bigint::{BigInt, ParseBigIntError};
 use num_rational::BigRational;
 use num_traits::{pow, FromPrimitive};
 use std::{
+    borrow::Cow,
     fs::File,
     io::{self, Read},
 };
@@ -35,10 +37,7 @@ pub fn is_whitespace(ch: char) -> bool {
 }
 
 pub fn is_end_delimiter(ch: char) -> bool {
-    match ch {
-        ')' | ']' | '}' | '>' => true,
-        _ => false,
-    }
+    matches!(ch, ')' | ']' | '}' | '>')
 }
 
 pub fn is_numeric_char(ch: char) -> bool {
@@ -51,22 +50,135 @@ pub fn is_numeric_char(ch: char) -> bool {
 
 /// Returns true if `ch` is an ASCII decimal digit.
 pub fn is_digit(ch: char) -> bool {
-    match ch {
-        '0'..='9' => true,
-        _ => false,
-    }
+    ch.is_ascii_digit()
 }
 
 pub fn is_reserved(field: &str) -> bool {
-    match field {
-        "@" | "null" | "true" | "false" | "Obj" | "Str" | "Arr" | "Tup" => true,
-        _ => false,
+    matches!(
+        field,
+        "@" | "null" | "true" | "false" | "Obj" | "Str" | "Arr" | "Tup"
+    )
+}
+
+/// Parses a string of ASCII decimal digits, avoiding arbitrary-precision parsing for short strings.
+pub fn parse_digits(s: &str) -> Result<BigInt, ParseBigIntError> {
+    // Any 19 digits fit in a u64.
+    if s.len() <= 19 {
+        if let Ok(n) = s.parse::<u64>() {
+            return Ok(n.into());
+        }
     }
+
+    s.parse()
 }
 
 pub fn frac_from_whole_and_dec(whole: BigInt, decimal: BigInt, dec_len: usize) -> BigRational {
     let denom = pow(BigInt::from_u8(10).unwrap(), dec_len);
-    BigRational::new(whole, 1.into()) + BigRational::new(decimal, denom)
+    BigRational::new(whole * &denom + decimal, denom)
+}
+
+/// An error in a number passed to `parse_unsigned_numeric`.
+pub enum NumericError {
+    /// A character which is not allowed, and its byte offset.
+    InvalidChar(char, usize),
+    /// A number without digits, such as `.`.
+    NoDigits,
+    /// Digits which could not be parsed.
+    Digits(ParseBigIntError),
+}
+
+impl From<ParseBigIntError> for NumericError {
+    fn from(e: ParseBigIntError) -> Self {
+        NumericError::Digits(e)
+    }
+}
+
+/// Parses a number without a sign, such as `1_000` or `0,5`, to an `Int`, or to a `Frac` if it has
+/// a decimal point.
+pub fn parse_unsigned_numeric(s: &str) -> Result<Value, NumericError> {
+    // Byte offset of the decimal point, if any.
+    let mut dec = None;
+    let mut under = false;
+    let mut has_under = false;
+
+    for (i, ch) in s.char_indices() {
+        match ch {
+            ch if is_digit(ch) => (),
+            '.' | ',' if dec.is_none() => {
+                dec = Some(i);
+            }
+            '_' if !under => {
+                under = true;
+                has_under = true;
+            }
+            _ => return Err(NumericError::InvalidChar(ch, i)),
+        }
+
+        if ch != '_' {
+            under = false;
+        }
+    }
+
+    let (s1, s2) = match dec {
+        Some(dec) => (&s[..dec], &s[dec + 1..]),
+        None => (s, ""),
+    };
+
+    // Underscores are only separators; only allocate if there are any.
+    let (s1, s2) = if has_under {
+        (
+            Cow::Owned(s1.replace('_', "")),
+            Cow::Owned(s2.replace('_', "")),
+        )
+    } else {
+        (Cow::Borrowed(s1), Cow::Borrowed(s2))
+    };
+
+    if dec.is_some() {
+        // Parse a Frac from a number with a decimal.
+        if s1.is_empty() && s2.is_empty() {
+            return Err(NumericError::NoDigits);
+        }
+
+        let whole: BigInt = if s1.is_empty() {
+            0u8.into()
+        } else {
+            parse_digits(&s1)?
+        };
+
+        // Remove trailing zeros.
+        let s2 = s2.trim_end_matches('0');
+
+        let (decimal, dec_len): (BigInt, usize) = if s2.is_empty() {
+            (0u8.into(), 1)
+        } else {
+            (parse_digits(s2)?, s2.len())
+        };
+
+        Ok(frac_from_whole_and_dec(whole, decimal, dec_len).into())
+    } else {
+        // Parse an Int.
+        if s1.is_empty() {
+            return Err(NumericError::NoDigits);
+        }
+
+        Ok(parse_digits(&s1)?.into())
+    }
+}
+
+/// Converts a string from outside of a .over file, such as an environment variable, to a `Value`
+/// of type `t`. Only `Str`, `Int` and `Bool` are supported. Returns `None` if the conversion fails.
+pub fn coerce_str(s: &str, t: &Type) -> Option<Value> {
+    match *t {
+        Type::Str => Some(s.into()),
+        Type::Int => s.trim().parse::<BigInt>().ok().map(Value::Int),
+        Type::Bool => match s.trim().to_lowercase().as_str() {
+            "true" | "1" => Some(true.into()),
+            "false" | "0" => Some(false.into()),
+            _ => None,
+        },
+        _ => None,
+    }
 }
 
 /// Reads a file and returns its contents in a string.
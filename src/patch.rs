@@ -0,0 +1,379 @@
+//! Structured changes which can be applied to an `Obj`.
+//!
+//! A `Patch` is a list of operations, each targeting the value at a dotted path such as
+//! `db.pool_size` or `servers.2.port`, where `Arr` and `Tup` elements are identified by their
+//! index. Applying a patch returns a new `Obj`; the original is left untouched.
+//!
+//! Patches can also be loaded from .over files with the following layout:
+//!
+//! ```text
+//! ops: [
+//!     { op: "set" path: "db.pool_size" value: 20 }
+//!     { op: "remove" path: "debug" }
+//!     { op: "insert" path: "servers" index: 0 value: { host: "new" } }
+//!     { op: "insert" path: "features" value: "metrics" } # Appends.
+//! ]
+//! ```
+//!
+//! # Examples
+//!
+//! ```
+//! # #[macro_use] extern crate over;
+//! # fn main() {
+//!
+//! use over::patch::Patch;
+//!
+//! let obj = obj! { "db" => obj! { "pool_size" => 10 }, "debug" => true };
+//!
+//! let mut patch = Patch::new();
+//! patch.set("db.pool_size", 20);
+//! patch.remove("debug");
+//!
+//! assert_eq!(patch.apply(&obj).unwrap(), obj! { "db" => obj! { "pool_size" => 20 } });
+//!
+//! # }
+//! ```
+
+use crate::{
+    arr::Arr,
+    error::OverError,
+    obj::{Obj, Pair},
+    tup::Tup,
+    types::Type,
+    util::join_path,
+    value::Value,
+    OverResult,
+};
+use num_traits::ToPrimitive;
+use std::{slice::Iter, str::FromStr};
+
+/// A single patch operation.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Op {
+    /// Sets the value at the path. A field which does not exist is added to its `Obj`; an `Arr`
+    /// or `Tup` index must already exist.
+    Set(String, Value),
+    /// Removes the field or element at the path. Only fields defined directly on an `Obj` can be
+    /// removed.
+    Remove(String),
+    /// Inserts a value into the `Arr` at the path, at the given index or at the end if there is
+    /// none.
+    Insert(String, Option<usize>, Value),
+}
+
+/// An ordered list of operations to apply to an `Obj`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Patch {
+    ops: Vec<Op>,
+}
+
+impl Patch {
+    /// Returns a new, empty patch.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns a patch from the `ops` field of `obj`. See the module documentation for the layout.
+    pub fn from_obj(obj: &Obj) -> OverResult<Self> {
+        let mut patch = Self::new();
+
+        for value in obj.get_arr("ops")?.iter() {
+            let op = value.get_obj()?;
+            let path = op.get_str("path")?;
+
+            match op.get_str("op")?.as_str() {
+                "set" => patch.set(&path, op.get("value").ok_or_else(not_found("value"))?),
+                "remove" => patch.remove(&path),
+                "insert" => {
+                    let index = match op.get("index") {
+                        Some(index) => {
+                            let index = index.get_int()?;
+                            match index.to_usize() {
+                                Some(index) => Some(index),
+                                None => {
+                                    return Err(OverError::InvalidPatchOp(format!(
+                                        "insert at index {}",
+                                        index
+                                    )))
+                                }
+                            }
+                        }
+                        None => None,
+                    };
+                    let value = op.get("value").ok_or_else(not_found("value"))?;
+
+                    patch.push(Op::Insert(path, index, value));
+                }
+                name => return Err(OverError::InvalidPatchOp(name.into())),
+            }
+        }
+
+        Ok(patch)
+    }
+
+    /// Returns a patch loaded from the .over file at `path`.
+    pub fn from_file(path: &str) -> OverResult<Self> {
+        Self::from_obj(&Obj::from_file(path)?)
+    }
+
+    /// Adds an operation to the end of this patch.
+    pub fn push(&mut self, op: Op) {
+        self.ops.push(op);
+    }
+
+    /// Adds an operation setting the value at `path` to `value`.
+    pub fn set<V>(&mut self, path: &str, value: V)
+    where
+        V: Into<Value>,
+    {
+        self.push(Op::Set(path.into(), value.into()));
+    }
+
+    /// Adds an operation removing the value at `path`.
+    pub fn remove(&mut self, path: &str) {
+        self.push(Op::Remove(path.into()));
+    }
+
+    /// Adds an operation inserting `value` into the `Arr` at `path` at `index`, or at the end if
+    /// `index` is `None`.
+    pub fn insert<V>(&mut self, path: &str, index: Option<usize>, value: V)
+    where
+        V: Into<Value>,
+    {
+        self.push(Op::Insert(path.into(), index, value.into()));
+    }
+
+    /// Returns a reference to the operations in this patch.
+    pub fn ops_ref(&self) -> &Vec<Op> {
+        &self.ops
+    }
+
+    /// An iterator visiting all operations in order.
+    pub fn iter(&self) -> Iter<'_, Op> {
+        self.ops.iter()
+    }
+
+    /// Returns the number of operations in this patch.
+    pub fn len(&self) -> usize {
+        self.ops.len()
+    }
+
+    /// Returns whether this patch has no operations.
+    pub fn is_empty(&self) -> bool {
+        self.ops.is_empty()
+    }
+
+    /// Applies every operation in order to `obj`, returning the resulting `Obj`.
+    ///
+    /// Returns an error if a path is invalid or does not exist, if a field name is invalid, or if
+    /// a value does not match the type of the other elements of an `Arr`. Nothing is applied if
+    /// any operation fails.
+    pub fn apply(&self, obj: &Obj) -> OverResult<Obj> {
+        let mut value = Value::Obj(obj.clone());
+
+        for op in &self.ops {
+            value = match *op {
+                Op::Set(ref path, ref new) => update(
+                    &value,
+                    &split_path(path)?,
+                    "",
+                    &mut |parent, key, prefix| set_child(parent, key, new.clone(), prefix),
+                )?,
+                Op::Remove(ref path) => update(&value, &split_path(path)?, "", &mut remove_child)?,
+                Op::Insert(ref path, index, ref new) => update(
+                    &value,
+                    &split_path(path)?,
+                    "",
+                    &mut |parent, key, prefix| {
+                        let arr = match get_child(parent, key, prefix)? {
+                            Value::Arr(arr) => arr,
+                            value => {
+                                return Err(OverError::TypeMismatch(
+                                    Type::Arr(Box::new(Type::Any)),
+                                    value.get_type(),
+                                ))
+                            }
+                        };
+
+                        let index = index.unwrap_or_else(|| arr.len());
+                        if index > arr.len() {
+                            return Err(OverError::ArrOutOfBounds(index));
+                        }
+
+                        let mut values = arr.values_ref().clone();
+                        values.insert(index, new.clone());
+                        let arr = arr_with_changed(values, index)?;
+
+                        set_child(parent, key, arr.into(), prefix)
+                    },
+                )?,
+            };
+        }
+
+        value.get_obj()
+    }
+}
+
+impl FromStr for Patch {
+    type Err = OverError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_obj(&Obj::from_str(s)?)
+    }
+}
+
+// Returns the value at `path` in `obj`, including inherited fields.
+pub(crate) fn get_at(obj: &Obj, path: &str) -> OverResult<Value> {
+    let mut value = Value::Obj(obj.clone());
+    let mut prefix = String::new();
+
+    for key in split_path(path)? {
+        value = get_child(&value, key, &prefix)?;
+        prefix = join_path(&prefix, key);
+    }
+
+    Ok(value)
+}
+
+fn not_found(field: &'static str) -> impl Fn() -> OverError {
+    move || OverError::FieldNotFound(field.into())
+}
+
+fn split_path(path: &str) -> OverResult<Vec<&str>> {
+    let segments: Vec<&str> = path.split('.').collect();
+
+    if segments.iter().any(|segment| segment.is_empty()) {
+        Err(OverError::InvalidPath(path.into()))
+    } else {
+        Ok(segments)
+    }
+}
+
+fn parse_index(key: &str, prefix: &str) -> OverResult<usize> {
+    key.parse()
+        .map_err(|_| OverError::InvalidPath(join_path(prefix, key)))
+}
+
+// Returns `value` with the container holding the last segment replaced by the result of `f`,
+// which is given the container, the last segment and the path to the container.
+fn update<F>(value: &Value, segments: &[&str], prefix: &str, f: &mut F) -> OverResult<Value>
+where
+    F: FnMut(&Value, &str, &str) -> OverResult<Value>,
+{
+    let (key, rest) = segments.split_first().unwrap();
+
+    if rest.is_empty() {
+        return f(value, key, prefix);
+    }
+
+    let child = get_child(value, key, prefix)?;
+    let child = update(&child, rest, &join_path(prefix, key), f)?;
+
+    set_child(value, key, child, prefix)
+}
+
+fn get_child(parent: &Value, key: &str, prefix: &str) -> OverResult<Value> {
+    match *parent {
+        Value::Obj(ref obj) => obj
+            .get(key)
+            .ok_or_else(|| OverError::FieldNotFound(join_path(prefix, key))),
+        Value::Arr(ref arr) => arr.get(parse_index(key, prefix)?),
+        Value::Tup(ref tup) => tup.get(parse_index(key, prefix)?),
+        _ => Err(OverError::InvalidPath(join_path(prefix, key))),
+    }
+}
+
+fn set_child(parent: &Value, key: &str, child: Value, prefix: &str) -> OverResult<Value> {
+    match *parent {
+        Value::Obj(ref obj) => {
+            if !Obj::is_valid_field(key) {
+                return Err(OverError::InvalidFieldName(key.into()));
+            }
+
+            let mut pairs = obj.pairs_ref().clone();
+            match pairs.iter_mut().find(|Pair(ref field, _)| field == key) {
+                Some(pair) => pair.1 = child,
+                None => pairs.push(Pair(key.into(), child)),
+            }
+
+            Ok(Obj::from_pairs_with_parents_unchecked(pairs, obj.parents_ref().clone()).into())
+        }
+        Value::Arr(ref arr) => {
+            let index = parse_index(key, prefix)?;
+            if index >= arr.len() {
+                return Err(OverError::ArrOutOfBounds(index));
+            }
+
+            let mut values = arr.values_ref().clone();
+            values[index] = child;
+
+            Ok(arr_with_changed(values, index)?.into())
+        }
+        Value::Tup(ref tup) => {
+            let index = parse_index(key, prefix)?;
+            if index >= tup.len() {
+                return Err(OverError::TupOutOfBounds(index));
+            }
+
+            let mut values = tup.values_ref().clone();
+            values[index] = child;
+
+            Ok(Tup::from_values(values).into())
+        }
+        _ => Err(OverError::InvalidPath(join_path(prefix, key))),
+    }
+}
+
+fn remove_child(parent: &Value, key: &str, prefix: &str) -> OverResult<Value> {
+    match *parent {
+        Value::Obj(ref obj) => {
+            if !obj.contains(key) {
+                return Err(OverError::FieldNotFound(join_path(prefix, key)));
+            }
+
+            Ok(obj.remove_fields(&[key]).into())
+        }
+        Value::Arr(ref arr) => {
+            let index = parse_index(key, prefix)?;
+            if index >= arr.len() {
+                return Err(OverError::ArrOutOfBounds(index));
+            }
+
+            let mut values = arr.values_ref().clone();
+            let _ = values.remove(index);
+
+            Ok(Arr::from_values(values)?.into())
+        }
+        Value::Tup(ref tup) => {
+            let index = parse_index(key, prefix)?;
+            if index >= tup.len() {
+                return Err(OverError::TupOutOfBounds(index));
+            }
+
+            let mut values = tup.values_ref().clone();
+            let _ = values.remove(index);
+
+            Ok(Tup::from_values(values).into())
+        }
+        _ => Err(OverError::InvalidPath(join_path(prefix, key))),
+    }
+}
+
+// Returns an `Arr` of `values`, checking that the value at `changed` has the same type as the
+// others.
+fn arr_with_changed(values: Vec<Value>, changed: usize) -> OverResult<Arr> {
+    let mut tcur = Type::Any;
+
+    for (index, value) in values.iter().enumerate() {
+        if index != changed {
+            // The other values already had a common type.
+            tcur = Type::most_specific(&tcur, &value.get_type()).unwrap().0;
+        }
+    }
+
+    let tnew = values[changed].get_type();
+    match Type::most_specific(&tcur, &tnew) {
+        Some((t, _)) => Ok(Arr::from_values_unchecked(values, t)),
+        None => Err(OverError::ArrTypeMismatch(tcur, tnew)),
+    }
+}
@@ -2,10 +2,17 @@
 
 //! Tests.
 
-use crate::{error::OverError, types::Type, value::Value, OverResult, ReferenceType};
+use crate::{
+    error::OverError,
+    layered::ArrMerge,
+    obj::{Obj, Pair},
+    types::Type,
+    value::Value,
+    OverResult, ReferenceType,
+};
 #[cfg(test)]
 use pretty_assertions::{assert_eq, assert_ne};
-use std::convert::TryInto;
+use std::{convert::TryInto, str::FromStr};
 
 // Test setting and getting values.
 #[test]
@@ -207,3 +214,175 @@ fn types() -> OverResult<()> {
 
     Ok(())
 }
+
+#[test]
+fn merge() -> OverResult<()> {
+    let parent = obj! { "p" => 0 };
+    let obj1 = obj! {
+        "^" => parent.clone(),
+        "a" => 1,
+        "nested" => obj! { "x" => 1, "y" => 2 },
+    };
+    let obj2 = obj! {
+        "b" => 2,
+        "nested" => obj! { "x" => 10 },
+    };
+
+    let merged = obj1.merge(&obj2);
+    assert_eq!(merged.get("a").unwrap(), 1);
+    assert_eq!(merged.get("b").unwrap(), 2);
+    assert_eq!(merged.get("nested").unwrap(), obj! { "x" => 10 });
+    assert!(merged.parents_ref()[0].ptr_eq(&parent));
+
+    let merged = obj1.deep_merge(&obj2)?;
+    assert_eq!(merged.get("nested").unwrap(), obj! { "x" => 10, "y" => 2 });
+
+    let obj3 = obj! { "nested" => obj! { "y" => "two" } };
+    assert_eq!(
+        obj1.deep_merge(&obj3),
+        Err(OverError::FieldTypeMismatch(
+            "nested.y".into(),
+            Type::Int,
+            Type::Str
+        ))
+    );
+
+    let tags1 = obj! { "tags" => obj! { "list" => arr!["a"] } };
+    let tags2 = obj! { "tags" => obj! { "list" => arr!["b"] } };
+    assert_eq!(
+        tags1.deep_merge_with(&tags2, ArrMerge::Append)?,
+        obj! { "tags" => obj! { "list" => arr!["a", "b"] } }
+    );
+    assert_eq!(tags1.deep_merge(&tags2)?, tags2);
+    assert_eq!(
+        tags1.deep_merge_with(
+            &obj! { "tags" => obj! { "list" => arr![1] } },
+            ArrMerge::Append
+        ),
+        Err(OverError::FieldTypeMismatch(
+            "tags.list".into(),
+            Type::Arr(Box::new(Type::Str)),
+            Type::Arr(Box::new(Type::Int))
+        ))
+    );
+
+    let removed = obj1.remove_fields(&["a", "p"]);
+    assert_eq!(removed.len(), 1);
+    assert_eq!(removed.get("p").unwrap(), 0);
+
+    Ok(())
+}
+
+#[test]
+fn multiple_parents() -> OverResult<()> {
+    let base = obj! { "a" => 1, "b" => 2 };
+    let left = obj! { "^" => base.clone() };
+    let right = obj! { "^" => base.clone(), "b" => 3 };
+    let child = try_obj! { "^" => arr![left.clone(), right.clone()] }?;
+
+    assert_eq!(child.get("a").unwrap(), 1);
+    assert_eq!(child.get("b").unwrap(), 3);
+    assert!(child.get_with_source("a").unwrap().1.ptr_eq(&base));
+
+    let ancestors = child.ancestors();
+    assert_eq!(ancestors.len(), 3);
+    assert!(ancestors[0].ptr_eq(&left));
+    assert!(ancestors[1].ptr_eq(&right));
+    assert!(ancestors[2].ptr_eq(&base));
+
+    // Repeating "^" is equivalent to an Arr of parents, and parent order matters for equality.
+    assert_eq!(
+        try_obj! { "^" => left.clone(), "^" => right.clone() }?,
+        child
+    );
+    assert_ne!(try_obj! { "^" => arr![right, left] }?, child);
+
+    // Parents must be Objs.
+    assert_eq!(
+        try_obj! { "^" => arr![1] },
+        Err(OverError::TypeMismatch(
+            Type::Obj,
+            Type::Arr(Box::new(Type::Int))
+        ))
+    );
+
+    Ok(())
+}
+
+#[test]
+fn flatten() {
+    let base = obj! { "a" => 1, "b" => 2 };
+    let left = obj! { "^" => base.clone(), "c" => 3 };
+    let right = obj! { "^" => base.clone(), "b" => 4 };
+    let child = obj! { "^" => arr![left.clone(), right.clone()], "a" => 5 };
+
+    let all: Vec<(Pair, Obj)> = child.iter_all().collect();
+    assert_eq!(
+        all.iter().map(|(pair, _)| pair.clone()).collect::<Vec<_>>(),
+        vec![
+            Pair("a".into(), 5.into()),
+            Pair("c".into(), 3.into()),
+            Pair("b".into(), 4.into()),
+        ]
+    );
+    assert!(all[0].1.ptr_eq(&child));
+    assert!(all[1].1.ptr_eq(&left));
+    assert!(all[2].1.ptr_eq(&right));
+
+    assert_eq!(child.keys_all().collect::<Vec<_>>(), vec!["a", "c", "b"]);
+    assert!(child.contains_all("b"));
+    assert!(!child.contains("b"));
+    assert!(!child.contains_all("d"));
+
+    let flat = child.flatten();
+    assert!(!flat.has_parent());
+    assert_eq!(flat, obj! { "a" => 5, "c" => 3, "b" => 4 });
+    assert_eq!(obj! {}.flatten(), obj! {});
+}
+
+// Test that large Objs, whose fields are looked up through an index, behave like small ones.
+#[test]
+fn large_obj() -> OverResult<()> {
+    let n = 5_000;
+    let pairs: Vec<Pair> = (0..n)
+        .map(|i| Pair(format!("field{}", i), i.into()))
+        .collect();
+    let obj = Obj::from_pairs(pairs.clone(), None)?;
+
+    assert_eq!(obj.len(), n);
+    assert_eq!(obj.get_int("field0")?, int!(0));
+    assert_eq!(obj.get_int("field4999")?, int!(4999));
+    assert!(obj.contains("field2500"));
+    assert!(!obj.contains("field5000"));
+    assert_eq!(obj.pairs_ref(), &pairs);
+
+    // Merging keeps the order of `self`, with new fields at the end.
+    let other = obj! { "field3" => "three", "extra" => true };
+    let merged = obj.merge(&other);
+    assert_eq!(merged.len(), n + 1);
+    assert_eq!(merged.get_str("field3")?, "three");
+    assert_eq!(merged.pairs_ref()[3].0, "field3");
+    assert_eq!(merged.pairs_ref()[n].0, "extra");
+    assert!(merged.get_bool("extra")?);
+
+    let removed = merged.remove_fields(&["field1", "extra"]);
+    assert_eq!(removed.len(), n - 1);
+    assert!(!removed.contains("field1"));
+    assert_eq!(removed.get_int("field2")?, int!(2));
+
+    // Parsing detects duplicates and finds variables among many fields.
+    let contents: String = (0..n)
+        .map(|i| format!("field{}: {}\n", i, i))
+        .collect::<String>()
+        + "last: field4000";
+    let parsed = Obj::from_str(&contents)?;
+    assert_eq!(parsed.get_int("last")?, int!(4000));
+    assert_eq!(
+        Obj::from_str(&(contents + "\nfield123: 0")),
+        Err(OverError::ParseError(
+            "Duplicate field \"field123\" at line 5002, column 1".into()
+        ))
+    );
+
+    Ok(())
+}
@@ -4,7 +4,16 @@ use crate::{
     parse::format::Format, types::Type, value::Value, OverError, OverResult, ReferenceType,
     INDENT_STEP,
 };
-use std::{fmt, slice::Iter, sync::Arc};
+use std::{
+    cmp::Ordering,
+    fmt,
+    hash::{Hash, Hasher},
+    iter::FromIterator,
+    ops::Index,
+    slice::Iter,
+    sync::Arc,
+    vec,
+};
 
 #[derive(Clone, Debug)]
 struct TupInner {
@@ -82,7 +91,7 @@ impl Tup {
     }
 
     /// Returns an iterator over the Tup.
-    pub fn iter(&self) -> Iter<Value> {
+    pub fn iter(&self) -> Iter<'_, Value> {
         self.values_ref().iter()
     }
 }
@@ -119,15 +128,73 @@ impl From<Vec<Value>> for Tup {
     }
 }
 
-impl PartialEq for Tup {
-    fn eq(&self, other: &Self) -> bool {
-        // Quickly return false if the types don't match.
-        if self.inner.inner_tvec != other.inner.inner_tvec {
-            return false;
+impl FromIterator<Value> for Tup {
+    fn from_iter<I: IntoIterator<Item = Value>>(iter: I) -> Self {
+        Self::from_values(iter.into_iter().collect())
+    }
+}
+
+/// Returns the value at `index`.
+///
+/// # Panics
+///
+/// Panics if `index` is out of bounds. Use `get` to return an error instead.
+impl Index<usize> for Tup {
+    type Output = Value;
+
+    fn index(&self, index: usize) -> &Value {
+        &self.inner.values[index]
+    }
+}
+
+impl<'a> IntoIterator for &'a Tup {
+    type Item = &'a Value;
+    type IntoIter = Iter<'a, Value>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Moves the values out of the `Tup`, cloning them if it is shared.
+impl IntoIterator for Tup {
+    type Item = Value;
+    type IntoIter = vec::IntoIter<Value>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        match Arc::try_unwrap(self.inner) {
+            Ok(inner) => inner.values,
+            Err(inner) => inner.values.clone(),
         }
+        .into_iter()
+    }
+}
 
+/// Two `Tup`s are equal if they have equal values. The element types are not compared, as they
+/// follow from the values, except for empty `Arr`s, which are equal whatever their type.
+impl PartialEq for Tup {
+    fn eq(&self, other: &Self) -> bool {
         self.inner.values == other.inner.values
     }
 }
 
 impl Eq for Tup {}
+
+impl Hash for Tup {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.inner.values.hash(state)
+    }
+}
+
+/// `Tup`s are ordered by comparing their values in order, as for slices.
+impl Ord for Tup {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.inner.values.cmp(&other.inner.values)
+    }
+}
+
+impl PartialOrd for Tup {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
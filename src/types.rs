@@ -1,6 +1,10 @@
 //! Module for types.
 
-use std::fmt;
+use std::{
+    cmp::Ordering,
+    fmt,
+    hash::{Hash, Hasher},
+};
 
 /// Enum of possible types for `Value`s.
 #[derive(Clone, Debug)]
@@ -34,56 +38,14 @@ impl Type {
         use self::Type::*;
 
         match *self {
-            Any => {
-                if let Any = *other {
-                    true
-                } else {
-                    false
-                }
-            }
+            Any => matches!(*other, Any),
 
-            Null => {
-                if let Null = *other {
-                    true
-                } else {
-                    false
-                }
-            }
-            Bool => {
-                if let Bool = *other {
-                    true
-                } else {
-                    false
-                }
-            }
-            Int => {
-                if let Int = *other {
-                    true
-                } else {
-                    false
-                }
-            }
-            Frac => {
-                if let Frac = *other {
-                    true
-                } else {
-                    false
-                }
-            }
-            Str => {
-                if let Str = *other {
-                    true
-                } else {
-                    false
-                }
-            }
-            Obj => {
-                if let Obj = *other {
-                    true
-                } else {
-                    false
-                }
-            }
+            Null => matches!(*other, Null),
+            Bool => matches!(*other, Bool),
+            Int => matches!(*other, Int),
+            Frac => matches!(*other, Frac),
+            Str => matches!(*other, Str),
+            Obj => matches!(*other, Obj),
 
             Arr(ref t1) => {
                 if let Arr(ref t2) = *other {
@@ -106,12 +68,68 @@ impl Type {
         }
     }
 
+    /// Returns this `Type` wrapped in a `StrictType`, which can be hashed and ordered.
+    pub fn strict(self) -> StrictType {
+        StrictType(self)
+    }
+
+    // Returns the position of this type's variant in the declaration order.
+    fn rank(&self) -> u8 {
+        use self::Type::*;
+
+        match *self {
+            Any => 0,
+            Null => 1,
+            Bool => 2,
+            Int => 3,
+            Frac => 4,
+            Str => 5,
+            Arr(_) => 6,
+            Tup(_) => 7,
+            Obj => 8,
+        }
+    }
+
+    // Compares two types strictly, first by variant and then by inner types.
+    fn cmp_strict(&self, other: &Type) -> Ordering {
+        use self::Type::*;
+
+        match (self, other) {
+            (Arr(t1), Arr(t2)) => t1.cmp_strict(t2),
+            (Tup(tvec1), Tup(tvec2)) => {
+                for (t1, t2) in tvec1.iter().zip(tvec2.iter()) {
+                    match t1.cmp_strict(t2) {
+                        Ordering::Equal => (),
+                        ordering => return ordering,
+                    }
+                }
+                tvec1.len().cmp(&tvec2.len())
+            }
+            _ => self.rank().cmp(&other.rank()),
+        }
+    }
+
+    fn hash_strict<H: Hasher>(&self, state: &mut H) {
+        self.rank().hash(state);
+
+        match *self {
+            Self::Arr(ref t) => t.hash_strict(state),
+            Self::Tup(ref tvec) => {
+                tvec.len().hash(state);
+                for t in tvec {
+                    t.hash_strict(state);
+                }
+            }
+            _ => (),
+        }
+    }
+
     /// Returns true if this `Type` contains `Any`.
     pub fn has_any(&self) -> bool {
         match *self {
             Self::Any => true,
             Self::Arr(ref t) => Self::has_any(t),
-            Self::Tup(ref tvec) => tvec.iter().any(|t| Self::has_any(t)),
+            Self::Tup(ref tvec) => tvec.iter().any(Self::has_any),
             _ => false,
         }
     }
@@ -200,6 +218,9 @@ impl Type {
 
 /// Two types are considered equal if one of them is Any or they have the same variant.
 /// In the case of `Arr` and `Tup`, the inner types are recursively checked for equality.
+///
+/// This is not transitive, as `Any` equals both `Int` and `Str`, so `Type` does not implement
+/// `Hash` or `Ord`. Use `StrictType` instead.
 impl PartialEq for Type {
     fn eq(&self, other: &Self) -> bool {
         use self::Type::*;
@@ -246,7 +267,7 @@ impl fmt::Display for Type {
             Tup(ref tvec) => write!(
                 f,
                 "Tup({})",
-                match tvec.get(0) {
+                match tvec.first() {
                     Some(t1) => tvec
                         .iter()
                         .skip(1)
@@ -258,3 +279,50 @@ impl fmt::Display for Type {
         }
     }
 }
+
+/// A `Type` compared with strict equality, as with `Type::is`, so that `Any` is only equal to
+/// `Any`.
+///
+/// Unlike `Type`, whose equality treats `Any` as equal to every type, a `StrictType` can be used as
+/// a key in a `HashMap` or `BTreeMap`. Types are ordered by variant, in the order they are declared
+/// in `Type`, and then by their inner types.
+#[derive(Clone, Debug)]
+pub struct StrictType(pub Type);
+
+impl PartialEq for StrictType {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.is(&other.0)
+    }
+}
+
+impl Eq for StrictType {}
+
+impl Hash for StrictType {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash_strict(state)
+    }
+}
+
+impl Ord for StrictType {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.cmp_strict(&other.0)
+    }
+}
+
+impl PartialOrd for StrictType {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl From<Type> for StrictType {
+    fn from(t: Type) -> Self {
+        StrictType(t)
+    }
+}
+
+impl fmt::Display for StrictType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
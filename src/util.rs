@@ -25,6 +25,18 @@ macro_rules! map {
     }
 }
 
+/// Returns the dotted path to `key` inside the value at `path`, where an empty `path` is the root
+/// and an empty `key` is the value at `path` itself.
+pub fn join_path(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.into()
+    } else if key.is_empty() {
+        path.into()
+    } else {
+        format!("{}.{}", path, key)
+    }
+}
+
 /// Writes a string to a file.
 pub fn write_file_str(fname: &str, contents: &str) -> io::Result<()> {
     // Open a file in write-only mode
@@ -1,15 +1,28 @@
 //! Values.
 
 use crate::{
-    arr, error::OverError, obj, parse::format::Format, tup, types::Type, OverResult, INDENT_STEP,
+    arr,
+    convert::{self, FracPolicy},
+    error::OverError,
+    obj,
+    parse::{
+        self,
+        format::{self, Format},
+    },
+    tup,
+    types::Type,
+    OverResult, INDENT_STEP,
 };
 use num_bigint::BigInt;
 use num_rational::BigRational;
 use num_traits::ToPrimitive;
-use std::fmt;
+use std::{convert::TryFrom, fmt, ops::Index, str::FromStr};
 
 /// Enum of possible values and their inner types.
-#[derive(Clone, Debug, Eq, PartialEq)]
+///
+/// `Value`s are ordered first by variant, in the order below, and then by their contents. An `Int`
+/// is never equal to a `Frac`, so `1` and `1.0` are different keys in a map.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub enum Value {
     /// A null value.
     Null,
@@ -33,6 +46,25 @@ pub enum Value {
     Obj(obj::Obj),
 }
 
+/// How a `Frac` is rounded to a number of decimal places.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Rounding {
+    /// Rounds towards zero, so `2.5` becomes `2` and `-2.5` becomes `-2`.
+    Down,
+    /// Rounds away from zero, so `2.1` becomes `3` and `-2.1` becomes `-3`.
+    Up,
+    /// Rounds towards negative infinity, so `-2.1` becomes `-3`.
+    Floor,
+    /// Rounds towards positive infinity, so `2.1` becomes `3`.
+    Ceiling,
+    /// Rounds to the nearest number, with ties going away from zero, so `2.5` becomes `3`.
+    HalfUp,
+    /// Rounds to the nearest number, with ties going to the even one, so `2.5` becomes `2` and
+    /// `3.5` becomes `4`. This is the default.
+    #[default]
+    HalfEven,
+}
+
 macro_rules! get_fn {
     ( $doc:expr, $name:tt, $type:ty, $variant:ident ) => {
         #[doc=$doc]
@@ -43,7 +75,18 @@ macro_rules! get_fn {
                 Err(OverError::TypeMismatch(Type::$variant, self.get_type()))
             }
         }
-    }
+    };
+}
+
+macro_rules! get_int_fn {
+    ( $doc:expr, $name:tt, $type:ty, $to:ident ) => {
+        #[doc=$doc]
+        pub fn $name(&self) -> OverResult<$type> {
+            let int = self.get_int()?;
+            int.$to()
+                .ok_or_else(|| OverError::OutOfRange(int.to_string(), stringify!($type).into()))
+        }
+    };
 }
 
 impl Value {
@@ -79,13 +122,91 @@ impl Value {
         Obj
     );
 
+    get_int_fn!(
+        "Returns the `u8` contained in this `Value`. Returns an error if this `Value` is not \
+         `Int` or is out of range for `u8`.",
+        get_u8,
+        u8,
+        to_u8
+    );
+
+    get_int_fn!(
+        "Returns the `u16` contained in this `Value`. Returns an error if this `Value` is not \
+         `Int` or is out of range for `u16`.",
+        get_u16,
+        u16,
+        to_u16
+    );
+
+    get_int_fn!(
+        "Returns the `u32` contained in this `Value`. Returns an error if this `Value` is not \
+         `Int` or is out of range for `u32`.",
+        get_u32,
+        u32,
+        to_u32
+    );
+
+    get_int_fn!(
+        "Returns the `u64` contained in this `Value`. Returns an error if this `Value` is not \
+         `Int` or is out of range for `u64`.",
+        get_u64,
+        u64,
+        to_u64
+    );
+
+    get_int_fn!(
+        "Returns the `usize` contained in this `Value`. Returns an error if this `Value` is not \
+         `Int` or is out of range for `usize`.",
+        get_usize,
+        usize,
+        to_usize
+    );
+
+    get_int_fn!(
+        "Returns the `i8` contained in this `Value`. Returns an error if this `Value` is not \
+         `Int` or is out of range for `i8`.",
+        get_i8,
+        i8,
+        to_i8
+    );
+
+    get_int_fn!(
+        "Returns the `i16` contained in this `Value`. Returns an error if this `Value` is not \
+         `Int` or is out of range for `i16`.",
+        get_i16,
+        i16,
+        to_i16
+    );
+
+    get_int_fn!(
+        "Returns the `i32` contained in this `Value`. Returns an error if this `Value` is not \
+         `Int` or is out of range for `i32`.",
+        get_i32,
+        i32,
+        to_i32
+    );
+
+    get_int_fn!(
+        "Returns the `i64` contained in this `Value`. Returns an error if this `Value` is not \
+         `Int` or is out of range for `i64`.",
+        get_i64,
+        i64,
+        to_i64
+    );
+
+    /// Returns the `Int` or `Frac` contained in this `Value` as an `f64`.
+    ///
+    /// The result is rounded to the nearest `f64`, with ties going to the one with an even last
+    /// digit, so `1/3` becomes `0.3333333333333333`. Numbers too small for an `f64` become `0.0`.
+    /// Returns an error if this `Value` is not `Int` or `Frac`, or if it is too large for an `f64`.
+    /// To reject numbers which are not exactly an `f64`, use `convert::frac_to_f64`.
+    pub fn get_f64(&self) -> OverResult<f64> {
+        convert::frac_to_f64(self, FracPolicy::Nearest)
+    }
+
     /// Returns true if this `Value` is null.
     pub fn is_null(&self) -> bool {
-        if let Self::Null = *self {
-            true
-        } else {
-            false
-        }
+        matches!(*self, Self::Null)
     }
 
     /// Returns the `Type` of this `Value`.
@@ -136,6 +257,120 @@ impl Value {
             Err(OverError::TypeMismatch(Type::Tup(vec![]), self.get_type()))
         }
     }
+
+    /// Returns a `Frac` with the exact binary value of `float`, so `0.1` becomes
+    /// `3602879701896397/36028797018963968`. Returns an error if `float` is NaN or infinite.
+    pub fn frac_from_f64_exact(float: f64) -> OverResult<Value> {
+        BigRational::from_float(float)
+            .map(Value::Frac)
+            .ok_or_else(|| not_a_frac(float))
+    }
+
+    /// Returns a `Frac` with the exact binary value of `float`. See `frac_from_f64_exact`.
+    pub fn frac_from_f32_exact(float: f32) -> OverResult<Value> {
+        BigRational::from_float(float)
+            .map(Value::Frac)
+            .ok_or_else(|| not_a_frac(float))
+    }
+
+    /// Returns a `Frac` with the shortest decimal which converts back to `float`, so `0.1` becomes
+    /// `1/10`. Returns an error if `float` is NaN or infinite.
+    pub fn frac_from_f64_shortest(float: f64) -> OverResult<Value> {
+        if !float.is_finite() {
+            return Err(not_a_frac(float));
+        }
+        Self::frac_from_decimal_str(&float.to_string())
+    }
+
+    /// Returns a `Frac` with the shortest decimal which converts back to `float`. See
+    /// `frac_from_f64_shortest`.
+    pub fn frac_from_f32_shortest(float: f32) -> OverResult<Value> {
+        if !float.is_finite() {
+            return Err(not_a_frac(float));
+        }
+        Self::frac_from_decimal_str(&float.to_string())
+    }
+
+    /// Parses a decimal number such as `"133.70"` or `"-1_000,5"` to a `Frac`, following the rules
+    /// for numbers in OVER files. A single leading `+` or `-` is allowed. Numbers without a
+    /// decimal point, such as `"5"`, also give a `Frac`.
+    pub fn frac_from_decimal_str(s: &str) -> OverResult<Value> {
+        let (negative, digits) = match s.as_bytes().first() {
+            Some(b'-') => (true, &s[1..]),
+            Some(b'+') => (false, &s[1..]),
+            _ => (false, s),
+        };
+
+        let frac = match parse::util::parse_unsigned_numeric(digits) {
+            Ok(Value::Int(int)) => frac!(int, 1),
+            Ok(Value::Frac(frac)) => frac,
+            _ => {
+                return Err(OverError::InvalidValue(
+                    String::new(),
+                    format!("{:?} is not a decimal number", s),
+                ))
+            }
+        };
+
+        Ok(Value::Frac(if negative { -frac } else { frac }))
+    }
+
+    /// Returns the `Int` or `Frac` in this `Value` as a `Frac` rounded to `precision` decimal
+    /// places with `rounding`. Returns an error if this `Value` is not `Int` or `Frac`.
+    pub fn round_frac(&self, precision: usize, rounding: Rounding) -> OverResult<Value> {
+        let frac = self.get_frac()?;
+        Ok(format::round_frac(&frac, precision, rounding).into())
+    }
+
+    /// Writes the `Int` or `Frac` in this `Value` as a decimal with exactly `precision` decimal
+    /// places, rounding it with `rounding`, so `1/3` with a precision of `2` gives `"0.33"`.
+    /// Returns an error if this `Value` is not `Int` or `Frac`.
+    pub fn format_frac(&self, precision: usize, rounding: Rounding) -> OverResult<String> {
+        let frac = self.get_frac()?;
+        Ok(format::format_decimal(&frac, precision, rounding))
+    }
+}
+
+fn not_a_frac<T: fmt::Display>(float: T) -> OverError {
+    OverError::InvalidValue(String::new(), format!("{} is not a Frac", float))
+}
+
+// Returned when indexing into a `Value` which does not contain the index.
+static NULL: Value = Value::Null;
+
+/// Returns the element at `index` if this `Value` is an `Arr` or `Tup`.
+///
+/// Never panics: returns `Null` if this `Value` is not an `Arr` or `Tup` or if `index` is out of
+/// bounds. Indexing can therefore be chained, as in `value[0][1]`. Use `get_arr` or `get_tup` to
+/// tell a missing element apart from a `Null` one.
+impl Index<usize> for Value {
+    type Output = Value;
+
+    fn index(&self, index: usize) -> &Value {
+        let values = match *self {
+            Self::Arr(ref arr) => arr.values_ref(),
+            Self::Tup(ref tup) => tup.values_ref(),
+            _ => return &NULL,
+        };
+
+        values.get(index).unwrap_or(&NULL)
+    }
+}
+
+/// Returns the value of `field`, which may be inherited, if this `Value` is an `Obj`.
+///
+/// Never panics: returns `Null` if this `Value` is not an `Obj` or if `field` is not found.
+/// Indexing can therefore be chained, as in `value["a"]["b"]`. Use `get_obj` to tell a missing
+/// field apart from a `Null` one.
+impl Index<&str> for Value {
+    type Output = Value;
+
+    fn index(&self, field: &str) -> &Value {
+        match *self {
+            Self::Obj(ref obj) => obj.get_ref(field).unwrap_or(&NULL),
+            _ => &NULL,
+        }
+    }
 }
 
 impl fmt::Display for Value {
@@ -144,6 +379,15 @@ impl fmt::Display for Value {
     }
 }
 
+/// Parses a single value in .over syntax, e.g. `[1 2 3]` or `{ a: "b" }`.
+impl FromStr for Value {
+    type Err = OverError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(parse::load_value_from_str(s)?)
+    }
+}
+
 // impl PartialEq
 
 macro_rules! impl_eq {
@@ -176,7 +420,7 @@ impl_eq!(Arr, arr::Arr);
 impl_eq!(Tup, tup::Tup);
 impl_eq!(Obj, obj::Obj);
 
-impl<'a> PartialEq<&'a str> for Value {
+impl PartialEq<&str> for Value {
     fn eq(&self, other: &&str) -> bool {
         match *self {
             Self::Str(ref value) => value == other,
@@ -185,7 +429,7 @@ impl<'a> PartialEq<&'a str> for Value {
     }
 }
 
-impl<'a> PartialEq<Value> for &'a str {
+impl PartialEq<Value> for &str {
     fn eq(&self, other: &Value) -> bool {
         match *other {
             Value::Str(ref value) => value == self,
@@ -259,7 +503,8 @@ impl_from!(i32, Int);
 impl_from!(i64, Int);
 impl_from!(BigInt, Int);
 
-// This is commented because the resultant values don't pass equality checks.
+// This is commented because the resultant values don't pass equality checks. Use
+// `Value::frac_from_f64_exact` or `Value::frac_from_f64_shortest` instead.
 //
 // impl From<f32> for Value {
 //     fn from(inner: f32) -> Self {
@@ -274,7 +519,7 @@ impl_from!(BigInt, Int);
 impl_from!(BigRational, Frac);
 
 impl_from!(String, Str);
-impl<'a> From<&'a str> for Value {
+impl From<&str> for Value {
     fn from(inner: &str) -> Self {
         Self::Str(inner.into())
     }
@@ -285,3 +530,72 @@ impl_from!(arr::Arr, Arr);
 impl_from!(tup::Tup, Tup);
 
 impl_from!(obj::Obj, Obj);
+
+// impl TryFrom
+
+macro_rules! impl_try_from {
+    ($type:ty, $fn:tt) => {
+        impl TryFrom<Value> for $type {
+            type Error = OverError;
+
+            fn try_from(value: Value) -> Result<Self, Self::Error> {
+                value.$fn()
+            }
+        }
+    };
+}
+
+impl_try_from!(bool, get_bool);
+
+impl_try_from!(usize, get_usize);
+impl_try_from!(u8, get_u8);
+impl_try_from!(u16, get_u16);
+impl_try_from!(u32, get_u32);
+impl_try_from!(u64, get_u64);
+impl_try_from!(i8, get_i8);
+impl_try_from!(i16, get_i16);
+impl_try_from!(i32, get_i32);
+impl_try_from!(i64, get_i64);
+impl_try_from!(BigInt, get_int);
+
+impl_try_from!(f64, get_f64);
+impl_try_from!(BigRational, get_frac);
+
+impl_try_from!(String, get_str);
+
+/// Converts each element of an `Arr`.
+impl<T> TryFrom<Value> for Vec<T>
+where
+    T: TryFrom<Value, Error = OverError>,
+{
+    type Error = OverError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        value.get_arr()?.iter().cloned().map(T::try_from).collect()
+    }
+}
+
+macro_rules! impl_try_from_tup {
+    ( $len:expr; $( $name:ident ),+ ) => {
+        /// Converts each element of a `Tup` with the same length.
+        impl<$( $name ),+> TryFrom<Value> for ( $( $name, )+ )
+        where
+            $( $name: TryFrom<Value, Error = OverError> ),+
+        {
+            type Error = OverError;
+
+            fn try_from(value: Value) -> Result<Self, Self::Error> {
+                let mut values = convert::tup_values(&value, $len)?.into_iter();
+
+                Ok(( $( $name::try_from(values.next().unwrap())?, )+ ))
+            }
+        }
+    };
+}
+
+impl_try_from_tup!(1; A);
+impl_try_from_tup!(2; A, B);
+impl_try_from_tup!(3; A, B, C);
+impl_try_from_tup!(4; A, B, C, D);
+impl_try_from_tup!(5; A, B, C, D, E);
+impl_try_from_tup!(6; A, B, C, D, E, F);
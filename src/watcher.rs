@@ -0,0 +1,229 @@
+//! Reloading an .over file when it or any file it includes changes.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use over::{context::ParseContext, watcher::Watcher};
+//! use std::time::Duration;
+//!
+//! let (watcher, receiver) =
+//!     Watcher::with_channel("config.over", ParseContext::new(), Duration::from_secs(1)).unwrap();
+//!
+//! for result in receiver {
+//!     match result {
+//!         Ok(obj) => println!("Reloaded: {}", obj),
+//!         Err(e) => println!("Error, keeping the last good config: {}", e),
+//!     }
+//! }
+//! # drop(watcher);
+//! ```
+
+use crate::{context::ParseContext, obj::Obj, parse, OverResult};
+use std::{
+    collections::HashMap,
+    fs,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant, SystemTime},
+};
+
+// Modification time and length of a file, or `None` if it could not be read.
+type Stamp = Option<(SystemTime, u64)>;
+
+/// Watches an .over file and every file it includes, parsing it again whenever any of them
+/// change.
+///
+/// Files are polled in a background thread at a fixed interval. After each reload, the new `Obj`
+/// or the error is delivered to a callback. On error, the last successfully parsed `Obj` is kept
+/// and remains available through `current`; the watched files are then those of the last
+/// successful parse together with any file the failed parse reached.
+///
+/// The background thread is stopped when the `Watcher` is dropped.
+#[derive(Debug)]
+pub struct Watcher {
+    // The last successfully parsed `Obj`.
+    current: Arc<Mutex<Obj>>,
+    // Set to stop the background thread.
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl Watcher {
+    /// Parses the file at `path` with the given context and starts watching it and its includes,
+    /// checking for changes every `interval` and calling `callback` with the result of each
+    /// reload.
+    ///
+    /// Returns an error if the initial parse fails.
+    pub fn new<F>(
+        path: &str,
+        ctx: ParseContext,
+        interval: Duration,
+        callback: F,
+    ) -> OverResult<Self>
+    where
+        F: FnMut(OverResult<Obj>) + Send + 'static,
+    {
+        let (obj, deps) = Obj::from_file_with_context_and_deps(path, &ctx)?;
+
+        let current = Arc::new(Mutex::new(obj));
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let mut state = State {
+            path: path.into(),
+            ctx,
+            stamps: stamps(path, &deps),
+            deps,
+            current: current.clone(),
+            callback,
+        };
+        let thread_stop = stop.clone();
+        let handle = thread::spawn(move || {
+            while !thread_stop.load(Ordering::SeqCst) {
+                let deadline = Instant::now() + interval;
+                while let Some(timeout) = deadline.checked_duration_since(Instant::now()) {
+                    thread::park_timeout(timeout);
+                    if thread_stop.load(Ordering::SeqCst) {
+                        return;
+                    }
+                }
+
+                state.poll();
+            }
+        });
+
+        Ok(Self {
+            current,
+            stop,
+            handle: Some(handle),
+        })
+    }
+
+    /// Like `new`, but delivers the result of each reload through the returned channel instead of
+    /// a callback.
+    pub fn with_channel(
+        path: &str,
+        ctx: ParseContext,
+        interval: Duration,
+    ) -> OverResult<(Self, Receiver<OverResult<Obj>>)> {
+        let (sender, receiver) = mpsc::channel();
+        let watcher = Self::new(path, ctx, interval, move |result| {
+            // The receiver may have been dropped; there is nobody left to tell.
+            let _ = sender.send(result);
+        })?;
+
+        Ok((watcher, receiver))
+    }
+
+    /// Returns the last successfully parsed `Obj`.
+    pub fn current(&self) -> Obj {
+        self.current.lock().unwrap().clone()
+    }
+}
+
+impl Drop for Watcher {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+
+        if let Some(handle) = self.handle.take() {
+            handle.thread().unpark();
+            let _ = handle.join();
+        }
+    }
+}
+
+// State owned by the background thread.
+struct State<F> {
+    path: String,
+    ctx: ParseContext,
+    // Files the last good parse depended on, plus any reached by failed parses since.
+    deps: Vec<String>,
+    // Stamps of the watched files as of the last reload.
+    stamps: Vec<Stamp>,
+    current: Arc<Mutex<Obj>>,
+    callback: F,
+}
+
+impl<F> State<F>
+where
+    F: FnMut(OverResult<Obj>),
+{
+    // Reloads the file if any watched file changed.
+    fn poll(&mut self) {
+        let new_stamps = stamps(&self.path, &self.deps);
+        if new_stamps == self.stamps {
+            return;
+        }
+        self.stamps = new_stamps;
+
+        let mut deps = Vec::new();
+        let result = parse::load_from_file_tracking_deps(&self.path, &self.ctx, &mut deps);
+        match result {
+            Ok(obj) => {
+                if deps != self.deps {
+                    self.stamps = self.restamp(&deps);
+                    self.deps = deps;
+                }
+                *self.current.lock().unwrap() = obj.clone();
+                (self.callback)(Ok(obj));
+            }
+            Err(e) => {
+                // Keep watching the files of the last good parse, together with any file the
+                // failed parse reached, so that fixing any of them triggers a reload.
+                let mut all_deps = self.deps.clone();
+                for dep in deps {
+                    if !all_deps.contains(&dep) {
+                        all_deps.push(dep);
+                    }
+                }
+                if all_deps.len() != self.deps.len() {
+                    all_deps.sort();
+                    self.stamps = self.restamp(&all_deps);
+                    self.deps = all_deps;
+                }
+                (self.callback)(Err(e.into()));
+            }
+        }
+    }
+
+    // Returns the stamps of the watched file and `deps`. Files which were already watched keep
+    // the stamps taken before parsing, so that changes made to them while parsing still trigger a
+    // reload. Only newly added files are checked now, so a change made to one of them between
+    // being read and being checked is missed.
+    fn restamp(&self, deps: &[String]) -> Vec<Stamp> {
+        let old: HashMap<&str, Stamp> = self
+            .deps
+            .iter()
+            .map(String::as_str)
+            .zip(self.stamps[1..].iter().copied())
+            .collect();
+
+        let mut new_stamps = vec![self.stamps[0]];
+        for dep in deps {
+            new_stamps.push(match old.get(dep.as_str()) {
+                Some(&stamp) => stamp,
+                None => stamp(dep),
+            });
+        }
+
+        new_stamps
+    }
+}
+
+// Returns the stamps of the watched file followed by those of its dependencies.
+fn stamps(path: &str, deps: &[String]) -> Vec<Stamp> {
+    std::iter::once(path)
+        .chain(deps.iter().map(String::as_str))
+        .map(stamp)
+        .collect()
+}
+
+// Returns the modification time and length of the file at `path`.
+fn stamp(path: &str) -> Stamp {
+    fs::metadata(path)
+        .and_then(|metadata| Ok((metadata.modified()?, metadata.len())))
+        .ok()
+}
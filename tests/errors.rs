@@ -131,6 +131,11 @@ fn errors() {
         "Invalid character \'\\n\' for value at line 1, column 7",
         "Invalid character \'\\r\' for value at line 1, column 7"
     );
+    error_helper!(
+        "env.over",
+        "Environment variable access at line 1, column 7 is not allowed; enable it with the \
+         `allow_env` parse option"
+    );
     error_helper!(
         "escape.over",
         "Invalid escape character \'a\' following backslash at line 1, column 12. If you meant to \
@@ -144,6 +149,22 @@ fn errors() {
         "field_obj.over",
         "Invalid field name \"Obj\" at line 1, column 1"
     );
+    error_helper!(
+        "function_arity.over",
+        "Function \"len\" at line 1, column 4 takes 1 argument; found 2"
+    );
+    error_helper!(
+        "function_error.over",
+        "Error calling function \"range\" at line 1, column 4: Step must not be zero"
+    );
+    error_helper!(
+        "function_type.over",
+        "Function \"upper\" expected Str at line 2, column 10; found Int"
+    );
+    error_helper!(
+        "function_unknown.over",
+        "Function \"foo\" at line 1, column 4 could not be found"
+    );
     error_helper!(
         "fuzz1.over",
         "Invalid closing bracket ')' at line 20, column 1; expected \']\'"
@@ -172,7 +193,8 @@ fn errors() {
     );
     error_helper!(
         "fuzz8.over",
-        "Invalid character \'\\\"\' for value at line 34, column 3"
+        "Invalid character \'\\\"\' for value at line 34, column 3",
+        "Invalid character \'\"\' for value at line 34, column 3"
     );
     error_helper!(
         "fuzz9.over",
@@ -195,7 +217,8 @@ fn errors() {
     );
     error_helper!(
         "include1.over",
-        "Invalid character \'\\\"\' for value at line 1, column 14"
+        "Invalid character \'\\\"\' for value at line 1, column 14",
+        "Invalid character \'\"\' for value at line 1, column 14"
     );
     error_helper!(
         "include2.over",
@@ -226,6 +249,11 @@ fn errors() {
         "include_self.over",
         "Tried to cyclically include file \"include_self.over\" at line 1, column 11"
     );
+    error_helper!(
+        "merge_conflict.over",
+        "Error calling function \"merge\" at line 1, column 4: Type mismatch for field \"x\": \
+         expected Int, found Str"
+    );
     error_helper!(
         "op_arr.over",
         "Could not apply operator \'+\' on types Arr(Int) and Arr(Str) at line 1, column 13"
@@ -267,3 +295,28 @@ fn errors() {
     error_helper!("unexpected_end2.over", "Unexpected end at line 3");
     error_helper!("value_amp.over", "Invalid value \"@\" at line 1, column 8");
 }
+
+// Test the positions of parse errors.
+#[test]
+fn error_positions() {
+    let position = |contents: &str| {
+        let e =
+            Obj::parse_str_in_file(contents, Some("tests/test_files/unsaved.over")).unwrap_err();
+        (e.file().map(String::from), e.line(), e.col())
+    };
+    let file = Some(String::from("tests/test_files/unsaved.over"));
+
+    assert_eq!(position("a: 1\nb: c"), (file.clone(), Some(2), Some(4)));
+    assert_eq!(
+        position("t: (1 2)\nb: t.5"),
+        (file.clone(), Some(2), Some(6))
+    );
+    assert_eq!(position("a: {\n"), (file, Some(2), None));
+
+    let e = Obj::parse_str_in_file("a: 1\nb: c", None).unwrap_err();
+    assert_eq!(e.file(), None);
+    assert_eq!(
+        e.message(),
+        "Variable \"c\" at line 2, column 4 could not be found"
+    );
+}
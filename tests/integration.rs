@@ -8,15 +8,37 @@ extern crate over;
 
 mod errors;
 
+use num_bigint::BigInt;
 use num_traits::ToPrimitive;
 use over::{
+    args,
+    arr::Arr,
+    context::{ParseContext, ParseOptions},
+    diff::{self, Change, DiffOptions},
+    document::{Document, NodeKind},
+    layered::{ArrMerge, Layered},
+    lint::{self, LintOptions, Rule},
+    loader::Loader,
     obj::{Obj, Pair},
-    types::Type,
-    value::Value,
-    OverResult, ReferenceType,
+    patch::{Op, Patch},
+    tup::Tup,
+    types::{StrictType, Type},
+    value::{Rounding, Value},
+    watcher::Watcher,
+    OverError, OverResult, ReferenceType,
 };
 #[cfg(test)]
 use pretty_assertions::assert_eq;
+use std::{
+    convert::TryFrom,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
 
 // Make comparisons with ints a bit more concise.
 fn get_int(obj: &Obj, field: &str) -> i64 {
@@ -142,7 +164,7 @@ fn obj() {
     assert_eq!(bools1.num_references(), 5); // Include the reference created above.
 
     let outie = obj.get_obj("outie").unwrap();
-    assert_eq!(outie.get_parent().unwrap(), bools);
+    assert_eq!(outie.parents_ref()[0], bools);
     assert_eq!(get_int(&outie, "z"), 0);
     assert_eq!(outie.num_references(), 2); // Include the reference created above.
 
@@ -247,6 +269,460 @@ fn numbers() -> OverResult<()> {
     Ok(())
 }
 
+// Test getting numbers as primitive types and converting values with `TryFrom`.
+#[test]
+fn primitives() -> OverResult<()> {
+    let obj = Obj::from_file("tests/test_files/numbers.over")?;
+
+    assert_eq!(obj.get_i8("neg")?, -4);
+    assert_eq!(obj.get_i64("neg")?, -4);
+    assert_eq!(
+        obj.get_u8("neg"),
+        Err(OverError::OutOfRange("-4".into(), "u8".into()))
+    );
+    assert_eq!(obj.get_f64("neg")?, -4.0);
+    assert_eq!(obj.get_f64("neg_whole_frac")?, -5.25);
+    assert_eq!(obj.get_f64("frac_from_dec")?, 1.3);
+    assert_eq!(obj.get_f64("big_frac")?, 91_577_735.000_000_000_008);
+    assert_eq!(
+        obj.get_u16("whole_frac"),
+        Err(OverError::TypeMismatch(Type::Int, Type::Frac))
+    );
+    assert_eq!(
+        obj.get_u32("missing"),
+        Err(OverError::FieldNotFound("missing".into()))
+    );
+
+    let value = Value::from(300);
+    assert_eq!(
+        value.get_u8(),
+        Err(OverError::OutOfRange("300".into(), "u8".into()))
+    );
+    assert_eq!(value.get_i16()?, 300);
+    assert_eq!(value.get_u16()?, 300);
+    assert_eq!(value.get_u32()?, 300);
+    assert_eq!(value.get_u64()?, 300);
+    assert_eq!(value.get_usize()?, 300);
+    assert_eq!(value.get_i32()?, 300);
+    assert_eq!(
+        Value::from(-129).get_i8().unwrap_err().to_string(),
+        "-129 is out of range for i8"
+    );
+
+    // Rounded to the nearest f64, with ties to even.
+    assert_eq!(Value::from(frac!(1, 3)).get_f64()?, 1.0 / 3.0);
+    assert_eq!(Value::from(frac!(-2, 3)).get_f64()?, -2.0 / 3.0);
+    assert_eq!(
+        Value::from(int!((1u64 << 53) + 1)).get_f64()?,
+        9_007_199_254_740_992.0
+    );
+    assert_eq!(
+        Value::from(int!((1u64 << 53) + 3)).get_f64()?,
+        9_007_199_254_740_996.0
+    );
+    let tiny = frac!(1, num_traits::pow(BigInt::from(10), 400));
+    assert_eq!(Value::from(tiny).get_f64()?, 0.0);
+    // Subnormals are rounded once, straight to their fewer bits: just above half of the smallest
+    // subnormal rounds up, and exactly half rounds to zero.
+    let above_half = frac!((1u64 << 59) + 1, BigInt::from(1) << 1134);
+    assert_eq!(Value::from(above_half).get_f64()?, f64::from_bits(1));
+    let half = frac!(1, BigInt::from(1) << 1075);
+    assert_eq!(Value::from(half).get_f64()?, 0.0);
+    let three_halves = frac!(3, BigInt::from(1) << 1075);
+    assert_eq!(Value::from(three_halves).get_f64()?, f64::from_bits(2));
+    let huge = Value::from(num_traits::pow(BigInt::from(10), 400));
+    assert_eq!(
+        huge.get_f64(),
+        Err(OverError::OutOfRange(huge.to_string(), "f64".into()))
+    );
+
+    let obj: Obj = "a: 7 b: \"s\" c: [1 2 3] d: (1 \"x\" 0.5) e: [(1 true) (2 false)]".parse()?;
+    assert_eq!(u16::try_from(obj.get("a").unwrap())?, 7);
+    assert_eq!(String::try_from(obj.get("b").unwrap())?, "s");
+    assert_eq!(Vec::<u8>::try_from(obj.get("c").unwrap())?, vec![1, 2, 3]);
+    assert_eq!(
+        <(i32, String, f64)>::try_from(obj.get("d").unwrap())?,
+        (1, "x".into(), 0.5)
+    );
+    assert_eq!(
+        Vec::<(u64, bool)>::try_from(obj.get("e").unwrap())?,
+        vec![(1, true), (2, false)]
+    );
+    assert_eq!(
+        <(i32, String)>::try_from(obj.get("d").unwrap()),
+        Err(OverError::TypeMismatch(
+            Type::Tup(vec![Type::Any, Type::Any]),
+            Type::Tup(vec![Type::Int, Type::Str, Type::Frac])
+        ))
+    );
+    assert_eq!(
+        String::try_from(obj.get("a").unwrap()),
+        Err(OverError::TypeMismatch(Type::Str, Type::Int))
+    );
+
+    Ok(())
+}
+
+// Test constructing Fracs from floats and decimal strings.
+#[test]
+fn frac_constructors() -> OverResult<()> {
+    assert_eq!(
+        Value::frac_from_f64_exact(0.1)?,
+        frac!(3_602_879_701_896_397u64, 36_028_797_018_963_968u64)
+    );
+    assert_eq!(Value::frac_from_f32_exact(0.25)?, frac!(1, 4));
+    assert_eq!(Value::frac_from_f64_shortest(0.1)?, frac!(1, 10));
+    assert_eq!(Value::frac_from_f64_shortest(-133.7)?, frac!(-1337, 10));
+    assert_eq!(Value::frac_from_f64_shortest(5.0)?, frac!(5, 1));
+    assert_eq!(Value::frac_from_f32_shortest(0.1)?, frac!(1, 10));
+    assert_eq!(
+        Value::frac_from_f64_shortest(1.0 / 3.0)?.get_f64()?,
+        1.0 / 3.0
+    );
+
+    assert_eq!(Value::frac_from_decimal_str("133.70")?, frac!(1337, 10));
+    assert_eq!(Value::frac_from_decimal_str("-1_000,5")?, frac!(-2001, 2));
+    assert_eq!(Value::frac_from_decimal_str("+.5")?, frac!(1, 2));
+    assert_eq!(Value::frac_from_decimal_str("7")?, frac!(7, 1));
+
+    let obj = Obj::from_str("a: 133.70")?;
+    assert_eq!(
+        obj.get("a").unwrap(),
+        Value::frac_from_decimal_str("133.70")?
+    );
+
+    for s in &["", "-", ".", "1.2.3", "1__0", "--1", "1e5", " 1"] {
+        assert_eq!(
+            Value::frac_from_decimal_str(s),
+            Err(OverError::InvalidValue(
+                String::new(),
+                format!("{:?} is not a decimal number", s)
+            ))
+        );
+    }
+    assert_eq!(
+        Value::frac_from_f64_exact(f64::INFINITY),
+        Err(OverError::InvalidValue(
+            String::new(),
+            "inf is not a Frac".into()
+        ))
+    );
+    assert_eq!(
+        Value::frac_from_f32_shortest(f32::NAN),
+        Err(OverError::InvalidValue(
+            String::new(),
+            "NaN is not a Frac".into()
+        ))
+    );
+
+    Ok(())
+}
+
+// Test writing Fracs as decimals, fractions and mixed numbers, and rounding them.
+#[test]
+fn frac_format() -> OverResult<()> {
+    let obj = Obj::from_str(
+        "a: 133.70 b: 5/1 c: -21/4 d: 1/3 e: -16/3 f: 7/3 g: -.0 h: 1/1024 i: -1/50",
+    )?;
+    assert_eq!(
+        obj.write_to_string(),
+        "a: 133.7\nb: 5.0\nc: -5.25\nd: 1/3\ne: -5-1/3\nf: 2+1/3\ng: 0.0\nh: 0.0009765625\n\
+         i: -0.02\n"
+    );
+    assert_eq!(Obj::from_str(&obj.write_to_string())?, obj);
+    assert_eq!(obj.get("a").unwrap().to_string(), "133.7");
+
+    let check = |value: Value, precision, rounding, expected: &str| -> OverResult<()> {
+        assert_eq!(value.format_frac(precision, rounding)?, expected);
+        assert_eq!(
+            value.round_frac(precision, rounding)?,
+            Value::frac_from_decimal_str(expected)?
+        );
+        Ok(())
+    };
+
+    check(frac!(1, 3).into(), 2, Rounding::HalfEven, "0.33")?;
+    check(frac!(2, 3).into(), 2, Rounding::HalfEven, "0.67")?;
+    check(frac!(1337, 10).into(), 2, Rounding::HalfEven, "133.70")?;
+    check(frac!(5, 2).into(), 0, Rounding::HalfEven, "2")?;
+    check(frac!(7, 2).into(), 0, Rounding::HalfEven, "4")?;
+    check(frac!(-5, 2).into(), 0, Rounding::HalfEven, "-2")?;
+    check(frac!(5, 2).into(), 0, Rounding::HalfUp, "3")?;
+    check(frac!(-5, 2).into(), 0, Rounding::HalfUp, "-3")?;
+    check(frac!(-21, 10).into(), 0, Rounding::Down, "-2")?;
+    check(frac!(-21, 10).into(), 0, Rounding::Up, "-3")?;
+    check(frac!(-21, 10).into(), 0, Rounding::Floor, "-3")?;
+    check(frac!(-21, 10).into(), 0, Rounding::Ceiling, "-2")?;
+    check(frac!(21, 10).into(), 0, Rounding::Up, "3")?;
+    check(frac!(-1, 1000).into(), 2, Rounding::HalfEven, "0.00")?;
+    check(frac!(-1, 8).into(), 2, Rounding::HalfEven, "-0.12")?;
+    check(int!(42).into(), 1, Rounding::default(), "42.0")?;
+
+    assert_eq!(
+        Value::from("1").format_frac(2, Rounding::HalfEven),
+        Err(OverError::TypeMismatch(Type::Frac, Type::Str))
+    );
+
+    Ok(())
+}
+
+// Test indexing, iterating over and collecting into containers.
+#[test]
+fn containers() -> OverResult<()> {
+    let obj = Obj::from_str(
+        "@base: { size: 2 } \
+         shape: { ^: @base name: \"circle\" points: [(1 2) (3 4)] } \
+         empty: []",
+    )?;
+    let shape = obj.get_obj("shape")?;
+
+    assert_eq!(shape["name"], "circle");
+    assert_eq!(shape["size"], 2);
+    assert_eq!(shape["points"][1][0], 3);
+    let points = shape.get_arr("points")?;
+    assert_eq!(points[0], tup!(1, 2));
+    assert_eq!(points[0].get_tup()?[1], 2);
+
+    // Indexing a `Value` returns `Null` for anything missing.
+    let value = Value::Obj(obj.clone());
+    assert_eq!(value["shape"]["name"], "circle");
+    assert_eq!(value["shape"]["size"], 2);
+    assert_eq!(value["shape"]["points"][0][1], 2);
+    assert!(value["shape"]["points"][2].is_null());
+    assert!(value["shape"]["missing"]["a"].is_null());
+    assert!(value["empty"][0].is_null());
+    assert!(value[0].is_null());
+    assert!(value["shape"]["name"]["a"].is_null());
+
+    let mut sum = 0;
+    for point in &points {
+        for n in &point.get_tup()? {
+            sum += n.get_i32()?;
+        }
+    }
+    assert_eq!(sum, 10);
+
+    let fields: Vec<&str> = (&shape).into_iter().map(|Pair(f, _)| f.as_str()).collect();
+    assert_eq!(fields, vec!["name", "points"]);
+    let pairs: Vec<Pair> = shape.clone().into_iter().collect();
+    assert_eq!(pairs[0], Pair("name".into(), "circle".into()));
+    let values: Vec<Value> = points.clone().into_iter().collect();
+    assert_eq!(values, points.values_ref().clone());
+    let values: Vec<Value> = tup!(1, "a").into_iter().collect();
+    assert_eq!(values, vec![Value::from(1), Value::from("a")]);
+
+    let tup: Tup = vec![Value::from(1), Value::from("a")].into_iter().collect();
+    assert_eq!(tup, tup!(1, "a"));
+    let arr: OverResult<Arr> = (1..4).map(Value::from).collect();
+    assert_eq!(arr?, arr![1, 2, 3]);
+    let arr: OverResult<Arr> = vec![Value::from(1), Value::from("a")].into_iter().collect();
+    assert_eq!(arr, Err(OverError::ArrTypeMismatch(Type::Int, Type::Str)));
+
+    let collected: OverResult<Obj> = vec![
+        Pair("a".into(), Value::from(1)),
+        Pair("b".into(), Value::from(2)),
+        Pair("a".into(), Value::from(3)),
+    ]
+    .into_iter()
+    .collect();
+    assert_eq!(collected?, obj! { "a" => 3, "b" => 2 });
+    let copy: OverResult<Obj> = shape.iter_all().map(|(pair, _)| pair).collect();
+    assert_eq!(copy?, shape.flatten());
+    let invalid: OverResult<Obj> = vec![Pair("1a".into(), Value::from(1))]
+        .into_iter()
+        .collect();
+    assert_eq!(invalid, Err(OverError::InvalidFieldName("1a".into())));
+    let tuples = vec![
+        ("a".to_string(), Value::from(1)),
+        ("b".to_string(), Value::from("x")),
+    ];
+    let from_tuples: OverResult<Obj> = tuples.into_iter().map(Pair::from).collect();
+    assert_eq!(from_tuples?, obj! { "a" => 1, "b" => "x" });
+    let invalid: OverResult<Obj> = vec![("a b".to_string(), Value::Null)]
+        .into_iter()
+        .map(Pair::from)
+        .collect();
+    assert_eq!(invalid, Err(OverError::InvalidFieldName("a b".into())));
+
+    Ok(())
+}
+
+// Test that indexing an `Obj` panics when the field is missing.
+#[test]
+#[should_panic(expected = "Field not found: \"missing\"")]
+fn obj_index_panics() {
+    let obj = obj! { "a" => 1 };
+    let _ = &obj["missing"];
+}
+
+// Test hashing and ordering values and types.
+#[test]
+// The field index cached by `Obj` is interior mutability, but doesn't affect hashing or ordering.
+#[allow(clippy::mutable_key_type)]
+fn hash_ord() -> OverResult<()> {
+    use std::collections::{BTreeSet, HashMap, HashSet};
+
+    let obj = Obj::from_str(
+        "@base: { a: 1 } \
+         x: { ^: @base b: 2 } \
+         y: { ^: @base b: 2 } \
+         z: { b: 2 } \
+         w: { b: 2 a: 1 }",
+    )?;
+
+    let values: Vec<Value> = vec![
+        obj.get("x").unwrap(),
+        arr![2, 3].into(),
+        tup!(1, "a").into(),
+        "b".into(),
+        frac!(1, 1).into(),
+        int!(1).into(),
+        Value::Null,
+        obj.get("z").unwrap(),
+        arr![1, 5].into(),
+        true.into(),
+        obj.get("y").unwrap(),
+        "a".into(),
+        obj.get("w").unwrap(),
+        int!(-1).into(),
+        frac!(1, 2).into(),
+        Arr::from_values_unchecked(vec![], Type::Str).into(),
+        arr![].into(),
+    ];
+
+    let sorted: BTreeSet<Value> = values.iter().cloned().collect();
+    let expected: Vec<Value> = vec![
+        Value::Null,
+        true.into(),
+        int!(-1).into(),
+        int!(1).into(),
+        frac!(1, 2).into(),
+        frac!(1, 1).into(),
+        "a".into(),
+        "b".into(),
+        arr![].into(),
+        arr![1, 5].into(),
+        arr![2, 3].into(),
+        tup!(1, "a").into(),
+        obj.get("z").unwrap(),
+        obj.get("x").unwrap(),
+        obj.get("w").unwrap(),
+    ];
+    assert_eq!(sorted.into_iter().collect::<Vec<_>>(), expected);
+
+    // Objs with equal fields and parents are the same key, but parents and field order matter.
+    let set: HashSet<Value> = values.iter().cloned().collect();
+    assert_eq!(set.len(), expected.len());
+    assert!(set.contains(&obj.get("y").unwrap()));
+    assert_ne!(obj.get("x"), obj.get("z"));
+    assert!(obj.get_obj("z")? < obj.get_obj("x")?);
+
+    // Values can be sorted, with all variants compared consistently.
+    let mut sorted = values;
+    sorted.sort();
+    sorted.dedup();
+    assert_eq!(sorted, expected);
+
+    // Tups holding empty Arrs of different types are equal, and compare and hash the same.
+    let hash = |value: &Value| {
+        use std::hash::{Hash, Hasher};
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        value.hash(&mut hasher);
+        hasher.finish()
+    };
+    let strs: Value = tup!(Arr::from_values_unchecked(vec![], Type::Str)).into();
+    let ints: Value = tup!(Arr::from_values_unchecked(vec![], Type::Int)).into();
+    assert_eq!(strs, ints);
+    assert_eq!(strs.cmp(&ints), std::cmp::Ordering::Equal);
+    assert_eq!(hash(&strs), hash(&ints));
+
+    let mut counts: HashMap<Value, usize> = HashMap::new();
+    for value in &tup!(1, 2, 1, "a") {
+        *counts.entry(value.clone()).or_default() += 1;
+    }
+    assert_eq!(counts[&int!(1).into()], 2);
+    assert_eq!(counts[&"a".into()], 1);
+
+    // Types are hashed and ordered strictly.
+    assert_eq!(Type::Any, Type::Int);
+    assert_ne!(Type::Any.strict(), Type::Int.strict());
+    let types: HashSet<StrictType> = vec![
+        Type::Any,
+        Type::Int,
+        Type::Arr(Box::new(Type::Any)),
+        Type::Arr(Box::new(Type::Int)),
+        Type::Int,
+    ]
+    .into_iter()
+    .map(StrictType::from)
+    .collect();
+    assert_eq!(types.len(), 4);
+    let types: BTreeSet<StrictType> = vec![
+        Type::Obj,
+        Type::Tup(vec![Type::Int, Type::Str]),
+        Type::Tup(vec![Type::Int]),
+        Type::Arr(Box::new(Type::Str)),
+        Type::Null,
+        Type::Any,
+    ]
+    .into_iter()
+    .map(Type::strict)
+    .collect();
+    assert_eq!(
+        types.iter().map(|t| t.to_string()).collect::<Vec<_>>(),
+        vec![
+            "Any",
+            "Null",
+            "Arr(Str)",
+            "Tup(Int)",
+            "Tup(Int, Str)",
+            "Obj"
+        ]
+    );
+
+    Ok(())
+}
+
+// Test that values are read correctly from slices of the input and that positions are reported
+// in characters, not bytes.
+#[test]
+fn lexing() -> OverResult<()> {
+    let obj = Obj::from_str(
+        "plain: \"héllo wörld\" escaped: \"a\\\"b\\\\c\\nd\" end: \"tail\\\\\"
+         under: 1_000_000 big: 123456789012345678901234567890 dec: 1_0.2_5
+         chain: \"ü\"+$ # Comment with ünïcode\n",
+    );
+    assert_eq!(
+        format!("{}", obj.unwrap_err()),
+        "Invalid character '$' for value at line 3, column 21"
+    );
+
+    let obj = Obj::from_str(
+        "plain: \"héllo wörld\" escaped: \"a\\\"b\\\\c\\nd\" end: \"tail\\\\\"
+         under: 1_000_000 big: 123456789012345678901234567890 dec: 1_0.2_5 # ünïcode\n",
+    )?;
+    assert_eq!(obj.get_str("plain")?, "héllo wörld");
+    assert_eq!(obj.get_str("escaped")?, "a\"b\\c\nd");
+    assert_eq!(obj.get_str("end")?, "tail\\");
+    assert_eq!(get_int(&obj, "under"), 1_000_000);
+    assert_eq!(
+        obj.get_int("big")?,
+        BigInt::parse_bytes(b"123456789012345678901234567890", 10).unwrap()
+    );
+    assert_eq!(obj.get_frac("dec")?, frac!(41, 4));
+
+    assert_eq!(
+        format!("{}", Obj::from_str("ä: \"ö\" b: ü$").unwrap_err()),
+        "Invalid character '$' for value at line 1, column 12"
+    );
+    assert_eq!(
+        format!("{}", Obj::from_str("a: \"unterminated\n\n").unwrap_err()),
+        "Unexpected end at line 3"
+    );
+
+    Ok(())
+}
+
 #[test]
 fn operations() -> OverResult<()> {
     let obj = Obj::from_file("tests/test_files/operations.over")?;
@@ -277,6 +753,532 @@ fn operations() -> OverResult<()> {
     Ok(())
 }
 
+#[test]
+fn functions() -> OverResult<()> {
+    let obj = Obj::from_file("tests/test_files/functions.over")?;
+
+    assert_eq!(obj.get("len_arr").unwrap(), int!(5));
+    assert_eq!(obj.get("len_str").unwrap(), int!(11));
+    assert_eq!(obj.get("len_tup").unwrap(), int!(3));
+    assert_eq!(obj.get("len_obj").unwrap(), int!(2));
+    assert_eq!(obj.get("keys").unwrap(), arr!["name", "age"]);
+
+    assert_eq!(obj.get("min").unwrap(), int!(1));
+    assert_eq!(obj.get("max").unwrap(), int!(3));
+    assert_eq!(obj.get("max_frac").unwrap(), frac!(2, 3));
+
+    assert_eq!(obj.get("floor").unwrap(), int!(3));
+    assert_eq!(obj.get("ceil").unwrap(), int!(-3));
+    assert_eq!(obj.get("round").unwrap(), int!(3));
+    assert_eq!(obj.get("round_neg").unwrap(), int!(-3));
+
+    assert_eq!(obj.get("upper").unwrap(), "HELLO");
+    assert_eq!(obj.get("lower").unwrap(), "dorothy");
+    assert_eq!(obj.get("join").unwrap(), "a, b, c");
+    assert_eq!(obj.get("split").unwrap(), arr!["a", "b", "c"]);
+
+    assert_eq!(obj.get("range1").unwrap(), arr![0, 1, 2]);
+    assert_eq!(obj.get("range2").unwrap(), arr![2, 3, 4]);
+    assert_eq!(obj.get("range3").unwrap(), arr![10, 7, 4, 1]);
+    assert_eq!(obj.get("range_empty").unwrap(), arr![]);
+    assert_eq!(
+        obj.get("range1").unwrap().get_type(),
+        Type::Arr(Box::new(Type::Int))
+    );
+    assert_eq!(
+        Obj::from_str("r: range(1_000_000)")?.get_arr("r")?.len(),
+        1_000_000
+    );
+    assert_eq!(
+        Obj::from_str("r: range(0 10000000000 2)"),
+        Err(OverError::ParseError(
+            "Error calling function \"range\" at line 1, column 4: Range would have 5000000000 \
+             elements; the maximum is 1000000"
+                .into()
+        ))
+    );
+
+    assert_eq!(obj.get("nested").unwrap(), int!(10));
+    assert_eq!(obj.get("op").unwrap(), int!(9));
+    assert_eq!(obj.get("dot").unwrap(), "age");
+
+    Ok(())
+}
+
+#[test]
+fn context() -> OverResult<()> {
+    let mut ctx = ParseContext::new();
+    ctx.set_global("build", 41)?;
+    ctx.set_global("hostname", "localhost")?;
+    ctx.set_global("features", obj! { "debug" => true })?;
+    ctx.set_function("secret", |args: &[Value]| match args {
+        [Value::Str(ref name)] => Ok(format!("secret-{}", name).into()),
+        _ => Err("expected a single Str".into()),
+    })?;
+    ctx.set_function("port_for", |args: &[Value]| match args {
+        [Value::Str(ref s)] if s == "api" => Ok(8080.into()),
+        [Value::Str(ref s)] if s == "web" => Ok(80.into()),
+        _ => Err("unknown service".into()),
+    })?;
+    // Host functions shadow built-in functions.
+    ctx.set_function("len", |_: &[Value]| Ok(0.into()))?;
+
+    let obj = Obj::from_file_with_context("tests/test_files/context.over", &ctx)?;
+
+    assert_eq!(obj.get("build").unwrap(), int!(42));
+    assert_eq!(obj.get("host").unwrap(), "localhost");
+    assert_eq!(obj.get("debug").unwrap(), true);
+    assert_eq!(obj.get("db").unwrap(), "secret-db");
+    assert_eq!(obj.get("api_port").unwrap(), int!(8080));
+    assert_eq!(obj.get("len").unwrap(), int!(0));
+    assert_eq!(
+        obj.get("sub").unwrap(),
+        obj! { "build" => 41, "port" => 80 }
+    );
+
+    // Errors.
+
+    assert!(ctx.set_global("@build", 1).is_err());
+    assert!(ctx
+        .set_function("1st", |_: &[Value]| Ok(Value::Null))
+        .is_err());
+
+    let err = Obj::from_str_with_context("port: port_for(\"db\")", &ctx).unwrap_err();
+    assert_eq!(
+        format!("{}", err),
+        "Error calling function \"port_for\" at line 1, column 7: unknown service"
+    );
+
+    let err = Obj::from_str_with_context("@build: 1", &ctx).unwrap_err();
+    assert_eq!(
+        format!("{}", err),
+        "Duplicate global \"@build\" at line 1, column 1"
+    );
+
+    // Without the context, the globals and functions are not found.
+    assert!(Obj::from_file("tests/test_files/context.over").is_err());
+
+    Ok(())
+}
+
+#[test]
+fn env() -> OverResult<()> {
+    let mut ctx = ParseContext::new();
+    ctx.set_options(ParseOptions {
+        allow_env: true,
+        ..Default::default()
+    });
+    ctx.set_env_lookup(|name: &str| {
+        let value = match name {
+            "OVER_TEST_URL" => "postgres://localhost",
+            "OVER_TEST_PORT" => " 5432 ",
+            "OVER_TEST_DEBUG" => "TRUE",
+            _ => return None,
+        };
+        Some(value.into())
+    });
+
+    let obj = Obj::from_file_with_context("tests/test_files/env.over", &ctx)?;
+
+    assert_eq!(obj.get("url").unwrap(), "postgres://localhost");
+    assert_eq!(obj.get("port").unwrap(), int!(5432));
+    assert_eq!(obj.get("debug").unwrap(), true);
+    assert_eq!(obj.get("name").unwrap(), "default");
+    assert_eq!(obj.get("workers").unwrap(), int!(8));
+
+    // Errors.
+
+    let err = Obj::from_str_with_context("url: env(\"OVER_TEST_UNSET\")", &ctx).unwrap_err();
+    assert_eq!(
+        format!("{}", err),
+        "Environment variable \"OVER_TEST_UNSET\" at line 1, column 6 is not set and has no \
+         default"
+    );
+
+    let err = Obj::from_str_with_context("url: env(\"OVER_TEST_URL\" 1)", &ctx).unwrap_err();
+    assert_eq!(
+        format!("{}", err),
+        "Environment variable \"OVER_TEST_URL\" at line 1, column 6 has value \
+         \"postgres://localhost\", which is not a valid Int"
+    );
+
+    let err = Obj::from_str_with_context("url: env(\"OVER_TEST_URL\" 1.5)", &ctx).unwrap_err();
+    assert_eq!(
+        format!("{}", err),
+        "Function \"env\" expected Str, Int or Bool at line 1, column 26; found Frac"
+    );
+
+    // Without a lookup, variables are read from the process environment, which Cargo sets up for
+    // tests.
+    let mut ctx = ParseContext::new();
+    ctx.set_options(ParseOptions {
+        allow_env: true,
+        ..Default::default()
+    });
+    let obj = Obj::from_str_with_context("name: env(\"CARGO_PKG_NAME\")", &ctx)?;
+    assert_eq!(obj.get("name").unwrap(), env!("CARGO_PKG_NAME"));
+
+    Ok(())
+}
+
+#[test]
+fn merge() -> OverResult<()> {
+    let obj = Obj::from_file("tests/test_files/merge.over")?;
+
+    let shallow = obj.get_obj("shallow")?;
+    assert_eq!(shallow.get("name").unwrap(), "base");
+    assert_eq!(shallow.get("log_level").unwrap(), "info");
+    assert_eq!(shallow.get("tls").unwrap(), obj! { "enabled" => true });
+    assert_eq!(shallow.get("timeout").unwrap(), int!(30));
+    assert_eq!(
+        shallow
+            .iter()
+            .map(|Pair(f, _)| f.as_str())
+            .collect::<Vec<_>>(),
+        vec!["name", "port", "tls", "log_level"]
+    );
+
+    let deep = obj.get_obj("deep")?;
+    assert_eq!(
+        deep.get("tls").unwrap(),
+        obj! { "enabled" => true, "cert" => "none" }
+    );
+    assert_eq!(deep.get("timeout").unwrap(), int!(30));
+
+    let removed = obj.get_obj("removed")?;
+    assert_eq!(removed.len(), 1);
+    assert!(!removed.contains("port"));
+    assert!(removed.has_parent());
+
+    assert_eq!(
+        obj.get_obj("removed_empty")?,
+        obj! { "log_level" => "info" }
+    );
+
+    // Inherited fields are still visible after removal.
+    assert_eq!(
+        obj.get_obj("removed_inherited")?.get("timeout").unwrap(),
+        int!(30)
+    );
+
+    Ok(())
+}
+
+#[test]
+fn parents() -> OverResult<()> {
+    let obj = Obj::from_file("tests/test_files/parents.over")?;
+
+    let diamond = obj.get_obj("diamond")?;
+    assert_eq!(diamond.parents_ref().len(), 2);
+    assert_eq!(diamond.get("a").unwrap(), 10);
+    assert_eq!(diamond.get("b").unwrap(), 20);
+    assert_eq!(diamond.get("c").unwrap(), 3);
+    assert_eq!(diamond.get("e").unwrap(), 5);
+
+    // The shared base is searched after both of the parents inheriting from it.
+    let ancestors = diamond.ancestors();
+    assert_eq!(ancestors.len(), 3);
+    assert!(ancestors[0].ptr_eq(&diamond.parents_ref()[0]));
+    assert!(ancestors[1].ptr_eq(&diamond.parents_ref()[1]));
+    assert_eq!(diamond.get_with_source("c").unwrap().1, ancestors[2]);
+
+    let repeated = obj.get_obj("repeated")?;
+    assert_eq!(repeated.parents_ref().len(), 3);
+    assert_eq!(repeated.get("a").unwrap(), 10);
+    assert_eq!(repeated.get("b").unwrap(), 20);
+    assert_eq!(repeated.get("d").unwrap(), 4);
+
+    // Each ancestor comes after everything inheriting from it, and otherwise in depth-first order.
+    let o = |name: &str, parents: Vec<Obj>| {
+        Obj::from_pairs_with_parents(vec![Pair("name".into(), name.into())], parents)
+    };
+    let f = o("f", vec![])?;
+    let e = o("e", vec![f.clone()])?;
+    let d = o("d", vec![f])?;
+    let c = o("c", vec![e.clone()])?;
+    let b = o("b", vec![d, e])?;
+    let a = o("a", vec![b, c])?;
+    let names: Vec<Value> = a
+        .ancestors()
+        .iter()
+        .map(|obj| obj["name"].clone())
+        .collect();
+    assert_eq!(names, vec!["b", "d", "c", "e", "f"]);
+
+    // Stacked diamonds have exponentially many paths to the base, but each ancestor is only
+    // visited once.
+    let mut top = o("base", vec![])?;
+    for _ in 0..64 {
+        let left = o("left", vec![top.clone()])?;
+        let right = o("right", vec![top])?;
+        top = o("top", vec![left, right])?;
+    }
+    assert_eq!(top.ancestors().len(), 3 * 64);
+    assert!(top.get("missing").is_none());
+    assert!(Value::Obj(top)["missing"].is_null());
+
+    // Formatting and re-parsing preserves the parents.
+    let formatted = diamond.write_to_string();
+    assert!(formatted.contains("^: ["));
+    assert_eq!(Obj::from_str(&formatted)?, diamond);
+
+    Ok(())
+}
+
+#[test]
+fn diff() -> OverResult<()> {
+    let old = Obj::from_file("tests/test_files/diff_old.over")?.into();
+    let new = Obj::from_file("tests/test_files/diff_new.over")?.into();
+
+    let diff = diff::diff(&old, &new);
+    assert_eq!(
+        diff.iter()
+            .map(|entry| entry.to_string())
+            .collect::<Vec<_>>(),
+        vec![
+            "~ servers.2.port: 8080 → 8081",
+            "~ limits.1: 200 → 250",
+            "- features.1: \"metrics\"",
+            "- settings.debug: true",
+            "+ owner: \"ops\"",
+        ]
+    );
+    assert_eq!(
+        diff.entries()[0].change,
+        Change::Changed(8080.into(), 8081.into())
+    );
+
+    // Inherited fields are only compared when requested.
+    let diff = diff::diff_with_options(&old, &new, DiffOptions { inherited: true });
+    assert_eq!(diff.len(), 6);
+    assert_eq!(diff.entries()[4].path, "settings.timeout");
+
+    // The .over rendering can be parsed back.
+    let rendered = Obj::from_str(&diff.write_to_string())?;
+    let changes = rendered.get_arr("changes")?;
+    assert_eq!(changes.len(), 6);
+    assert_eq!(
+        changes.get(0)?,
+        obj! { "path" => "servers.2.port", "kind" => "changed", "old" => 8080, "new" => 8081 }
+    );
+
+    assert!(diff::diff(&old, &old).is_empty());
+    assert_eq!(
+        diff::diff(&1.into(), &"1".into()).to_string(),
+        "~ .: 1 → \"1\"\n"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn patch() -> OverResult<()> {
+    let obj = obj! {
+        "db" => obj! { "host" => "localhost", "pool_size" => 10 },
+        "debug" => true,
+        "servers" => arr![
+            obj! { "host" => "a", "port" => 8080 },
+            obj! { "host" => "b", "port" => 8080 }
+        ],
+        "features" => arr!["auth"],
+        "limits" => tup!(100, 200),
+    };
+
+    let patch = Patch::from_file("tests/test_files/patch.over")?;
+    assert_eq!(patch.len(), 6);
+
+    let patched = patch.apply(&obj)?;
+    assert_eq!(
+        patched,
+        obj! {
+            "db" => obj! { "host" => "localhost", "pool_size" => 20 },
+            "servers" => arr![
+                obj! { "host" => "new", "port" => 80 },
+                obj! { "host" => "a", "port" => 8080 },
+                obj! { "host" => "b", "port" => 8081 }
+            ],
+            "features" => arr!["auth", "metrics"],
+            "limits" => tup!(100, "none"),
+        }
+    );
+    // The original is unchanged.
+    assert_eq!(obj.get("debug").unwrap(), true);
+
+    // Errors.
+    let check = |op: Op, error: OverError| {
+        let mut patch = Patch::new();
+        patch.push(op);
+        assert_eq!(patch.apply(&obj), Err(error));
+    };
+    check(
+        Op::Set("servers.0".into(), 1.into()),
+        OverError::ArrTypeMismatch(Type::Obj, Type::Int),
+    );
+    check(
+        Op::Insert("servers".into(), None, "c".into()),
+        OverError::ArrTypeMismatch(Type::Obj, Type::Str),
+    );
+    check(
+        Op::Insert("servers".into(), Some(3), obj! {}.into()),
+        OverError::ArrOutOfBounds(3),
+    );
+    check(
+        Op::Set("db.1host".into(), 1.into()),
+        OverError::InvalidFieldName("1host".into()),
+    );
+    check(
+        Op::Set("db.missing.port".into(), 1.into()),
+        OverError::FieldNotFound("db.missing".into()),
+    );
+    check(
+        Op::Remove("servers.first".into()),
+        OverError::InvalidPath("servers.first".into()),
+    );
+    check(
+        Op::Remove("debug.value".into()),
+        OverError::InvalidPath("debug.value".into()),
+    );
+    check(
+        Op::Remove("db..host".into()),
+        OverError::InvalidPath("db..host".into()),
+    );
+    check(
+        Op::Insert("debug".into(), None, 1.into()),
+        OverError::TypeMismatch(Type::Arr(Box::new(Type::Any)), Type::Bool),
+    );
+
+    assert_eq!(
+        Patch::from_str("ops: [{ op: \"rename\" path: \"db\" }]"),
+        Err(OverError::InvalidPatchOp("rename".into()))
+    );
+
+    Ok(())
+}
+
+#[test]
+fn layered() -> OverResult<()> {
+    let mut layered = Layered::new();
+    layered.add_file("tests/test_files/layers/base.over");
+    layered.add_file("tests/test_files/layers/prod.over");
+    layered.add_str("local", "db: { pool_size: 2 }");
+
+    let config = layered.load()?;
+    assert_eq!(
+        config.obj(),
+        &obj! {
+            "name" => "service",
+            "db" => obj! {
+                "host" => "db.internal",
+                "port" => 5432,
+                "pool_size" => 2,
+                "replicas" => tup!("r1", "r2"),
+            },
+            "features" => arr!["metrics"],
+            "servers" => arr![obj! { "host" => "prod" }],
+        }
+    );
+
+    let base = "tests/test_files/layers/base.over";
+    let prod = "tests/test_files/layers/prod.over";
+    assert_eq!(config.layer_names(), &vec![base, prod, "local"]);
+    assert_eq!(config.source_of("name"), Some(base));
+    assert_eq!(config.source_of("db"), Some("local"));
+    assert_eq!(config.source_of("db.host"), Some(prod));
+    assert_eq!(config.source_of("db.port"), Some(base));
+    assert_eq!(config.layer_of("db.pool_size"), Some(2));
+    assert_eq!(config.source_of("db.replicas.1"), Some(prod));
+    assert_eq!(config.source_of("servers.0.host"), Some(prod));
+    // Replaced elements are forgotten.
+    assert_eq!(config.source_of("servers.1"), None);
+    assert_eq!(config.source_of("missing"), None);
+
+    // Append Arrs instead of replacing them.
+    layered.set_arr_merge(ArrMerge::Append);
+    let config = layered.load()?;
+    assert_eq!(
+        config.obj().get("features").unwrap(),
+        arr!["auth", "metrics"]
+    );
+    assert_eq!(config.source_of("features.0"), Some(base));
+    assert_eq!(config.source_of("features.1"), Some(prod));
+    assert_eq!(config.obj().get_arr("servers")?.len(), 3);
+
+    // Values may not change type.
+    layered.add_obj("bad", obj! { "db" => obj! { "port" => "5432" } });
+    assert_eq!(
+        layered.load().unwrap_err(),
+        OverError::FieldTypeMismatch("db.port".into(), Type::Int, Type::Str)
+    );
+
+    Ok(())
+}
+
+#[test]
+fn args() -> OverResult<()> {
+    let obj = obj! {
+        "server" => obj! { "host" => "localhost", "port" => 8080 },
+        "features" => arr!["auth"],
+    };
+
+    let obj = args::apply_args(
+        &obj,
+        &[
+            "server.port=9000",
+            "features=[\"a\" \"b\"]",
+            "server.tls={ cert: \"server.pem\" }",
+            "server.port=9001 # Comments are allowed.",
+        ],
+    )?;
+    assert_eq!(
+        obj,
+        obj! {
+            "server" => obj! {
+                "host" => "localhost",
+                "port" => 9001,
+                "tls" => obj! { "cert" => "server.pem" },
+            },
+            "features" => arr!["a", "b"],
+        }
+    );
+
+    assert_eq!(
+        args::parse_arg("ratio=1/2+1")?,
+        ("ratio".into(), frac!(3, 2).into())
+    );
+    assert_eq!(Value::from_str(" (1 \"a\") ")?, tup!(1, "a"));
+
+    let check = |arg: &str, msg: &str| {
+        assert_eq!(
+            format!("{}", args::apply_args(&obj, &[arg]).unwrap_err()),
+            msg
+        );
+    };
+    check(
+        "server.port",
+        "Invalid argument \"server.port\": expected path=value",
+    );
+    check(
+        "server.port=\"9000\"",
+        "Invalid argument \"server.port=\"9000\"\": Type mismatch for field \"server.port\": \
+         expected Int, found Str",
+    );
+    check(
+        "server.port=9000 host: 1",
+        "Invalid argument \"server.port=9000 host: 1\": Invalid character 'h' for value at \
+         line 1, column 6",
+    );
+    check(
+        "server.port=",
+        "Invalid argument \"server.port=\": Unexpected end at line 1",
+    );
+    check(
+        "client.port=1",
+        "Invalid argument \"client.port=1\": Field not found: \"client\"",
+    );
+
+    Ok(())
+}
+
 #[test]
 fn any_type() -> OverResult<()> {
     let obj = Obj::from_file("tests/test_files/any_type.over")?;
@@ -335,6 +1337,468 @@ fn includes() -> OverResult<()> {
 
 // TODO: Test multi-line.over (need substitution)
 
+// Test reloading files when they or their includes change.
+#[test]
+fn watcher() -> OverResult<()> {
+    let watch_dir = std::env::temp_dir().join(format!("over_watcher_{}", std::process::id()));
+    let path = |name: &str| watch_dir.join(name).to_str().unwrap().to_string();
+    let main_path = path("main.over");
+    let inc_path = path("inc.over");
+    let bad_path = path("bad.over");
+    let other_path = path("other.over");
+
+    // Replace files atomically so that the watcher never sees them half-written.
+    let write = |path: &str, contents: &str| {
+        let tmp_path = format!("{}.tmp", path);
+        std::fs::write(&tmp_path, contents).unwrap();
+        std::fs::rename(&tmp_path, path).unwrap();
+    };
+
+    let _ = std::fs::remove_dir_all(&watch_dir);
+    std::fs::create_dir(&watch_dir).unwrap();
+    write(&main_path, "a: 1 inc: <\"inc.over\">");
+    write(&inc_path, "b: 2");
+
+    let (obj, deps) = Obj::from_file_with_deps(&main_path)?;
+    assert_eq!(obj.get_obj("inc")?.get("b").unwrap(), 2);
+    assert_eq!(deps.len(), 2);
+    assert!(deps[0].ends_with("inc.over"));
+    assert!(deps[1].ends_with("main.over"));
+
+    // Edits the include once while the file is being parsed.
+    let mut ctx = ParseContext::new();
+    let edited = Arc::new(AtomicBool::new(false));
+    let edit_path = inc_path.clone();
+    ctx.set_function("edit_inc", move |_: &[Value]| {
+        if !edited.swap(true, Ordering::SeqCst) {
+            write(&edit_path, "b: 4000");
+        }
+        Ok(Value::Null)
+    })?;
+
+    let (watcher, receiver) = Watcher::with_channel(&main_path, ctx, Duration::from_millis(10))?;
+    let timeout = Duration::from_secs(10);
+    assert_eq!(watcher.current(), obj);
+
+    // Changing an include reloads the file.
+    write(&inc_path, "b: 300");
+    let obj = receiver.recv_timeout(timeout).unwrap()?;
+    assert_eq!(obj.get_obj("inc")?.get("b").unwrap(), 300);
+
+    // Errors are delivered, and the last good Obj is kept.
+    write(&main_path, "a: ");
+    assert!(receiver.recv_timeout(timeout).unwrap().is_err());
+    assert_eq!(watcher.current(), obj);
+
+    write(&main_path, "a: 100 inc: <\"inc.over\">");
+    let obj = receiver.recv_timeout(timeout).unwrap()?;
+    assert_eq!(obj.get("a").unwrap(), 100);
+    assert_eq!(watcher.current(), obj);
+
+    // Files reached by a failed reload are watched too.
+    write(&bad_path, "c: ");
+    write(&main_path, "a: 100 inc: <\"inc.over\"> bad: <\"bad.over\">");
+    assert!(receiver.recv_timeout(timeout).unwrap().is_err());
+    assert_eq!(watcher.current(), obj);
+
+    write(&bad_path, "c: 3");
+    let obj = receiver.recv_timeout(timeout).unwrap()?;
+    assert_eq!(obj.get_obj("bad")?.get("c").unwrap(), 3);
+    assert_eq!(watcher.current(), obj);
+
+    // A watched file changed while parsing is reloaded again, even if the reload added new files.
+    write(&other_path, "d: 4");
+    write(
+        &main_path,
+        "a: 100 inc: <\"inc.over\"> other: <\"other.over\"> edited: edit_inc()",
+    );
+    let obj = receiver.recv_timeout(timeout).unwrap()?;
+    assert_eq!(obj.get_obj("inc")?.get("b").unwrap(), 300);
+    let obj = receiver.recv_timeout(timeout).unwrap()?;
+    assert_eq!(obj.get_obj("inc")?.get("b").unwrap(), 4000);
+
+    drop(watcher);
+    std::fs::remove_dir_all(&watch_dir).unwrap();
+
+    Ok(())
+}
+
+// Test reusing includes between loads.
+#[test]
+fn loader() -> OverResult<()> {
+    let loader_dir = std::env::temp_dir().join(format!("over_loader_{}", std::process::id()));
+    let write = |name: &str, contents: &str| {
+        let path = loader_dir.join(name).to_str().unwrap().to_string();
+        let tmp_path = format!("{}.tmp", path);
+        std::fs::write(&tmp_path, contents).unwrap();
+        std::fs::rename(&tmp_path, &path).unwrap();
+        path
+    };
+
+    let _ = std::fs::remove_dir_all(&loader_dir);
+    std::fs::create_dir(&loader_dir).unwrap();
+    let shared = write("shared.over", "[1 2] <Arr \"nested.over\">");
+    write("nested.over", "3");
+    let a = write("a.over", "name: \"a\" values: <Arr \"shared.over\">");
+    let b = write("b.over", "name: \"b\" values: <Arr \"shared.over\">");
+
+    let loader = Arc::new(Loader::new());
+    let values = |obj: &Obj| obj.get_arr("values").unwrap();
+
+    let obj_a = loader.load_file(&a)?;
+    let obj_b = loader.load_file(&b)?;
+    assert_eq!(values(&obj_a), arr![arr![1, 2], arr![3]]);
+    assert!(values(&obj_a).ptr_eq(&values(&obj_b)));
+    assert_eq!(loader.cached_len(), 2);
+
+    // Changing a nested include reparses every include depending on it.
+    write("nested.over", "3 4");
+    let obj_a = loader.load_file(&a)?;
+    assert_eq!(values(&obj_a), arr![arr![1, 2], arr![3, 4]]);
+    assert!(values(&obj_a).ptr_eq(&values(&loader.load_file(&b)?)));
+
+    // Invalidating an include removes it and every include depending on it.
+    assert_eq!(loader.invalidate(&shared), 1);
+    assert!(!values(&obj_a).ptr_eq(&values(&loader.load_file(&a)?)));
+    assert_eq!(
+        loader.invalidate(loader_dir.join("nested.over").to_str().unwrap()),
+        2
+    );
+    assert_eq!(loader.cached_len(), 0);
+
+    // The loader can be shared between threads.
+    let obj_a = loader.load_file(&a)?;
+    let handles: Vec<_> = (0..4)
+        .map(|_| {
+            let loader = loader.clone();
+            let b = b.clone();
+            thread::spawn(move || loader.load_file(&b).unwrap())
+        })
+        .collect();
+    for handle in handles {
+        assert!(values(&handle.join().unwrap()).ptr_eq(&values(&obj_a)));
+    }
+
+    loader.clear();
+    assert_eq!(loader.cached_len(), 0);
+
+    std::fs::remove_dir_all(&loader_dir).unwrap();
+
+    Ok(())
+}
+
+// Test that parsing includes in parallel gives the same results and errors as parsing them
+// sequentially.
+#[cfg(feature = "parallel")]
+#[test]
+fn parallel_includes() -> OverResult<()> {
+    let mut ctx = ParseContext::new();
+    ctx.set_options(ParseOptions {
+        parallel_includes: true,
+        ..Default::default()
+    });
+
+    let obj = Obj::from_file_with_context("tests/test_files/parallel/main.over", &ctx)?;
+    assert_eq!(
+        obj,
+        obj! {
+            "a" => obj! { "x" => 1, "nested" => arr![1, 2, 3] },
+            "nums" => arr![1, 2, 3],
+            "both" => tup!(obj! { "x" => 1, "nested" => arr![1, 2, 3] }, tup!("t", 1)),
+        }
+    );
+    // Each file is only included once.
+    assert!(obj
+        .get_obj("a")?
+        .ptr_eq(&obj.get_tup("both")?.get(0)?.get_obj()?));
+
+    let mut paths = vec![];
+    for dir in &[
+        "tests/test_files",
+        "tests/test_files/errors",
+        "tests/test_files/parallel",
+    ] {
+        for entry in std::fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension() == Some("over".as_ref()) {
+                paths.push(path.to_str().unwrap().to_string());
+            }
+        }
+    }
+
+    for path in &paths {
+        assert_eq!(
+            Obj::from_file(path),
+            Obj::from_file_with_context(path, &ctx),
+            "{}",
+            path
+        );
+    }
+
+    // The first error in the file is reported, regardless of which include fails first.
+    let err =
+        Obj::from_file_with_context("tests/test_files/parallel/errors.over", &ctx).unwrap_err();
+    assert!(format!("{}", err).contains("bad1.over"));
+    let err =
+        Obj::from_file_with_context("tests/test_files/parallel/cycle.over", &ctx).unwrap_err();
+    assert!(format!("{}", err).contains("Tried to cyclically include file \"cycle.over\""));
+
+    Ok(())
+}
+
+// Test editing a document while preserving its layout.
+#[test]
+fn document() -> OverResult<()> {
+    let path = "tests/test_files/document.over";
+    let original = std::fs::read_to_string(path).unwrap();
+    let mut doc = Document::from_file(path)?;
+
+    assert_eq!(doc.text(), original);
+    assert_eq!(doc.to_string(), original);
+    assert_eq!(doc.to_obj()?, Obj::from_file(path)?);
+
+    let names: Vec<&str> = doc.fields().iter().map(|field| field.name()).collect();
+    assert_eq!(names, vec!["@port", "name", "notes", "server", "debug"]);
+    assert!(doc.fields()[0].is_global());
+    assert_eq!(doc.get_text("notes"), Some("<Str \"includes/str.over\">"));
+    assert_eq!(doc.get_text("server.port"), Some("@port+80"));
+    assert_eq!(doc.get_text("server.limits.2.0"), Some("\"a\""));
+    assert!(matches!(
+        doc.get("server.limits").unwrap().kind(),
+        NodeKind::Tup(_)
+    ));
+    assert!(doc.get("server.port.0").is_none());
+    assert!(doc.get("server.limits.3").is_none());
+
+    doc.set("server.port", 9000)?;
+    doc.set("server.limits.1", 30)?;
+    doc.set_raw("server.host", "\"0.0.0.0\"")?;
+    doc.set("server.tls.enabled", true)?;
+    doc.set("server.workers", 4)?;
+    doc.set("server.extra", obj! {"a" => 1})?;
+    doc.remove("server.limits.0")?;
+    doc.remove("debug")?;
+    doc.set("version", arr![1, 2])?;
+
+    assert_eq!(
+        doc.text(),
+        "# Settings for the server.
+
+@port: 8000 # Default port.
+
+name: \"server\"
+notes: <Str \"includes/str.over\">
+
+server: {
+    host: \"0.0.0.0\"   # Only local for now.
+    port: 9000
+    limits: (30 (\"a\" 1))
+    tls: { enabled: true }
+    workers: 4
+    extra: {
+        a: 1
+    }
+}
+version: [
+    1
+    2
+]
+
+# Unused.
+"
+    );
+
+    let obj = doc.to_obj()?;
+    assert_eq!(obj.get("notes"), Obj::from_file(path)?.get("notes"));
+    assert_eq!(obj.get_obj("server")?.get_int("port")?, BigInt::from(9000));
+    assert_eq!(
+        obj.get_obj("server")?.get_obj("tls")?,
+        obj! {"enabled" => true}
+    );
+    assert_eq!(obj.get_arr("version")?, arr![1, 2]);
+    assert!(!obj.contains("debug"));
+
+    // Failed edits leave the document unchanged.
+    let text = doc.text().to_string();
+    assert!(doc.set_raw("name", "1 2").is_err());
+    assert!(doc.set_raw("name", "{").is_err());
+    assert_eq!(
+        doc.remove("missing"),
+        Err(OverError::FieldNotFound("missing".into()))
+    );
+    assert_eq!(
+        doc.set("name.x", 1),
+        Err(OverError::InvalidPath("name.x".into()))
+    );
+    assert_eq!(
+        doc.set("server..x", 1),
+        Err(OverError::InvalidPath("server..x".into()))
+    );
+    assert_eq!(
+        doc.set("bad-name", 1),
+        Err(OverError::InvalidFieldName("bad-name".into()))
+    );
+    assert_eq!(doc.text(), text);
+
+    // Fields on the same line.
+    let mut doc = Document::from_str("a: 1 b: (1 2) # Comment.\nc: {}")?;
+    doc.remove("a")?;
+    doc.set("c.x", "y")?;
+    doc.set("d", 2)?;
+    doc.remove("b.0")?;
+    assert_eq!(doc.text(), "b: (2) # Comment.\nc: { x: \"y\" }\nd: 2");
+
+    // Removing the last field on a line leaves no trailing whitespace.
+    let mut doc = Document::from_str("a: 1 b: 2\nc: (1 2)  \nd: 3 e: 4")?;
+    doc.remove("b")?;
+    doc.remove("c.1")?;
+    doc.remove("e")?;
+    assert_eq!(doc.text(), "a: 1\nc: (1)  \nd: 3");
+    let mut doc = Document::from_str("x: { a: 1 b: 2 }")?;
+    doc.remove("x.b")?;
+    assert_eq!(doc.text(), "x: { a: 1 }");
+
+    let mut doc = Document::from_str("")?;
+    doc.set("a", 1)?;
+    doc.set("b", 2)?;
+    assert_eq!(doc.text(), "a: 1\nb: 2\n");
+
+    assert_eq!(
+        Document::from_str("a: {\nb: 1").unwrap_err(),
+        OverError::ParseError("Unexpected end at line 2".into())
+    );
+    assert_eq!(
+        Document::from_str("a: 1\n b: )").unwrap_err(),
+        OverError::ParseError("Invalid character ')' for value at line 2, column 5".into())
+    );
+
+    Ok(())
+}
+
+// Test warnings for suspicious files.
+#[test]
+fn lint() -> OverResult<()> {
+    let path = "tests/test_files/lint.over";
+    let warnings = lint::lint_file(path, LintOptions::default())?;
+
+    let found: Vec<(Rule, usize, usize)> = warnings
+        .iter()
+        .map(|warning| (warning.rule, warning.line, warning.col))
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            (Rule::UnusedGlobal, 3, 1),
+            (Rule::UnusedInclude, 5, 10),
+            (Rule::ReservedName, 6, 1),
+            (Rule::ParentTypeMismatch, 15, 5),
+            (Rule::CommaDecimal, 20, 9),
+            (Rule::CommaDecimal, 21, 14),
+            (Rule::ReservedName, 22, 1),
+            (Rule::ReservedName, 23, 1),
+        ]
+    );
+    assert_eq!(
+        warnings[3].to_string(),
+        "Field \"a\" has type Str but overrides a field of type Int in its parent at line 15, \
+         column 5"
+    );
+    assert_eq!(
+        warnings[7].to_string(),
+        "Field \"obj\" looks like the reserved word \"Obj\" at line 23, column 1"
+    );
+    let text = std::fs::read_to_string(path).unwrap();
+    assert_eq!(&text[warnings[1].span.clone()], "<\"includes/obj3.over\">");
+    assert_eq!(&text[warnings[4].span.clone()], ",");
+
+    // Each rule can be turned off.
+    for rule in Rule::ALL.iter().copied() {
+        let mut options = LintOptions::default();
+        options.set(rule, false);
+        assert!(!options.is_enabled(rule));
+
+        let warnings = lint::lint_file(path, options)?;
+        assert!(warnings.iter().all(|warning| warning.rule != rule));
+        assert!(!warnings.is_empty());
+        assert_eq!(Rule::from_name(rule.name()), Some(rule));
+    }
+    assert_eq!(lint::lint_file(path, LintOptions::none())?, vec![]);
+
+    // Referenced globals and includes, and commas in strings, are fine.
+    let text = "@a: 1 @b: <Obj \"tests/test_files/includes/obj3.over\">
+                c: @a+@b.test d: \"1,5\" e1: 2 f: e1";
+    assert_eq!(lint::lint_str(text, LintOptions::default())?, vec![]);
+
+    // Parents in arrays, and inherited fields with the same type.
+    let text = "@p: { a: 1 b: [] } c: [{ ^: @p a: 2 b: [1] } { ^: @p a: 2,5 }]";
+    let warnings = lint::lint_str(text, LintOptions::default())?;
+    let rules: Vec<Rule> = warnings.iter().map(|warning| warning.rule).collect();
+    assert_eq!(rules, vec![Rule::ParentTypeMismatch, Rule::CommaDecimal]);
+
+    // With shared ancestors, the overridden field is the one `get` finds: `b` comes before `d`.
+    let text = "@d: { a: 1 } @b: { ^: @d } @c: { ^: @d a: \"c\" }
+                x: { ^: [@b @c] a: \"x\" } y: { ^: [@b @c] a: 2 }";
+    let warnings = lint::lint_str(text, LintOptions::default())?;
+    let found: Vec<(Rule, usize, usize)> = warnings
+        .iter()
+        .map(|warning| (warning.rule, warning.line, warning.col))
+        .collect();
+    assert_eq!(found, vec![(Rule::ParentTypeMismatch, 2, 58)]);
+
+    assert!(lint::lint_str("a: b", LintOptions::default()).is_err());
+
+    Ok(())
+}
+
+// Test the lint command of the command-line tool.
+#[test]
+fn lint_cli() {
+    let run = |args: &[&str]| {
+        let output = std::process::Command::new(env!("CARGO_BIN_EXE_over"))
+            .args(args)
+            .output()
+            .unwrap();
+        (
+            output.status.code(),
+            String::from_utf8(output.stdout).unwrap(),
+            String::from_utf8(output.stderr).unwrap(),
+        )
+    };
+
+    let (code, stdout, _) = run(&["lint", "tests/test_files/lint.over"]);
+    assert_eq!(code, Some(1));
+    assert_eq!(stdout.lines().count(), 8);
+    assert!(stdout.starts_with(
+        "tests/test_files/lint.over: warning: Global \"@unused\" is never used at line 3, column \
+         1 [unused-global]\n"
+    ));
+
+    let (code, stdout, _) = run(&[
+        "lint",
+        "--allow",
+        "reserved-name",
+        "-A",
+        "comma-decimal",
+        "tests/test_files/lint.over",
+    ]);
+    assert_eq!(code, Some(1));
+    assert_eq!(stdout.lines().count(), 3);
+
+    let (code, stdout, _) = run(&["lint", "tests/test_files/basic.over"]);
+    assert_eq!((code, stdout.as_str()), (Some(0), ""));
+
+    let (code, _, stderr) = run(&["lint", "tests/test_files/errors/fuzz1.over"]);
+    assert_eq!(code, Some(2));
+    assert!(stderr.starts_with("error: tests/test_files/errors/fuzz1.over: "));
+
+    let (code, _, stderr) = run(&["lint", "--allow", "unknown", "tests/test_files/lint.over"]);
+    assert_eq!(code, Some(2));
+    assert!(stderr.starts_with("Unknown rule \"unknown\""));
+
+    let (code, _, _) = run(&["frobnicate"]);
+    assert_eq!(code, Some(2));
+}
+
 // Test writing objects to files.
 #[test]
 fn write() -> OverResult<()> {